@@ -1,9 +1,54 @@
+use std::path::Path;
+
+use anyhow::{anyhow, Context};
 use rand::thread_rng;
 use rsa::{Pkcs1v15Encrypt, RsaPrivateKey};
 use serde::{Deserialize, Serialize};
 use ssh_key::{Fingerprint, PrivateKey};
 use tokio::fs;
 
+use crate::config::Config;
+
+/// Creates an HTTP client with an authenticated session for the user in the config.
+pub async fn login(config: &Config) -> anyhow::Result<reqwest::Client> {
+    let mut client = reqwest::ClientBuilder::new()
+        // Sessions are stored in the cookie jar.
+        .cookie_store(true)
+        // Sessions rely on HTTPS being active.
+        .https_only({
+            #[cfg(debug_assertions)]
+            let x = false;
+            #[cfg(not(debug_assertions))]
+            let x = true;
+            x
+        })
+        .build()
+        .context("could not build http client")?;
+
+    let priv_key = match &config.ssh_key {
+        // If the user has configured a path to an SSH key, use that.
+        Some(ssh_key_path) => {
+            let priv_key_data = fs::read_to_string(Path::new(ssh_key_path))
+                .await
+                .context("could not read ssh key file")?;
+            PrivateKey::from_openssh(priv_key_data)
+                .context("could not parse openssh private key")?
+        }
+        // Otherwise, try to find the private key.
+        None => match get_private_key().await {
+            Some(priv_key) => priv_key,
+            None => {
+                return Err(anyhow!("could not find private ssh key"));
+            }
+        },
+    };
+
+    create_session(&mut client, &config.host, &config.username, priv_key)
+        .await
+        .context("could not create session")?;
+    Ok(client)
+}
+
 /// Walks through the user's `~/.ssh` folder and returns the first private key it finds.
 pub async fn get_private_key() -> Option<PrivateKey> {
     let ssh_dir = homedir::get_my_home().ok()??.join(".ssh/");
@@ -0,0 +1,30 @@
+use anyhow::Context;
+use arboard::Clipboard;
+
+/// Copies text to the clipboard.
+///
+/// On linux, the clipboard is owned by the application that set it. This function will therefore
+/// block until the clipboard is overwritten by another application.
+pub fn copy(text: String) -> anyhow::Result<()> {
+    let mut clipboard = Clipboard::new().context("failed to get clipboard")?;
+    #[allow(unused_mut)]
+    let mut set = clipboard.set();
+    #[cfg(target_os = "linux")]
+    {
+        use arboard::SetExtLinux;
+        println!(
+            "Detected you are on linux. This application will keep running to persist the clipboard."
+        );
+        set = set.wait();
+    }
+    set.text(text).context("could not write to clipboard")?;
+    Ok(())
+}
+
+/// Reads text from the clipboard.
+pub fn read() -> anyhow::Result<String> {
+    Clipboard::new()
+        .context("failed to get clipboard")?
+        .get_text()
+        .context("could not read text from clipboard")
+}
@@ -0,0 +1,84 @@
+use std::{
+    io::{self, IsTerminal, Read},
+    time::Duration,
+};
+
+use anyhow::{anyhow, Context};
+use clap::Args;
+use reqwest::StatusCode;
+use serde::Serialize;
+
+use crate::{auth, clipboard, config::Config};
+
+/// Share a snippet of text.
+#[derive(Args)]
+pub struct PasteArgs {
+    /// The language of the text, used for syntax highlighting. Either the name of the language or
+    /// a file extension, such as `rust` or `rs`.
+    #[arg(short, long)]
+    language: Option<String>,
+    /// How long the paste should remain available, for example `30m`, `12h` or `7d`.
+    ///
+    /// If unset, the paste will not expire.
+    #[arg(short, long, value_parser = humantime::parse_duration)]
+    expires_in: Option<Duration>,
+    /// Read the text from the clipboard instead of from stdin.
+    ///
+    /// The clipboard is also used when stdin is not redirected.
+    #[arg(short, long)]
+    clipboard: bool,
+}
+
+#[derive(Serialize)]
+struct PastePayload {
+    content: String,
+    language: Option<String>,
+    expires_in: Option<u32>,
+}
+
+/// Uploads a snippet of text read from stdin or the clipboard.
+pub async fn paste(config: Config, args: PasteArgs) -> anyhow::Result<()> {
+    let expires_in = args
+        .expires_in
+        .map(|v| u32::try_from(v.as_secs()))
+        .transpose()
+        .context("expiry duration is too long")?;
+
+    let content = if args.clipboard || io::stdin().is_terminal() {
+        clipboard::read()?
+    } else {
+        let mut content = String::new();
+        io::stdin()
+            .read_to_string(&mut content)
+            .context("could not read from stdin")?;
+        content
+    };
+
+    if content.is_empty() {
+        return Err(anyhow!("there is no text to share"));
+    }
+
+    let client = auth::login(&config).await?;
+
+    let resp = client
+        .post(format!("{}/api/pastes", config.host))
+        .json(&PastePayload {
+            content,
+            language: args.language,
+            expires_in,
+        })
+        .send()
+        .await
+        .context("could not make request")?;
+    if resp.status() != StatusCode::OK {
+        return Err(anyhow!(
+            "server returned with status code {}: {}",
+            resp.status(),
+            resp.text().await.context("could not read error response")?,
+        ));
+    }
+
+    let url = resp.text().await.context("could not read response")?;
+    println!("Use the following link to share it: {url} (copied to clipboard)");
+    clipboard::copy(url)
+}
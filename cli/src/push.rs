@@ -0,0 +1,213 @@
+use std::{
+    io,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use anyhow::{anyhow, Context};
+use clap::Args;
+use flate2::{write::GzEncoder, Compression};
+use indicatif::ProgressBar;
+use reqwest::{Body, StatusCode};
+use tokio::{
+    fs,
+    task::{self, JoinHandle},
+};
+use tokio_util::io::ReaderStream;
+
+use crate::{auth, byte_stream, clipboard, config::Config};
+
+/// Upload files and generate a link to share them.
+#[derive(Args)]
+pub struct PushArgs {
+    /// One or more files to share.
+    ///
+    /// When multiple files are provided, they will be combined into an archive. When a single
+    /// directory is provided, its contents will be combined into an archive.
+    files: Vec<PathBuf>,
+    /// Create a short link for the upload, such as `/s/my-build`.
+    ///
+    /// Use `--slug=<SLUG>` to pick the slug yourself, or only `--slug` to get a random one.
+    #[arg(long, num_args = 0..=1, require_equals = true, default_missing_value = "")]
+    slug: Option<String>,
+    /// Replace the content of an earlier upload instead of creating a new one.
+    ///
+    /// Accepts either the ID of the file or its share link. The share link stays the same and
+    /// keeps serving the latest content; the previous content is kept as an older version.
+    #[arg(long, value_name = "ID", conflicts_with = "slug")]
+    replace: Option<String>,
+    /// Put the upload in a folder, such as `builds/nightly`. Missing folders are created.
+    #[arg(long, value_name = "PATH", conflicts_with = "replace")]
+    folder: Option<String>,
+    /// Add a tag to the upload. Can be used multiple times.
+    #[arg(long = "tag", value_name = "TAG", conflicts_with = "replace")]
+    tags: Vec<String>,
+    /// Upload the file to a team, using the ID of the team. All members of the team can manage it.
+    #[arg(long, value_name = "TEAM_ID", conflicts_with = "replace")]
+    team: Option<String>,
+}
+
+/// Uploads one or more files, combining them into an archive if needed.
+pub async fn push(config: Config, args: PushArgs) -> anyhow::Result<()> {
+    let cli_theme = dialoguer::theme::ColorfulTheme::default();
+
+    if args.files.is_empty() {
+        return Err(anyhow!("please provide at least one file to share"));
+    }
+
+    // Determine if we are uploading a single non-directory file. This is used to determine whether
+    // to archieve the input files or not.
+    let is_single_file = if args.files.len() == 1 {
+        let is_dir = match fs::metadata(&args.files[0])
+            .await
+            .context("trying to read file")?
+            .file_type()
+        {
+            e if e.is_dir() => true,
+            e if e.is_file() => false,
+            other => return Err(anyhow!("unsupported file type: {other:?}")),
+        };
+
+        !is_dir
+    } else {
+        false
+    };
+
+    // Determine the name of the file to be uploaded.
+    let mut file_name = if args.files.len() == 1 {
+        let mut file_name = args.files[0]
+            .file_name()
+            .and_then(|v| v.to_str())
+            .unwrap_or("file")
+            .to_string();
+        if !is_single_file {
+            file_name += ".tar.gz";
+        }
+        file_name
+    } else {
+        "files.tar.gz".to_string()
+    };
+
+    let replace_id = args.replace.as_deref().map(file_id_from_link);
+
+    // A replaced file keeps its name, so there is nothing to configure.
+    let default_settings = replace_id.is_some()
+        || dialoguer::Confirm::with_theme(&cli_theme)
+            .with_prompt("Do you want to use the default settings?")
+            .interact()?;
+
+    if !default_settings {
+        file_name = dialoguer::Input::with_theme(&cli_theme)
+            .with_prompt("What name should the uploaded file have?")
+            .default(file_name)
+            .interact_text()?;
+    }
+
+    let (mut writer, reader) = byte_stream::byte_stream(4096);
+
+    // Write all the files into a compressed tar archive.
+    let write_task = task::spawn_blocking(move || -> anyhow::Result<()> {
+        use std::fs;
+
+        if !is_single_file {
+            let enc = GzEncoder::new(writer, Compression::default());
+            let mut archive = tar::Builder::new(enc);
+
+            for file in args.files {
+                let is_dir = std::fs::metadata(&file)
+                    .context("could not get metadata for file")?
+                    .is_dir();
+
+                let file_name = Path::new(
+                    file.file_name()
+                        .and_then(|v| v.to_str())
+                        .unwrap_or("unnamed"),
+                );
+                if is_dir {
+                    archive
+                        .append_dir_all(file_name, file.clone())
+                        .context("could not add directory to archive")?;
+                } else {
+                    archive
+                        .append_path_with_name(file.clone(), file_name)
+                        .context("could not add directory to archive")?;
+                }
+            }
+            archive.into_inner()?.try_finish()?;
+        } else {
+            let mut reader =
+                fs::File::open(&args.files[0]).context("could not open file for reading")?;
+            io::copy(&mut reader, &mut writer)?;
+        }
+        Ok(())
+    });
+
+    let client = auth::login(&config).await?;
+
+    let progress = ProgressBar::new_spinner().with_message("Uploading file");
+    progress.enable_steady_tick(Duration::from_millis(100));
+
+    // Upload the file while it is being written.
+    let upload_task: JoinHandle<anyhow::Result<_>> = tokio::spawn(async move {
+        let mut req = match replace_id {
+            Some(file_id) => client.put(format!("{}/api/files/{file_id}", config.host)),
+            None => client
+                .post(format!("{}/api/files", config.host))
+                .header("file_name", file_name),
+        };
+        if let Some(slug) = args.slug {
+            req = req.header("slug", slug);
+        }
+        if let Some(folder) = args.folder {
+            req = req.header("folder", folder);
+        }
+        if !args.tags.is_empty() {
+            req = req.header("tags", args.tags.join(","));
+        }
+        if let Some(team) = args.team {
+            req = req.header("team", team);
+        }
+
+        let resp = req
+            .body(Body::wrap_stream(ReaderStream::new(reader)))
+            .send()
+            .await
+            .context("could make request")?;
+        if resp.status() != StatusCode::OK {
+            eprintln!("Server returned with status code {}", resp.status());
+            eprintln!(
+                "Error body: {}",
+                resp.text().await.context("could not error response")?,
+            );
+            return Ok(());
+        }
+        let url = resp.text().await.context("could not read response")?;
+        progress.finish_with_message("File has been uploaded!");
+        println!(
+            "Use the following link to share it: {} (copied to clipboard)",
+            &url
+        );
+
+        clipboard::copy(url)
+    });
+
+    write_task
+        .await
+        .context("writer task crashed")?
+        .context("while writing files")?;
+    upload_task
+        .await
+        .context("upload task crashed")?
+        .context("could not upload file")?;
+
+    Ok(())
+}
+
+/// Extracts the file ID from a share link such as `https://example.com/files/<id>/<name>`. Values
+/// that are not a share link are assumed to be a file ID already.
+fn file_id_from_link(link: &str) -> String {
+    link.split_once("/files/")
+        .and_then(|(_, rest)| rest.split('/').next())
+        .unwrap_or(link)
+        .to_string()
+}
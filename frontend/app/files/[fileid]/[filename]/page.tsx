@@ -3,7 +3,7 @@ import { buttonVariants } from "@/components/ui/button"
 import { serverFetch } from "@/lib/server-fetch"
 import { Download } from "lucide-react"
 import Link from "next/link"
-import { notFound } from "next/navigation"
+import { notFound, redirect } from "next/navigation"
 
 export default async function File({
   params,
@@ -23,6 +23,13 @@ export default async function File({
 
   const fileInfo = await res.json()
 
+  // Links using a previous name of a renamed file are sent to the current name.
+  if (fileInfo["file_name"] != decodeURIComponent(params.filename)) {
+    redirect(
+      `/files/${params.fileid}/${encodeURIComponent(fileInfo["file_name"])}`
+    )
+  }
+
   return (
     <main className="flex flex-col justify-center flex-1">
       <div className="flex items-center justify-center flex-col gap-4">
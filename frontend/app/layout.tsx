@@ -5,15 +5,19 @@ import { cn } from "@/lib/utils"
 import { ThemeProvider } from "@/components/theme-provider"
 import { MainNav } from "@/components/main-nav"
 import { Toaster } from "@/components/ui/sonner"
+import { getConfig } from "@/lib/config"
 
 const fontSans = localFont({
   src: "./fonts/inter.ttf",
   variable: "--font-sans",
 })
 
-export const metadata: Metadata = {
-  title: "Beacon",
-  description: "A file sharing service.",
+export async function generateMetadata(): Promise<Metadata> {
+  const config = await getConfig()
+  return {
+    title: config.instance_name,
+    description: "A file sharing service.",
+  }
 }
 
 export default function RootLayout({
@@ -0,0 +1,21 @@
+import { Dashboard } from "@/components/dashboard"
+import { SettingsForm } from "@/components/settings-form"
+import { getSettings } from "@/lib/config"
+
+export default async function Settings() {
+  const settings = await getSettings()
+
+  return (
+    <Dashboard.Page>
+      <Dashboard.Header>
+        <Dashboard.Title>Settings</Dashboard.Title>
+        <Dashboard.Subtext>
+          These settings take precedence over the configuration file of the
+          server. Settings that are left empty use the value of the
+          configuration file.
+        </Dashboard.Subtext>
+      </Dashboard.Header>
+      <SettingsForm settings={settings} />
+    </Dashboard.Page>
+  )
+}
@@ -1,16 +1,36 @@
+import Link from "next/link"
 import { redirect } from "next/navigation"
 import { serverFetch } from "@/lib/server-fetch"
 import { FileTable, columns } from "./file-table"
 import { getSession } from "@/lib/sessions"
 import { Dashboard } from "@/components/dashboard"
+import { Button } from "@/components/ui/button"
+import { Input } from "@/components/ui/input"
 
-export default async function Panel() {
+// Query parameters that are passed along to the file listing API.
+const LISTING_PARAMS = ["name", "sort", "order", "cursor"]
+
+export default async function Panel({
+  searchParams,
+}: {
+  searchParams: { [key: string]: string | string[] | undefined }
+}) {
   let session = getSession()
   if (!session) {
     redirect("/login")
   }
 
-  const resp = await serverFetch(`/api/users/${session?.uuid}/files`)
+  const query = new URLSearchParams()
+  for (const key of LISTING_PARAMS) {
+    const value = searchParams[key]
+    if (typeof value == "string" && value != "") {
+      query.set(key, value)
+    }
+  }
+
+  const resp = await serverFetch(
+    `/api/users/${session?.uuid}/files?${query.toString()}`
+  )
 
   if (resp.status == 401) {
     redirect("/login")
@@ -20,7 +40,8 @@ export default async function Panel() {
     throw new Error("Failed to fetch files")
   }
 
-  const files = (await resp.json()).map(
+  const page = await resp.json()
+  const files = page.files.map(
     (item: { file_id: string; upload_date: number }) => {
       return {
         ...item,
@@ -38,7 +59,33 @@ export default async function Panel() {
           the command line interface.
         </Dashboard.Subtext>
       </Dashboard.Header>
+      <form className="flex gap-2">
+        <Input
+          name="name"
+          placeholder="Search files, e.g. *.tar.gz"
+          defaultValue={query.get("name") ?? ""}
+        />
+        <Button type="submit" variant="secondary">
+          Search
+        </Button>
+      </form>
       <FileTable columns={columns} data={files} />
+      {page.next && (
+        <div className="flex justify-end">
+          <Button asChild variant="outline">
+            <Link
+              href={{
+                query: {
+                  ...Object.fromEntries(query),
+                  cursor: page.next,
+                },
+              }}
+            >
+              Next page
+            </Link>
+          </Button>
+        </div>
+      )}
     </Dashboard.Page>
   )
 }
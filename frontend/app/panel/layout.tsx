@@ -41,6 +41,7 @@ export default async function RootLayout({
             <NavGroup title="Admin">
               <NavItem href="/panel/admin/users" content="Users" />
               <NavItem href="/panel/admin/invites" content="Invites" />
+              <NavItem href="/panel/admin/settings" content="Settings" />
             </NavGroup>
           ) : (
             <></>
@@ -0,0 +1,39 @@
+import {
+  Card,
+  CardContent,
+  CardDescription,
+  CardHeader,
+  CardTitle,
+} from "@/components/ui/card"
+import { SetupForm } from "@/components/setup-form"
+import { serverFetch } from "@/lib/server-fetch"
+import { redirect } from "next/navigation"
+
+export default async function SetupPage() {
+  const resp = await serverFetch("/api/setup")
+  if (!resp.ok) {
+    throw new Error("Unable to fetch setup status")
+  }
+  const status: { required: boolean } = await resp.json()
+  if (!status.required) {
+    redirect("/login")
+  }
+
+  return (
+    <main className="flex flex-col justify-center flex-1">
+      <div className="flex items-center justify-center flex-col gap-4">
+        <Card className="max-w-lg">
+          <CardHeader>
+            <CardTitle className="pr-64">Welcome</CardTitle>
+            <CardDescription>
+              Please create the administrator account of this instance.
+            </CardDescription>
+          </CardHeader>
+          <CardContent>
+            <SetupForm />
+          </CardContent>
+        </Card>
+      </div>
+    </main>
+  )
+}
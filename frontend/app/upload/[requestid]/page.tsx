@@ -0,0 +1,68 @@
+import {
+  Card,
+  CardContent,
+  CardDescription,
+  CardHeader,
+  CardTitle,
+} from "@/components/ui/card"
+import { UploadRequestForm } from "@/components/upload-request-form"
+import { serverFetch } from "@/lib/server-fetch"
+import { notFound } from "next/navigation"
+
+export default async function UploadRequest({
+  params,
+}: {
+  params: { requestid: string }
+}) {
+  const res = await serverFetch(`/api/upload-requests/${params.requestid}`)
+  if (res.status == 404) {
+    return notFound()
+  }
+
+  if (res.status == 410) {
+    return (
+      <main className="flex flex-col justify-center flex-1">
+        <div className="flex items-center justify-center flex-col gap-4">
+          <Card>
+            <CardHeader>
+              <CardTitle>Upload link closed</CardTitle>
+              <CardDescription>
+                This link has expired or no more files can be uploaded.
+              </CardDescription>
+            </CardHeader>
+          </Card>
+        </div>
+      </main>
+    )
+  }
+
+  if (!res.ok) {
+    throw new Error("Failed to fetch data")
+  }
+
+  const request = await res.json()
+
+  return (
+    <main className="flex flex-col justify-center flex-1">
+      <div className="flex items-center justify-center flex-col gap-4">
+        <Card>
+          <CardHeader>
+            <CardTitle className="pr-48">
+              Upload files for {request["owner_name"]}
+            </CardTitle>
+            <CardDescription>
+              {request["description"] ?? "No account is needed to upload."}
+            </CardDescription>
+          </CardHeader>
+          <CardContent>
+            <UploadRequestForm
+              requestId={params.requestid}
+              hasPassword={request["has_password"]}
+              maxFileSize={request["max_file_size"]}
+            />
+          </CardContent>
+        </Card>
+      </div>
+    </main>
+  )
+}
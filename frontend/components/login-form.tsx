@@ -25,6 +25,15 @@ const formSchema = z.object({
   password: z.string().max(80, {
     message: "Password must be at most 80 characters",
   }),
+  new_password: z
+    .string()
+    .min(8, {
+      message: "Password must be at least 8 characters",
+    })
+    .max(80, {
+      message: "Password must be at most 80 characters",
+    })
+    .optional(),
 })
 
 type LoginState =
@@ -35,6 +44,8 @@ type LoginState =
 export function LoginForm() {
   const router = useRouter()
   const [loginState, setLoginState] = useState<LoginState>({ type: "idle" })
+  // Set when the server requires the user to choose a new password before logging in.
+  const [mustChangePassword, setMustChangePassword] = useState(false)
 
   const form = useForm<z.infer<typeof formSchema>>({
     resolver: zodResolver(formSchema),
@@ -57,6 +68,17 @@ export function LoginForm() {
       return
     }
 
+    if (resp.status == 429) {
+      setLoginState({ type: "error", message: await resp.text() })
+      return
+    }
+
+    if (resp.status == 403 || resp.status == 400) {
+      setMustChangePassword(true)
+      setLoginState({ type: "error", message: await resp.text() })
+      return
+    }
+
     if (!resp.ok) {
       setLoginState({ type: "error", message: "An unknown error occurred." })
       throw new Error("An error occurred while trying to authenticate")
@@ -108,6 +130,28 @@ export function LoginForm() {
             </FormItem>
           )}
         />
+        {mustChangePassword ? (
+          <FormField
+            control={form.control}
+            name="new_password"
+            render={({ field }) => (
+              <FormItem>
+                <FormLabel>New Password</FormLabel>
+                <FormControl>
+                  <Input
+                    autoComplete="new-password"
+                    type="password"
+                    placeholder="your new password"
+                    {...field}
+                  />
+                </FormControl>
+                <FormMessage />
+              </FormItem>
+            )}
+          />
+        ) : (
+          <></>
+        )}
         {(() => {
           // Display an error message if the login failed.
           if (loginState.type == "error") {
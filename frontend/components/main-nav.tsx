@@ -40,12 +40,13 @@ export async function UserNav(props: VariantProps<typeof buttonVariants>) {
   )
 }
 
-export function MainNav() {
+export async function MainNav() {
+  const config = await getConfig()
   return (
     <header className="sticky top-0 border-b border-border/50 z-10 py-2">
       <div className="container flex flex-row items-center bg-background">
         <Link href="/" className="text-2xl font-semibold pl-8 pr-8">
-          Beacon
+          {config.instance_name}
         </Link>
         <div className="flex flex-row w-full pr-8 justify-end self-stretch items-center gap-2">
           <UserNav variant="outline" />
@@ -99,7 +99,7 @@ export function RegisterForm(props: { require_invite_code?: boolean }) {
       body: JSON.stringify(values),
     })
 
-    if (resp.status == 403) {
+    if (resp.status == 403 || resp.status == 429) {
       setRegisterState({
         type: "error",
         message: await resp.text(),
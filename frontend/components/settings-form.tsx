@@ -0,0 +1,320 @@
+"use client"
+
+import { z } from "zod"
+import { Button } from "@/components/ui/button"
+import {
+  Form,
+  FormControl,
+  FormDescription,
+  FormField,
+  FormItem,
+  FormLabel,
+  FormMessage,
+} from "@/components/ui/form"
+import { Input } from "@/components/ui/input"
+import {
+  Select,
+  SelectContent,
+  SelectItem,
+  SelectTrigger,
+  SelectValue,
+} from "@/components/ui/select"
+import { zodResolver } from "@hookform/resolvers/zod"
+import { useForm } from "react-hook-form"
+import { toast } from "sonner"
+import { useRouter } from "next/navigation"
+import { useState } from "react"
+import { LoaderCircle } from "lucide-react"
+import type { Settings } from "@/lib/config"
+
+const MEGABYTE = 1024 * 1024
+
+// Empty values use the value of the configuration file.
+const optionalNumber = z
+  .string()
+  .regex(/^\d*$/, { message: "Must be a whole number." })
+
+const formSchema = z.object({
+  instance_name: z.string().max(64),
+  allow_registering: z.enum(["default", "true", "false"]),
+  disable_invite_codes: z.enum(["default", "true", "false"]),
+  default_file_expiry: z.string(),
+  max_file_size: optionalNumber,
+  user_quota: optionalNumber,
+  max_slugs_per_user: optionalNumber,
+})
+
+type FormValues = z.infer<typeof formSchema>
+
+type FormState =
+  | { type: "idle" }
+  | { type: "submitting" }
+  | { type: "error"; message: string }
+
+function toBool(value: "default" | "true" | "false"): boolean | undefined {
+  return value == "default" ? undefined : value == "true"
+}
+
+function fromBool(value?: boolean): "default" | "true" | "false" {
+  return value === undefined ? "default" : value ? "true" : "false"
+}
+
+function toNumber(value: string, scale = 1): number | undefined {
+  return value === "" ? undefined : Number(value) * scale
+}
+
+function fromNumber(value?: number, scale = 1): string {
+  return value === undefined ? "" : (value / scale).toString()
+}
+
+export function SettingsForm(props: { settings: Settings }) {
+  const form = useForm<FormValues>({
+    resolver: zodResolver(formSchema),
+    defaultValues: {
+      instance_name: props.settings.instance_name ?? "",
+      allow_registering: fromBool(props.settings.allow_registering),
+      disable_invite_codes: fromBool(props.settings.disable_invite_codes),
+      default_file_expiry:
+        props.settings.default_file_expiry?.toString() ?? "default",
+      max_file_size: fromNumber(props.settings.max_file_size, MEGABYTE),
+      user_quota: fromNumber(props.settings.user_quota, MEGABYTE),
+      max_slugs_per_user: fromNumber(props.settings.max_slugs_per_user),
+    },
+  })
+  const [formState, setFormState] = useState<FormState>({
+    type: "idle",
+  })
+
+  const router = useRouter()
+
+  async function onSubmit(values: FormValues) {
+    setFormState({ type: "submitting" })
+
+    const settings: Settings = {
+      instance_name: values.instance_name.trim() || undefined,
+      allow_registering: toBool(values.allow_registering),
+      disable_invite_codes: toBool(values.disable_invite_codes),
+      default_file_expiry:
+        values.default_file_expiry == "default"
+          ? undefined
+          : Number(values.default_file_expiry),
+      max_file_size: toNumber(values.max_file_size, MEGABYTE),
+      user_quota: toNumber(values.user_quota, MEGABYTE),
+      max_slugs_per_user: toNumber(values.max_slugs_per_user),
+    }
+
+    const resp = await fetch(`/api/config`, {
+      method: "PUT",
+      headers: {
+        "Content-Type": "application/json",
+      },
+      body: JSON.stringify(settings),
+    })
+
+    if (resp.status == 403) {
+      setFormState({
+        type: "error",
+        message: "You are not authorized to perform this action.",
+      })
+      return
+    }
+
+    if (resp.status == 400) {
+      setFormState({ type: "error", message: await resp.text() })
+      return
+    }
+
+    if (!resp.ok) {
+      setFormState({ type: "error", message: "An unknown error occurred." })
+      throw new Error("An error occurred while trying to change the settings")
+    }
+
+    setFormState({ type: "idle" })
+    toast("Settings have been saved")
+    router.refresh()
+  }
+
+  return (
+    <Form {...form}>
+      <form
+        onSubmit={form.handleSubmit(onSubmit)}
+        className="space-y-8 max-w-xl"
+      >
+        <FormField
+          control={form.control}
+          name="instance_name"
+          render={({ field }) => (
+            <FormItem>
+              <FormLabel>Instance Name</FormLabel>
+              <FormControl>
+                <Input placeholder="Beacon" {...field} />
+              </FormControl>
+              <FormDescription>
+                The name shown at the top of every page.
+              </FormDescription>
+              <FormMessage />
+            </FormItem>
+          )}
+        />
+        <FormField
+          control={form.control}
+          name="allow_registering"
+          render={({ field }) => (
+            <FormItem>
+              <FormLabel>Registration</FormLabel>
+              <Select onValueChange={field.onChange} defaultValue={field.value}>
+                <FormControl>
+                  <SelectTrigger>
+                    <SelectValue />
+                  </SelectTrigger>
+                </FormControl>
+                <SelectContent>
+                  <SelectItem value="default">Configuration file</SelectItem>
+                  <SelectItem value="true">Open</SelectItem>
+                  <SelectItem value="false">Closed</SelectItem>
+                </SelectContent>
+              </Select>
+              <FormDescription>
+                Whether new accounts can be registered.
+              </FormDescription>
+              <FormMessage />
+            </FormItem>
+          )}
+        />
+        <FormField
+          control={form.control}
+          name="disable_invite_codes"
+          render={({ field }) => (
+            <FormItem>
+              <FormLabel>Invite Codes</FormLabel>
+              <Select onValueChange={field.onChange} defaultValue={field.value}>
+                <FormControl>
+                  <SelectTrigger>
+                    <SelectValue />
+                  </SelectTrigger>
+                </FormControl>
+                <SelectContent>
+                  <SelectItem value="default">Configuration file</SelectItem>
+                  <SelectItem value="false">Required</SelectItem>
+                  <SelectItem value="true">Not required</SelectItem>
+                </SelectContent>
+              </Select>
+              <FormDescription>
+                <strong>Warning:</strong> anyone can create an account if
+                registration is open and invite codes are not required.
+              </FormDescription>
+              <FormMessage />
+            </FormItem>
+          )}
+        />
+        <FormField
+          control={form.control}
+          name="default_file_expiry"
+          render={({ field }) => (
+            <FormItem>
+              <FormLabel>File Expiry</FormLabel>
+              <Select onValueChange={field.onChange} defaultValue={field.value}>
+                <FormControl>
+                  <SelectTrigger>
+                    <SelectValue />
+                  </SelectTrigger>
+                </FormControl>
+                <SelectContent>
+                  <SelectItem value="default">Configuration file</SelectItem>
+                  <SelectItem value="0">Never</SelectItem>
+                  <SelectItem value={(60 * 60 * 24 * 1).toString()}>
+                    1 day
+                  </SelectItem>
+                  <SelectItem value={(60 * 60 * 24 * 7).toString()}>
+                    7 days
+                  </SelectItem>
+                  <SelectItem value={(60 * 60 * 24 * 30).toString()}>
+                    30 days
+                  </SelectItem>
+                  <SelectItem value={(60 * 60 * 24 * 365).toString()}>
+                    365 days
+                  </SelectItem>
+                </SelectContent>
+              </Select>
+              <FormDescription>
+                How long uploaded files are kept when no other expiry is given.
+              </FormDescription>
+              <FormMessage />
+            </FormItem>
+          )}
+        />
+        <FormField
+          control={form.control}
+          name="max_file_size"
+          render={({ field }) => (
+            <FormItem>
+              <FormLabel>Maximum File Size (MiB)</FormLabel>
+              <FormControl>
+                <Input placeholder="Configuration file" {...field} />
+              </FormControl>
+              <FormDescription>
+                The largest file that can be uploaded. Use 0 for no limit.
+              </FormDescription>
+              <FormMessage />
+            </FormItem>
+          )}
+        />
+        <FormField
+          control={form.control}
+          name="user_quota"
+          render={({ field }) => (
+            <FormItem>
+              <FormLabel>User Quota (MiB)</FormLabel>
+              <FormControl>
+                <Input placeholder="Configuration file" {...field} />
+              </FormControl>
+              <FormDescription>
+                The total size of the files a user can own outside of teams.
+                Use 0 for no limit.
+              </FormDescription>
+              <FormMessage />
+            </FormItem>
+          )}
+        />
+        <FormField
+          control={form.control}
+          name="max_slugs_per_user"
+          render={({ field }) => (
+            <FormItem>
+              <FormLabel>Short Links per User</FormLabel>
+              <FormControl>
+                <Input placeholder="Configuration file" {...field} />
+              </FormControl>
+              <FormDescription>
+                The maximum amount of short links a single user can create.
+              </FormDescription>
+              <FormMessage />
+            </FormItem>
+          )}
+        />
+        {(() => {
+          if (formState.type == "error") {
+            return (
+              <p className="text-sm font-medium text-destructive">
+                {formState.message}
+              </p>
+            )
+          }
+        })()}
+        {(() => {
+          // Display a loading button when the form is being processed.
+          if (formState.type == "idle" || formState.type == "error") {
+            return <Button type="submit">Save</Button>
+          } else if (formState.type == "submitting") {
+            return (
+              <Button type="submit" disabled className="flex flex-row gap-2">
+                <LoaderCircle className="animate-spin" />
+                Saving
+              </Button>
+            )
+          }
+        })()}
+      </form>
+    </Form>
+  )
+}
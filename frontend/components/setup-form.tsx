@@ -0,0 +1,214 @@
+"use client"
+
+import { zodResolver } from "@hookform/resolvers/zod"
+import { useForm } from "react-hook-form"
+import { z } from "zod"
+import { toast } from "sonner"
+import { Button } from "@/components/ui/button"
+import {
+  Form,
+  FormControl,
+  FormDescription,
+  FormField,
+  FormItem,
+  FormLabel,
+  FormMessage,
+} from "@/components/ui/form"
+import { Input } from "@/components/ui/input"
+import { useRouter } from "next/navigation"
+import { useState } from "react"
+import { LoaderCircle } from "lucide-react"
+
+const formSchema = z
+  .object({
+    setup_token: z.string().min(1, {
+      message: "The setup token is required.",
+    }),
+    username: z
+      .string()
+      .min(3, {
+        message: "Username must be at least 3 characters.",
+      })
+      .max(20, {
+        message: "Username must be at most 20 characters.",
+      }),
+    password: z
+      .string()
+      .min(8, {
+        message: "Password must be at least 8 characters",
+      })
+      .max(80, {
+        message: "Password must be at most 80 characters",
+      }),
+    verifyPassword: z
+      .string()
+      .min(8, {
+        message: "Password must be at least 8 characters",
+      })
+      .max(80, {
+        message: "Password must be at most 80 characters",
+      }),
+  })
+  .refine((data) => data.password === data.verifyPassword, {
+    message: "Passwords did not match",
+    path: ["verifyPassword"],
+  })
+
+type SetupState =
+  | { type: "idle" }
+  | { type: "submitting" }
+  | { type: "error"; message: string }
+
+export function SetupForm() {
+  const router = useRouter()
+  const [setupState, setSetupState] = useState<SetupState>({
+    type: "idle",
+  })
+
+  const form = useForm<z.infer<typeof formSchema>>({
+    resolver: zodResolver(formSchema),
+    defaultValues: {},
+  })
+
+  async function onSubmit(values: z.infer<typeof formSchema>) {
+    setSetupState({ type: "submitting" })
+
+    let resp = await fetch("/api/setup", {
+      method: "POST",
+      headers: {
+        "Content-Type": "application/json",
+      },
+      body: JSON.stringify(values),
+    })
+
+    if (
+      resp.status == 400 ||
+      resp.status == 401 ||
+      resp.status == 409 ||
+      resp.status == 429
+    ) {
+      setSetupState({
+        type: "error",
+        message: await resp.text(),
+      })
+      return
+    }
+
+    if (!resp.ok) {
+      setSetupState({ type: "error", message: "An unknown error occurred." })
+      throw new Error("An error occurred while trying to complete the setup")
+    }
+
+    setSetupState({ type: "idle" })
+    toast("The administrator account has been created.")
+    router.replace("/")
+    router.refresh()
+  }
+
+  return (
+    <Form {...form}>
+      <form onSubmit={form.handleSubmit(onSubmit)} className="space-y-8">
+        <FormField
+          control={form.control}
+          name="setup_token"
+          render={({ field }) => (
+            <FormItem>
+              <FormLabel>Setup Token</FormLabel>
+              <FormControl>
+                <Input
+                  spellCheck="false"
+                  autoCapitalize="none"
+                  placeholder="the setup token"
+                  {...field}
+                />
+              </FormControl>
+              <FormDescription>
+                The setup token is printed to the log of the server when it
+                starts without an administrator.
+              </FormDescription>
+              <FormMessage />
+            </FormItem>
+          )}
+        />
+        <FormField
+          control={form.control}
+          name="username"
+          render={({ field }) => (
+            <FormItem>
+              <FormLabel>Username</FormLabel>
+              <FormControl>
+                <Input
+                  autoComplete="username"
+                  spellCheck="false"
+                  autoCapitalize="none"
+                  placeholder="your username"
+                  {...field}
+                />
+              </FormControl>
+              <FormMessage />
+            </FormItem>
+          )}
+        />
+        <FormField
+          control={form.control}
+          name="password"
+          render={({ field }) => (
+            <FormItem>
+              <FormLabel>Password</FormLabel>
+              <FormControl>
+                <Input
+                  type="password"
+                  autoComplete="new-password"
+                  placeholder="your password"
+                  {...field}
+                />
+              </FormControl>
+              <FormMessage />
+            </FormItem>
+          )}
+        />
+        <FormField
+          control={form.control}
+          name="verifyPassword"
+          render={({ field }) => (
+            <FormItem>
+              <FormLabel>Confirm Password</FormLabel>
+              <FormControl>
+                <Input
+                  type="password"
+                  autoComplete="new-password"
+                  placeholder="your password"
+                  {...field}
+                />
+              </FormControl>
+              <FormMessage />
+            </FormItem>
+          )}
+        />
+        {(() => {
+          // Display an error message if the setup failed.
+          if (setupState.type == "error") {
+            return (
+              <p className="text-sm font-medium text-destructive">
+                {setupState.message}
+              </p>
+            )
+          }
+        })()}
+        {(() => {
+          // Display a loading button when the form is being processed.
+          if (setupState.type == "idle" || setupState.type == "error") {
+            return <Button type="submit">Submit</Button>
+          } else if (setupState.type == "submitting") {
+            return (
+              <Button type="submit" disabled className="flex flex-row gap-2">
+                <LoaderCircle className="animate-spin" />
+                Submitting
+              </Button>
+            )
+          }
+        })()}
+      </form>
+    </Form>
+  )
+}
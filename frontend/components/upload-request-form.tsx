@@ -0,0 +1,118 @@
+"use client"
+
+import { Button } from "@/components/ui/button"
+import { Input } from "@/components/ui/input"
+import { Label } from "@/components/ui/label"
+import { useState } from "react"
+import { LoaderCircle } from "lucide-react"
+import { toast } from "sonner"
+
+type UploadState =
+  | { type: "idle" }
+  | { type: "submitting" }
+  | { type: "error"; message: string }
+
+export function UploadRequestForm({
+  requestId,
+  hasPassword,
+  maxFileSize,
+}: {
+  requestId: string
+  hasPassword: boolean
+  maxFileSize: number | null
+}) {
+  const [files, setFiles] = useState<FileList | null>(null)
+  const [password, setPassword] = useState("")
+  const [uploadState, setUploadState] = useState<UploadState>({
+    type: "idle",
+  })
+
+  async function onSubmit(event: React.FormEvent) {
+    event.preventDefault()
+    if (!files || files.length == 0) {
+      setUploadState({ type: "error", message: "Please select a file." })
+      return
+    }
+
+    setUploadState({ type: "submitting" })
+    for (const file of Array.from(files)) {
+      if (maxFileSize != null && file.size > maxFileSize) {
+        setUploadState({
+          type: "error",
+          message: `${file.name} is larger than ${maxFileSize} bytes.`,
+        })
+        return
+      }
+
+      const headers: { [key: string]: string } = { file_name: file.name }
+      if (hasPassword) {
+        headers["password"] = password
+      }
+      const resp = await fetch(`/api/upload-requests/${requestId}`, {
+        method: "POST",
+        headers,
+        body: file,
+      })
+
+      if (resp.status == 401) {
+        setUploadState({ type: "error", message: "Incorrect password." })
+        return
+      }
+
+      if (resp.status == 410) {
+        setUploadState({
+          type: "error",
+          message: "This link has expired or no more files can be uploaded.",
+        })
+        return
+      }
+
+      if (!resp.ok) {
+        setUploadState({ type: "error", message: await resp.text() })
+        return
+      }
+
+      toast(`${file.name} has been uploaded.`)
+    }
+
+    setUploadState({ type: "idle" })
+  }
+
+  return (
+    <form onSubmit={onSubmit} className="space-y-8">
+      <div className="space-y-2">
+        <Label htmlFor="files">Files</Label>
+        <Input
+          id="files"
+          type="file"
+          multiple
+          onChange={(e) => setFiles(e.target.files)}
+        />
+      </div>
+      {hasPassword && (
+        <div className="space-y-2">
+          <Label htmlFor="password">Password</Label>
+          <Input
+            id="password"
+            type="password"
+            value={password}
+            onChange={(e) => setPassword(e.target.value)}
+          />
+        </div>
+      )}
+      {uploadState.type == "error" && (
+        <p className="text-sm font-medium text-destructive">
+          {uploadState.message}
+        </p>
+      )}
+      {uploadState.type == "submitting" ? (
+        <Button type="submit" disabled className="flex flex-row gap-2">
+          <LoaderCircle className="animate-spin" />
+          Uploading
+        </Button>
+      ) : (
+        <Button type="submit">Upload</Button>
+      )}
+    </form>
+  )
+}
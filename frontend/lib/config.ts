@@ -3,6 +3,21 @@ import { serverFetch } from "./server-fetch"
 type Config = {
   allow_registering: boolean
   disable_invite_codes: boolean
+  instance_name: string
+  max_file_size: number | null
+  default_file_expiry: number | null
+}
+
+/// Settings stored by administrators, which take precedence over the configuration file. Unset
+/// settings use the value of the configuration file.
+export type Settings = {
+  allow_registering?: boolean
+  disable_invite_codes?: boolean
+  instance_name?: string
+  default_file_expiry?: number
+  max_file_size?: number
+  user_quota?: number
+  max_slugs_per_user?: number
 }
 
 export async function getConfig(): Promise<Config> {
@@ -13,3 +28,12 @@ export async function getConfig(): Promise<Config> {
 
   return await resp.json()
 }
+
+export async function getSettings(): Promise<Settings> {
+  let resp = await serverFetch("/api/config/settings")
+  if (!resp.ok) {
+    throw new Error("Unable to fetch settings")
+  }
+
+  return await resp.json()
+}
@@ -0,0 +1,4 @@
+-- Files can optionally expire, after which they are no longer served and eventually removed.
+alter table files add column expires_at timestamptz;
+
+create index files_expires_at on files (expires_at) where expires_at is not null;
@@ -0,0 +1,11 @@
+create table slugs (
+    -- A short, human readable name that resolves to a file. Always stored in lowercase.
+    slug text primary key,
+    file_id bigint not null references files(file_id) on delete cascade,
+    -- The user that created the slug. Used to enforce the per-user limit.
+    created_by uuid not null references users(user_id) on delete cascade,
+    created_at timestamptz not null default now()
+);
+
+create index slugs_file_id on slugs (file_id);
+create index slugs_created_by on slugs (created_by);
@@ -0,0 +1,14 @@
+-- The version of the content currently served for a file. Starts at 1 and is incremented every
+-- time the content of the file is replaced.
+alter table files add column version int not null default 1;
+
+-- Previous versions of a file's content. The current version is not included.
+create table file_versions (
+    file_id bigint not null references files(file_id) on delete cascade,
+    version int not null,
+    -- The size of this version in bytes.
+    file_size bigint not null constraint positive_size check (file_size >= 0),
+    upload_date timestamptz not null,
+
+    primary key (file_id, version)
+);
@@ -0,0 +1,44 @@
+-- The MIME type of a file, guessed from its name when it is uploaded.
+alter table files add column content_type text;
+
+-- Existing files only get the most common types, anything else is treated as binary data.
+update files set content_type = case lower(substring(file_name from '\.([^.]+)$'))
+    when 'txt' then 'text/plain'
+    when 'log' then 'text/plain'
+    when 'md' then 'text/markdown'
+    when 'html' then 'text/html'
+    when 'css' then 'text/css'
+    when 'csv' then 'text/csv'
+    when 'js' then 'text/javascript'
+    when 'json' then 'application/json'
+    when 'xml' then 'application/xml'
+    when 'pdf' then 'application/pdf'
+    when 'zip' then 'application/zip'
+    when 'gz' then 'application/gzip'
+    when 'tgz' then 'application/gzip'
+    when 'tar' then 'application/x-tar'
+    when 'png' then 'image/png'
+    when 'jpg' then 'image/jpeg'
+    when 'jpeg' then 'image/jpeg'
+    when 'gif' then 'image/gif'
+    when 'webp' then 'image/webp'
+    when 'bmp' then 'image/bmp'
+    when 'svg' then 'image/svg+xml'
+    when 'mp3' then 'audio/mpeg'
+    when 'mp4' then 'video/mp4'
+    when 'webm' then 'video/webm'
+    else 'application/octet-stream'
+end;
+
+alter table files alter column content_type set not null;
+
+-- Indexes backing the filters and keyset pagination of file listings. The file id is included as a
+-- tie-breaker, as the sort keys are not unique.
+create index files_uploader_upload_date on files (uploader_id, upload_date, file_id);
+create index files_uploader_file_size on files (uploader_id, file_size, file_id);
+create index files_uploader_file_name on files (uploader_id, file_name, file_id);
+create index files_uploader_content_type on files (uploader_id, content_type);
+
+-- Allows searching for substrings and glob patterns in file names.
+create extension if not exists pg_trgm;
+create index files_file_name_trgm on files using gin (file_name gin_trgm_ops);
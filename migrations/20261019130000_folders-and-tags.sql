@@ -0,0 +1,25 @@
+create table folders (
+    folder_id uuid primary key,
+    owner_id uuid not null references users(user_id) on delete cascade,
+    -- Folders without a parent are at the top level. Removing a folder removes its subfolders.
+    parent_id uuid references folders(folder_id) on delete cascade,
+    name text not null,
+    created_at timestamptz not null default now()
+);
+
+-- Folder names are unique within their parent. Top level folders use the owner as their parent,
+-- as null values are never considered equal.
+create unique index folders_unique_name on folders (owner_id, coalesce(parent_id, owner_id), name);
+create index folders_parent_id on folders (parent_id);
+
+-- Files in a removed folder are moved back to the top level.
+alter table files add column folder_id uuid references folders(folder_id) on delete set null;
+create index files_folder_id on files (folder_id);
+
+create table file_tags (
+    file_id bigint not null references files(file_id) on delete cascade,
+    tag text not null,
+    primary key (file_id, tag)
+);
+
+create index file_tags_tag on file_tags (tag, file_id);
@@ -0,0 +1,11 @@
+-- A free-text description of the file, set by its owner.
+alter table files add column description text;
+
+-- Previous names of files. Links using an old name are redirected to the current name, so links
+-- that were already shared keep working after a file is renamed.
+create table file_name_history (
+    file_id bigint not null references files(file_id) on delete cascade,
+    file_name text not null,
+    renamed_at timestamptz not null default now(),
+    primary key (file_id, file_name)
+);
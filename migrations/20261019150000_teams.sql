@@ -0,0 +1,25 @@
+create table teams (
+    team_id uuid primary key,
+    name text not null unique,
+    -- The maximum total size of the team's files in bytes. Teams without a quota have no limit.
+    quota bigint constraint positive_quota check (quota >= 0),
+    created_at timestamptz not null default now()
+);
+
+create table team_members (
+    team_id uuid not null references teams(team_id) on delete cascade,
+    user_id uuid not null references users(user_id) on delete cascade,
+    -- `owner`: can manage the team and its members.
+    -- `member`: can upload, replace and delete the team's files.
+    -- `viewer`: can list the team's files.
+    role text not null constraint valid_role check (role in ('owner', 'member', 'viewer')),
+    added_at timestamptz not null default now(),
+    primary key (team_id, user_id)
+);
+
+create index team_members_user_id on team_members (user_id);
+
+-- Files can be owned by a team, in which case all members can manage them. A team can only be
+-- removed once it no longer owns any files.
+alter table files add column team_id uuid references teams(team_id) on delete restrict;
+create index files_team_id on files (team_id, upload_date, file_id);
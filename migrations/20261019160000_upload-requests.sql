@@ -0,0 +1,20 @@
+-- Links that allow people without an account to upload files to a user.
+create table upload_requests (
+    -- The random token used in the upload link.
+    request_id text primary key,
+    owner_id uuid not null references users(user_id) on delete cascade,
+    -- Shown to the people uploading, such as what files are expected.
+    description text,
+    -- The folder uploaded files are put in. Files are put at the top level if this is not set.
+    folder_id uuid references folders(folder_id) on delete set null,
+    -- Uploading requires this password if it is set.
+    password_hash text,
+    max_files integer constraint positive_max_files check (max_files > 0),
+    max_file_size bigint constraint positive_max_file_size check (max_file_size > 0),
+    -- The amount of files that have been uploaded using the link.
+    file_count integer not null default 0,
+    expires_at timestamptz,
+    created_at timestamptz not null default now()
+);
+
+create index upload_requests_owner_id on upload_requests (owner_id);
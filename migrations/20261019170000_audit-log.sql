@@ -0,0 +1,22 @@
+-- Security-relevant and administrative actions. Users are not referenced using foreign keys, so
+-- the log is kept when users are removed.
+create table audit_log (
+    event_id bigint generated always as identity primary key,
+    created_at timestamptz not null default now(),
+    action text not null,
+    -- The user that performed the action, if known. For failed logins only the name that was used
+    -- is known.
+    actor_id uuid,
+    actor_name text,
+    -- What the action was performed on, such as a file or a user.
+    target_type text,
+    target_id text,
+    ip inet,
+    success boolean not null default true,
+    details jsonb
+);
+
+create index audit_log_created_at on audit_log (created_at);
+create index audit_log_actor_id on audit_log (actor_id, event_id);
+create index audit_log_action on audit_log (action, event_id);
+create index audit_log_target on audit_log (target_type, target_id);
@@ -0,0 +1,15 @@
+-- Every download of a file, used for download statistics.
+create table file_downloads (
+    download_id bigint generated always as identity primary key,
+    file_id bigint not null references files(file_id) on delete cascade,
+    -- The user that downloaded the file, if they were logged in.
+    user_id uuid references users(user_id) on delete set null,
+    downloaded_at timestamptz not null default now(),
+    -- The amount of bytes that were sent before the download finished or was aborted.
+    bytes_served bigint not null,
+    -- True if the whole file was sent.
+    completed boolean not null
+);
+
+create index file_downloads_file_id on file_downloads (file_id, downloaded_at);
+create index file_downloads_downloaded_at on file_downloads (downloaded_at);
@@ -0,0 +1,2 @@
+-- Set when a consistency check found the content of a file to be missing or to not match its size.
+alter table files add column broken_at timestamptz;
@@ -0,0 +1,21 @@
+-- Set for users that have to choose a new password before they can log in again.
+alter table users add column must_change_password bool not null default false;
+
+-- An earlier migration created an `admin` user with a publicly known password. It is removed if it
+-- was never used, which is always the case for new instances. The first admin is created during
+-- setup instead.
+delete from users
+    where password_hash = '$argon2id$v=19$m=19456,t=2,p=1$amNZJvxxwV2qDbT2/48bqg$IVuv8DqwtCDCK3CPplhGoj3Tff7ocZBsc/fyPH0h4Q8'
+        and not exists (select * from files where uploader_id = users.user_id)
+        and not exists (select * from invites where created_by = users.user_id)
+        and not exists (select * from sessions where sessions.user_id = users.user_id)
+        and not exists (select * from ssh_keys where ssh_keys.user_id = users.user_id)
+        and not exists (select * from audit_log where actor_id = users.user_id);
+
+-- Otherwise the account is in use and its password has to be changed on the next login. Existing
+-- sessions are removed, so this can't be avoided.
+update users
+    set must_change_password = true
+    where password_hash = '$argon2id$v=19$m=19456,t=2,p=1$amNZJvxxwV2qDbT2/48bqg$IVuv8DqwtCDCK3CPplhGoj3Tff7ocZBsc/fyPH0h4Q8';
+delete from sessions
+    where user_id in (select user_id from users where must_change_password);
@@ -0,0 +1,9 @@
+-- Settings changed by administrators while the server is running. These take precedence over the
+-- configuration file.
+create table settings (
+    key text primary key,
+    value jsonb not null,
+    updated_at timestamptz not null default now(),
+    -- The administrator that last changed the setting.
+    updated_by uuid references users (user_id) on delete set null
+);
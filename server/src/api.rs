@@ -1,22 +1,77 @@
-use axum::Router;
+use axum::{
+    response::{IntoResponse, Response},
+    Router,
+};
+use http::{header, HeaderMap, StatusCode};
 
 use crate::state::AppState;
 
+mod audit_log;
 mod auth;
 mod config;
 mod files;
+mod fsck;
+mod healthz;
 mod invites;
 mod logout;
+mod metrics;
+mod pastes;
+mod readyz;
+mod setup;
+mod slugs;
+mod stats;
+mod teams;
+mod upload_requests;
 mod usernames;
 mod users;
 
 pub(super) fn router() -> Router<AppState> {
     Router::new()
+        .nest("/audit-log", audit_log::router())
         .nest("/auth", auth::router())
         .nest("/config", config::router())
         .nest("/files", files::router())
+        .nest("/fsck", fsck::router())
         .nest("/invites", invites::router())
         .nest("/logout", logout::router())
+        .nest("/pastes", pastes::router())
+        .nest("/setup", setup::router())
         .nest("/usernames", usernames::router())
+        .nest("/slugs", slugs::router())
+        .nest("/stats", stats::router())
+        .nest("/teams", teams::router())
+        .nest("/upload-requests", upload_requests::router())
         .nest("/users", users::router())
 }
+
+/// Routes for health checks, which are served outside of `/api`.
+pub(super) fn health_router() -> Router<AppState> {
+    Router::new()
+        .nest("/healthz", healthz::router())
+        .nest("/readyz", readyz::router())
+}
+
+/// Routes for the Prometheus metrics endpoint, which is served outside of `/api`.
+pub(super) fn metrics_router() -> Router<AppState> {
+    metrics::router()
+}
+
+/// Routes for short links, which are served outside of `/api`.
+pub(super) fn short_link_router() -> Router<AppState> {
+    slugs::redirect_router()
+}
+
+/// Reads the length of an upload from its headers, so files that are known to be too large can be
+/// rejected before receiving them.
+fn content_length(headers: &HeaderMap) -> Option<u64> {
+    headers
+        .get(header::CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse().ok())
+}
+
+/// The response to uploads of files larger than `max` bytes.
+fn too_large(max: u64) -> Response {
+    let msg = format!("Files can be at most {max} bytes large");
+    (StatusCode::PAYLOAD_TOO_LARGE, msg).into_response()
+}
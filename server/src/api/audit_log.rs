@@ -0,0 +1,41 @@
+mod export;
+
+use axum::{
+    extract::{Query, State},
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+use http::StatusCode;
+use sqlx::PgPool;
+
+use crate::{
+    audit::{list_events, AuditFilter, AuditPage, AuditQueryError},
+    auth::Authentication,
+    error,
+    state::AppState,
+};
+
+pub(super) fn router() -> Router<AppState> {
+    Router::new()
+        .nest("/export", export::router())
+        .route("/", get(handle_get))
+}
+
+/// List audit log events matching a filter, newest first, one page at a time.
+async fn handle_get(
+    auth: Authentication,
+    State(db): State<PgPool>,
+    Query(filter): Query<AuditFilter>,
+    Query(page): Query<AuditPage>,
+) -> error::Result<Response> {
+    if !auth.is_admin {
+        return Ok(StatusCode::FORBIDDEN.into_response());
+    }
+
+    match list_events(&db, &filter, &page).await {
+        Ok(events) => Ok(Json(events).into_response()),
+        Err(AuditQueryError::Database(err)) => Err(err.into()),
+        Err(err) => Ok((err.status_code(), err.to_string()).into_response()),
+    }
+}
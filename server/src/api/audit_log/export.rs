@@ -0,0 +1,136 @@
+use axum::{
+    body::Body,
+    extract::{Query, State},
+    response::{IntoResponse, Response},
+    routing::get,
+    Router,
+};
+use futures::{stream, StreamExt};
+use http::{header, StatusCode};
+use serde::Deserialize;
+use sqlx::PgPool;
+use tracing::error;
+
+use crate::{
+    audit::{list_events, AuditFilter, AuditPage, AuditQueryError, CSV_HEADER, MAX_LIMIT},
+    auth::Authentication,
+    state::AppState,
+};
+
+pub(super) fn router() -> Router<AppState> {
+    Router::new().route("/", get(handle_get))
+}
+
+#[derive(Debug, Default, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum ExportFormat {
+    #[default]
+    Json,
+    Csv,
+}
+
+#[derive(Deserialize)]
+struct ExportOptions {
+    #[serde(default)]
+    format: ExportFormat,
+}
+
+/// The state of an export that is being streamed.
+enum ExportState {
+    Start,
+    Next(i64),
+    Done,
+}
+
+/// Download all audit log events matching a filter, newest first. JSON exports are a single
+/// array, CSV exports have a header line.
+///
+/// The events are read and sent one page at a time, so large exports don't have to fit in memory.
+async fn handle_get(
+    auth: Authentication,
+    State(db): State<PgPool>,
+    Query(filter): Query<AuditFilter>,
+    Query(options): Query<ExportOptions>,
+) -> crate::error::Result<Response> {
+    if !auth.is_admin {
+        return Ok(StatusCode::FORBIDDEN.into_response());
+    }
+
+    // Check the filter before starting the response, as errors can't be reported once the body
+    // is being sent.
+    let first_page = AuditPage {
+        limit: Some(MAX_LIMIT),
+        cursor: None,
+    };
+    match list_events(&db, &filter, &first_page).await {
+        Ok(_) => {}
+        Err(AuditQueryError::Database(err)) => return Err(err.into()),
+        Err(err) => return Ok((err.status_code(), err.to_string()).into_response()),
+    }
+
+    let format = options.format;
+    let pages = stream::unfold(ExportState::Start, move |state| {
+        let db = db.clone();
+        let filter = filter.clone();
+        async move {
+            let cursor = match state {
+                ExportState::Start => None,
+                ExportState::Next(cursor) => Some(cursor),
+                ExportState::Done => return None,
+            };
+            let page = AuditPage {
+                limit: Some(MAX_LIMIT),
+                cursor,
+            };
+
+            let page = match list_events(&db, &filter, &page).await {
+                Ok(v) => v,
+                Err(err) => {
+                    error!("Could not export audit log: {err:?}");
+                    return Some((Err(std::io::Error::other(err)), ExportState::Done));
+                }
+            };
+
+            let mut chunk = String::new();
+            for (i, event) in page.events.iter().enumerate() {
+                match format {
+                    ExportFormat::Json => {
+                        if cursor.is_some() || i > 0 {
+                            chunk.push(',');
+                        }
+                        chunk.push_str(
+                            &serde_json::to_string(event).expect("events can be serialized"),
+                        );
+                    }
+                    ExportFormat::Csv => chunk.push_str(&event.to_csv_line()),
+                }
+            }
+
+            let next = match page.next {
+                Some(cursor) => ExportState::Next(cursor),
+                None => ExportState::Done,
+            };
+            Some((Ok(chunk), next))
+        }
+    });
+
+    let (start, end, content_type, file_name) = match format {
+        ExportFormat::Json => ("[", "]", "application/json", "audit-log.json"),
+        ExportFormat::Csv => (CSV_HEADER, "", "text/csv; charset=utf-8", "audit-log.csv"),
+    };
+    let body = stream::iter([Ok(start.to_string())])
+        .chain(pages)
+        .chain(stream::iter([Ok(end.to_string())]));
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, content_type.to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{file_name}\""),
+            ),
+        ],
+        Body::from_stream(body),
+    )
+        .into_response())
+}
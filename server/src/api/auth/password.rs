@@ -16,10 +16,16 @@ use tower_cookies::Cookies;
 use tracing::warn;
 
 use crate::{
-    auth::UserAuthFailures,
+    audit::{AuditAction, AuditEvent},
+    auth::{UserAuthFailures, AUTH_FAILURE_RESET},
+    client::ClientInfo,
+    config::Config,
     error,
+    metrics::METRICS,
+    rate_limit::{Policy, RateLimiter},
     session::{create_session, store_session},
     state::AppState,
+    user::hash_password,
 };
 
 pub(super) fn router() -> Router<AppState> {
@@ -30,22 +36,35 @@ pub(super) fn router() -> Router<AppState> {
 struct AuthenticateForm {
     username: String,
     password: String,
+    /// The new password of the user, required if they must change their password before logging
+    /// in.
+    new_password: Option<String>,
 }
 
 async fn handle_post(
     cookies: Cookies,
+    client: ClientInfo,
     State(db): State<PgPool>,
     State(failures): State<Arc<UserAuthFailures>>,
+    State(limiter): State<Arc<RateLimiter>>,
+    State(config): State<Arc<Config>>,
     Json(form): Json<AuthenticateForm>,
 ) -> error::Result<Response> {
     // Get the current time. This is later used to ensure this requests takes a minimum amount of
     // time.
     let start = tokio::time::Instant::now();
 
+    if let Err(limited) = limiter
+        .check(Policy::Auth, client.ip, Some(&form.username))
+        .await
+    {
+        return Ok(limited.into_response());
+    }
+
     let mut tx = db.begin().await?;
     // First check if the user exists and if the password matches.
     let row = sqlx::query!(
-        "select user_id, password_hash from users where username = $1",
+        "select user_id, password_hash, must_change_password from users where username = $1",
         form.username,
     )
     .fetch_optional(&mut *tx)
@@ -55,10 +74,18 @@ async fn handle_post(
         warn!(?form.username, "Tried to log in for unknown user");
         // Authentication failed if the user could not be found.
         tx.commit().await?;
+        AuditEvent::new(AuditAction::Login)
+            .actor(None, &form.username)
+            .ip(client.ip)
+            .failed()
+            .details(serde_json::json!({ "method": "password", "reason": "unknown user" }))
+            .record(&db)
+            .await;
+        METRICS.auth_failure("password");
         return Ok(StatusCode::UNAUTHORIZED.into_response());
     };
 
-    let password = form.password;
+    let password = form.password.clone();
     let hash_check = tokio::task::spawn_blocking(move || {
         Argon2::default().verify_password(
             password.as_bytes(),
@@ -74,9 +101,10 @@ async fn handle_post(
 
         let entry = failures.entry(row.user_id).or_insert((0, start));
         // After an hour of no failed attempts we can reset the counter.
-        if start - entry.1 > tokio::time::Duration::from_secs(60 * 60) {
+        if start - entry.1 > AUTH_FAILURE_RESET {
             entry.0 = 0;
         }
+        entry.1 = start;
 
         // Ensure this doesn't overflow.
         entry.0 = entry.0.saturating_add(1);
@@ -91,20 +119,81 @@ async fn handle_post(
         .await;
 
         tx.commit().await?;
+        AuditEvent::new(AuditAction::Login)
+            .actor(Some(row.user_id), &form.username)
+            .target("user", row.user_id)
+            .ip(client.ip)
+            .failed()
+            .details(serde_json::json!({ "method": "password", "reason": "wrong password" }))
+            .record(&db)
+            .await;
         warn!(
             "Authentication for user `{}` failed: {err:?}",
             form.username
         );
+        METRICS.auth_failure("password");
         return Ok(StatusCode::UNAUTHORIZED.into_response());
     }
 
+    // Users with a password that is known to others, such as the former default admin, have to
+    // choose a new one before they can log in.
+    let changed_password = row.must_change_password;
+    if row.must_change_password {
+        let Some(new_password) = form.new_password else {
+            tx.commit().await?;
+            let msg = "You must choose a new password before logging in";
+            return Ok((StatusCode::FORBIDDEN, msg).into_response());
+        };
+        if new_password.is_empty() || new_password == form.password {
+            let msg = "The new password must be different from the current one";
+            return Ok((StatusCode::BAD_REQUEST, msg).into_response());
+        }
+
+        let password_hash = hash_password(new_password).await?;
+        sqlx::query!(
+            "update users set password_hash=$2, must_change_password=false where user_id=$1",
+            row.user_id,
+            password_hash,
+        )
+        .execute(&mut *tx)
+        .await?;
+    }
+
     // The user exists and the password matches, we can now create a session.
-    let session = create_session(&mut tx, row.user_id, Duration::weeks(1))
-        .await
-        .context("error while creating session")?;
+    let session = create_session(
+        &mut tx,
+        row.user_id,
+        Duration::seconds(config.sessions.login_lifetime as i64),
+    )
+    .await
+    .context("error while creating session")?;
     tx.commit().await?;
 
-    store_session(&cookies, &session)?;
+    store_session(&cookies, &session, client.https)?;
+
+    if changed_password {
+        AuditEvent::new(AuditAction::PasswordChanged)
+            .actor(Some(row.user_id), &form.username)
+            .target("user", row.user_id)
+            .ip(client.ip)
+            .details(serde_json::json!({ "required": true }))
+            .record(&db)
+            .await;
+    }
+    AuditEvent::new(AuditAction::Login)
+        .actor(Some(row.user_id), &form.username)
+        .target("user", row.user_id)
+        .ip(client.ip)
+        .details(serde_json::json!({ "method": "password" }))
+        .record(&db)
+        .await;
+    AuditEvent::new(AuditAction::SessionCreated)
+        .actor(Some(row.user_id), &form.username)
+        .target("user", row.user_id)
+        .ip(client.ip)
+        .details(serde_json::json!({ "valid_until": session.valid_until }))
+        .record(&db)
+        .await;
 
     Ok(Json(session).into_response())
 }
@@ -14,7 +14,14 @@ use sqlx::PgPool;
 use ssh_key::{Fingerprint, PublicKey};
 use tracing::warn;
 
-use crate::{auth::ssh::SSHAuthState, error, state::AppState};
+use crate::{
+    auth::ssh::SSHAuthState,
+    client::ClientInfo,
+    error,
+    metrics::METRICS,
+    rate_limit::{Policy, RateLimiter},
+    state::AppState,
+};
 
 pub(super) fn router() -> Router<AppState> {
     Router::new().route("/", post(handle_post))
@@ -27,13 +34,22 @@ struct PostData {
 }
 
 async fn handle_post(
+    client: ClientInfo,
     State(db): State<PgPool>,
     State(ssh): State<Arc<SSHAuthState>>,
+    State(limiter): State<Arc<RateLimiter>>,
     Json(data): Json<PostData>,
 ) -> error::Result<Response> {
+    if let Err(limited) = limiter
+        .check(Policy::Auth, client.ip, Some(&data.username))
+        .await
+    {
+        return Ok(limited.into_response());
+    }
+
     let Some(row) = sqlx::query!(
         r#"
-            select public_key, users.user_id
+            select public_key, users.user_id, must_change_password
                 from ssh_keys join users on ssh_keys.user_id=users.user_id
                 where users.username=$1 and public_key_fingerprint=$2
         "#,
@@ -44,9 +60,15 @@ async fn handle_post(
     .await?
     else {
         warn!("Unknown user or public key");
+        METRICS.auth_failure("ssh");
         return Ok(StatusCode::UNAUTHORIZED.into_response());
     };
 
+    if row.must_change_password {
+        let msg = "You must choose a new password by logging in on the website first";
+        return Ok((StatusCode::FORBIDDEN, msg).into_response());
+    }
+
     let public_key =
         PublicKey::from_openssh(&row.public_key).context("could not parse public key")?;
 
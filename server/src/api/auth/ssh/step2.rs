@@ -13,8 +13,13 @@ use tower_cookies::Cookies;
 use tracing::warn;
 
 use crate::{
+    audit::{AuditAction, AuditEvent},
     auth::ssh::{SSHAuthState, Ticket},
+    client::ClientInfo,
+    config::Config,
     error,
+    metrics::METRICS,
+    rate_limit::{Policy, RateLimiter},
     session::{self, store_session},
     state::AppState,
 };
@@ -25,12 +30,26 @@ pub(super) fn router() -> Router<AppState> {
 
 async fn handle_post(
     cookies: Cookies,
+    client: ClientInfo,
     State(db): State<PgPool>,
     State(ssh): State<Arc<SSHAuthState>>,
+    State(config): State<Arc<Config>>,
+    State(limiter): State<Arc<RateLimiter>>,
     Json(ticket): Json<Ticket>,
 ) -> error::Result<Response> {
+    if let Err(limited) = limiter.check(Policy::Auth, client.ip, None).await {
+        return Ok(limited.into_response());
+    }
+
     let Some((user, fingerprint)) = ssh.validate_response_ticket(ticket).await else {
         warn!("User supplied an unknown ticket");
+        AuditEvent::new(AuditAction::Login)
+            .ip(client.ip)
+            .failed()
+            .details(serde_json::json!({ "method": "ssh", "reason": "unknown ticket" }))
+            .record(&db)
+            .await;
+        METRICS.auth_failure("ssh");
         return Ok(StatusCode::UNAUTHORIZED.into_response());
     };
 
@@ -48,12 +67,45 @@ async fn handle_post(
     if !row.exists.unwrap_or(false) {
         tx.commit().await?;
         warn!("User or user SSH key has been removed since ssh auth step 1");
+        AuditEvent::new(AuditAction::Login)
+            .actor_id(user)
+            .target("user", user)
+            .ip(client.ip)
+            .failed()
+            .details(serde_json::json!({
+                "method": "ssh",
+                "fingerprint": fingerprint.to_string(),
+                "reason": "key removed",
+            }))
+            .record(&db)
+            .await;
+        METRICS.auth_failure("ssh");
         return Ok(StatusCode::UNAUTHORIZED.into_response());
     }
 
-    let session = session::create_session(&mut tx, user, Duration::minutes(1)).await?;
+    let session = session::create_session(
+        &mut tx,
+        user,
+        Duration::seconds(config.sessions.ssh_lifetime as i64),
+    )
+    .await?;
     tx.commit().await?;
-    store_session(&cookies, &session)?;
+    store_session(&cookies, &session, client.https)?;
+
+    AuditEvent::new(AuditAction::Login)
+        .actor_id(user)
+        .target("user", user)
+        .ip(client.ip)
+        .details(serde_json::json!({ "method": "ssh", "fingerprint": fingerprint.to_string() }))
+        .record(&db)
+        .await;
+    AuditEvent::new(AuditAction::SessionCreated)
+        .actor_id(user)
+        .target("user", user)
+        .ip(client.ip)
+        .details(serde_json::json!({ "valid_until": session.valid_until }))
+        .record(&db)
+        .await;
 
     Ok(Json(session).into_response())
 }
@@ -1,16 +1,66 @@
+mod reload;
+mod settings;
+
 use std::sync::Arc;
 
-use axum::{extract::State, routing::get, Json, Router};
+use axum::{
+    extract::State,
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+use http::StatusCode;
+use sqlx::PgPool;
+use tracing::info;
 
 use crate::{
-    config::{Config, PublicConfig},
+    audit::{AuditAction, AuditEvent},
+    auth::Authentication,
+    client::ClientInfo,
+    config::{Config, ConfigHandle, PublicConfig, Settings, SettingsError},
+    error,
     state::AppState,
 };
 
 pub(super) fn router() -> Router<AppState> {
-    Router::new().route("/", get(handle_get))
+    Router::new()
+        .route("/", get(handle_get).put(handle_put))
+        .nest("/reload", reload::router())
+        .nest("/settings", settings::router())
 }
 
 async fn handle_get(State(config): State<Arc<Config>>) -> Json<PublicConfig> {
-    Json(config.public_config.clone())
+    Json(config.public_config())
+}
+
+/// Replace the settings stored in the database, which take precedence over the configuration
+/// file. Settings that are left out use the value of the configuration file. Only available to
+/// admins.
+async fn handle_put(
+    auth: Authentication,
+    client: ClientInfo,
+    State(db): State<PgPool>,
+    State(config): State<Arc<ConfigHandle>>,
+    Json(settings): Json<Settings>,
+) -> error::Result<Response> {
+    if !auth.is_admin {
+        return Ok(StatusCode::FORBIDDEN.into_response());
+    }
+
+    let details = serde_json::json!(settings);
+    match config.update_settings(&db, settings, auth.user_id).await {
+        Ok(()) => {}
+        Err(SettingsError::Database(err)) => return Err(err.into()),
+        Err(err) => return Ok((err.status_code(), err.to_string()).into_response()),
+    }
+
+    info!(?auth.user_id, %details, "Changed settings.");
+    AuditEvent::new(AuditAction::SettingsChanged)
+        .by(&auth)
+        .ip(client.ip)
+        .details(details)
+        .record(&db)
+        .await;
+
+    Ok(Json(config.load().public_config()).into_response())
 }
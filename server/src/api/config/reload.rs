@@ -0,0 +1,55 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::State,
+    response::{IntoResponse, Response},
+    routing::post,
+    Json, Router,
+};
+use http::StatusCode;
+use sqlx::PgPool;
+use tracing::warn;
+
+use crate::{
+    audit::{AuditAction, AuditEvent},
+    auth::Authentication,
+    client::ClientInfo,
+    config::ConfigHandle,
+    error,
+    state::AppState,
+};
+
+pub(super) fn router() -> Router<AppState> {
+    Router::new().route("/", post(handle_post))
+}
+
+/// Read the configuration file again, applying the settings that don't require a restart. Only
+/// available to admins.
+async fn handle_post(
+    auth: Authentication,
+    client: ClientInfo,
+    State(db): State<PgPool>,
+    State(config): State<Arc<ConfigHandle>>,
+) -> error::Result<Response> {
+    if !auth.is_admin {
+        return Ok(StatusCode::FORBIDDEN.into_response());
+    }
+
+    let report = match config.reload().await {
+        Ok(report) => report,
+        Err(err) => {
+            warn!("Could not reload configuration, keeping the current one: {err:#}");
+            let msg = format!("Invalid configuration: {err:#}");
+            return Ok((StatusCode::BAD_REQUEST, msg).into_response());
+        }
+    };
+
+    AuditEvent::new(AuditAction::ConfigReloaded)
+        .by(&auth)
+        .ip(client.ip)
+        .details(serde_json::json!(report))
+        .record(&db)
+        .await;
+
+    Ok(Json(report).into_response())
+}
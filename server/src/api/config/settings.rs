@@ -0,0 +1,28 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::State,
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+use http::StatusCode;
+
+use crate::{auth::Authentication, config::ConfigHandle, error, state::AppState};
+
+pub(super) fn router() -> Router<AppState> {
+    Router::new().route("/", get(handle_get))
+}
+
+/// Get the settings stored in the database, without the values of the configuration file. Only
+/// available to admins.
+async fn handle_get(
+    auth: Authentication,
+    State(config): State<Arc<ConfigHandle>>,
+) -> error::Result<Response> {
+    if !auth.is_admin {
+        return Ok(StatusCode::FORBIDDEN.into_response());
+    }
+
+    Ok(Json(config.settings().await).into_response())
+}
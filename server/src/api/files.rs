@@ -1,45 +1,317 @@
-use std::{env, io, sync::Arc};
+use std::{io, sync::Arc};
 
 use axum::{
-    extract::{Request, State},
+    extract::{Query, Request, State},
     response::{IntoResponse, Response},
-    routing::post,
-    Router,
+    routing::get,
+    Json, Router,
 };
 use futures::TryStreamExt;
 use http::StatusCode;
+use serde::{Deserialize, Serialize};
+use tokio::io::AsyncReadExt;
 use tokio_util::io::StreamReader;
 use tracing::{debug, error, info};
+use uuid::Uuid;
 
-use crate::{auth::Authentication, file::FileDb, state::AppState};
+use crate::{
+    api::{content_length, too_large},
+    audit::{AuditAction, AuditEvent},
+    auth::Authentication,
+    client::ClientInfo,
+    config::Config,
+    file::{normalize_file_name, FileDb, FileFilter, Page, QueryError},
+    folder::{resolve_path, FolderError},
+    rate_limit::{Policy, RateLimiter},
+    shutdown::AcceptingUploads,
+    slug::{check_slug, create_slug, normalize_slug, short_link, SlugError},
+    state::AppState,
+    tag::{add_tags, parse_tags, TagError},
+    team::{member_role, remaining_quota},
+    user,
+};
 
 mod file_id;
 
 pub(super) fn router() -> Router<AppState> {
     Router::new()
         .nest("/:file_id", file_id::router())
-        .route("/", post(handle_post))
+        .route("/", get(handle_get).post(handle_post).delete(handle_delete))
+}
+
+/// List the files of all users. Only available to admins.
+async fn handle_get(
+    auth: Authentication,
+    State(file_store): State<Arc<FileDb>>,
+    Query(filter): Query<FileFilter>,
+    Query(page): Query<Page>,
+) -> crate::error::Result<Response> {
+    if !auth.is_admin {
+        return Ok(StatusCode::FORBIDDEN.into_response());
+    }
+
+    match file_store.list(&filter, &page).await {
+        Ok(files) => Ok(Json(files).into_response()),
+        Err(QueryError::Database(err)) => Err(err.into()),
+        Err(err) => Ok((err.status_code(), err.to_string()).into_response()),
+    }
+}
+
+#[derive(Deserialize)]
+struct DeleteQuery {
+    /// Only count the files that would be removed.
+    #[serde(default)]
+    dry_run: bool,
+}
+
+#[derive(Serialize)]
+struct DeleteSummary {
+    /// The amount of files that were (or would be) removed.
+    count: u64,
+    /// The total size of the files in bytes.
+    total_size: u64,
+    dry_run: bool,
+}
+
+/// Remove all files matching a filter. Only available to admins.
+async fn handle_delete(
+    auth: Authentication,
+    client: ClientInfo,
+    State(file_store): State<Arc<FileDb>>,
+    Query(filter): Query<FileFilter>,
+    Query(options): Query<DeleteQuery>,
+) -> crate::error::Result<Response> {
+    if !auth.is_admin {
+        return Ok(StatusCode::FORBIDDEN.into_response());
+    }
+
+    // Removing every file on the instance is almost certainly a mistake.
+    if filter.is_empty() {
+        return Ok((StatusCode::BAD_REQUEST, "At least one filter must be set").into_response());
+    }
+
+    let result = if options.dry_run {
+        file_store.count_matching(&filter).await
+    } else {
+        file_store.remove_matching(&filter).await
+    };
+    let (count, total_size) = match result {
+        Ok(v) => v,
+        Err(QueryError::Database(err)) => return Err(err.into()),
+        Err(err) => return Ok((err.status_code(), err.to_string()).into_response()),
+    };
+
+    if !options.dry_run {
+        info!(?auth.user_id, ?filter, count, total_size, "Removed files in bulk");
+        AuditEvent::new(AuditAction::FileDeleted)
+            .by(&auth)
+            .ip(client.ip)
+            .details(serde_json::json!({
+                "filter": format!("{filter:?}"),
+                "count": count,
+                "total_size": total_size,
+            }))
+            .record(file_store.db())
+            .await;
+    }
+    Ok(Json(DeleteSummary {
+        count,
+        total_size,
+        dry_run: options.dry_run,
+    })
+    .into_response())
 }
 
 async fn handle_post(
     auth: Authentication,
+    _: AcceptingUploads,
+    client: ClientInfo,
     State(file_store): State<Arc<FileDb>>,
+    State(config): State<Arc<Config>>,
+    State(limiter): State<Arc<RateLimiter>>,
     req: Request,
 ) -> Result<Response, StatusCode> {
+    if let Err(limited) = limiter
+        .check(Policy::Uploads, client.ip, Some(&auth.user_id.to_string()))
+        .await
+    {
+        return Ok(limited.into_response());
+    }
+
     debug!(?auth.user_id, "Started file upload");
 
-    let file_name = req
+    // Header values are not required to be ASCII, so the raw bytes are decoded to allow for
+    // non-ASCII file names.
+    let raw_file_name = req
         .headers()
         .get("file_name")
-        .and_then(|v| v.to_str().ok())
-        .unwrap_or("file")
-        .to_string();
+        .map(|v| std::str::from_utf8(v.as_bytes()))
+        .unwrap_or(Ok("file"));
+    let Ok(raw_file_name) = raw_file_name else {
+        return Ok((StatusCode::BAD_REQUEST, "File name must be valid UTF-8").into_response());
+    };
+
+    let file_name = match normalize_file_name(raw_file_name) {
+        Ok(v) => v,
+        Err(err) => {
+            debug!(?auth.username, "Rejected upload with invalid file name: {err}");
+            let msg = format!("Invalid file name: {err}");
+            return Ok((StatusCode::BAD_REQUEST, msg).into_response());
+        }
+    };
+
+    // A slug can be requested for the file using the `slug` header. An empty value requests a
+    // random slug.
+    let requested_slug = match req.headers().get("slug").map(|v| v.to_str()) {
+        None => None,
+        Some(Ok("")) => Some(None),
+        Some(Ok(slug)) => match normalize_slug(slug) {
+            Ok(slug) => Some(Some(slug)),
+            Err(err) => return Ok((err.status_code(), err.to_string()).into_response()),
+        },
+        Some(Err(_)) => {
+            return Ok((StatusCode::BAD_REQUEST, "Slug must be valid ASCII").into_response())
+        }
+    };
+
+    // Check the slug before receiving the file, so the user doesn't have to wait for the upload
+    // to finish to find out the slug is not available.
+    if let Some(slug) = &requested_slug {
+        let result = async {
+            let mut tx = file_store.db().begin().await?;
+            check_slug(
+                &mut tx,
+                auth.user_id,
+                slug.as_deref(),
+                config.max_slugs_per_user,
+            )
+            .await?;
+            tx.commit().await?;
+            Ok(())
+        }
+        .await;
+        if let Err(err) = result {
+            return Ok(slug_error_response(err));
+        }
+    }
+
+    // Tags can be added using a comma separated `tags` header.
+    let tags = match req
+        .headers()
+        .get("tags")
+        .map(|v| std::str::from_utf8(v.as_bytes()))
+    {
+        None => Vec::new(),
+        Some(Ok(tags)) => match parse_tags(tags) {
+            Ok(tags) => tags,
+            Err(err) => return Ok((err.status_code(), err.to_string()).into_response()),
+        },
+        Some(Err(_)) => {
+            return Ok((StatusCode::BAD_REQUEST, "Tags must be valid UTF-8").into_response())
+        }
+    };
+
+    // The file can be put in a folder using the `folder` header, containing a path such as
+    // `builds/nightly`. Folders that don't exist yet are created.
+    let folder_id = match req
+        .headers()
+        .get("folder")
+        .map(|v| std::str::from_utf8(v.as_bytes()))
+    {
+        None => None,
+        Some(Ok(path)) => {
+            let result = async {
+                let mut tx = file_store.db().begin().await?;
+                let folder_id = resolve_path(&mut tx, auth.user_id, path).await?;
+                tx.commit().await?;
+                Ok(folder_id)
+            }
+            .await;
+            match result {
+                Ok(folder_id) => folder_id,
+                Err(FolderError::Database(err)) => {
+                    error!("Could not resolve folder: {err:?}");
+                    return Err(StatusCode::INTERNAL_SERVER_ERROR);
+                }
+                Err(err) => return Ok((err.status_code(), err.to_string()).into_response()),
+            }
+        }
+        Some(Err(_)) => {
+            return Ok((StatusCode::BAD_REQUEST, "Folder must be valid UTF-8").into_response())
+        }
+    };
 
+    // The file can be owned by a team using the `team` header, containing the ID of the team.
+    let team_id = match req.headers().get("team").map(|v| v.to_str()) {
+        None => None,
+        Some(Ok(team_id)) => match team_id.parse::<Uuid>() {
+            Ok(v) => Some(v),
+            Err(_) => return Ok((StatusCode::BAD_REQUEST, "Invalid team ID").into_response()),
+        },
+        Some(Err(_)) => return Ok((StatusCode::BAD_REQUEST, "Invalid team ID").into_response()),
+    };
+    if let Some(team_id) = team_id {
+        let result = async {
+            let role = member_role(file_store.db(), team_id, auth.user_id).await?;
+            let remaining = remaining_quota(file_store.db(), team_id).await?;
+            Ok::<_, sqlx::Error>((role, remaining))
+        }
+        .await;
+        let (role, remaining) = result.map_err(|err| {
+            error!("Could not check team permissions: {err:?}");
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+        if !(auth.is_admin || role.is_some_and(|v| v.can_manage_files())) {
+            return Ok((
+                StatusCode::FORBIDDEN,
+                "You can not upload files to this team",
+            )
+                .into_response());
+        }
+        if remaining.is_some_and(|v| v <= 0) {
+            return Ok((
+                StatusCode::PAYLOAD_TOO_LARGE,
+                "The team's quota has been reached",
+            )
+                .into_response());
+        }
+    }
+
+    // Files owned by the user count towards their quota, files owned by a team only towards the
+    // quota of the team.
+    let user_quota = config.user_quota().filter(|_| team_id.is_none());
+    let remaining = user::remaining_quota(file_store.db(), auth.user_id, user_quota)
+        .await
+        .map_err(|err| {
+            error!("Could not check quota: {err:?}");
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    if remaining.is_some_and(|v| v <= 0) {
+        let msg = "Your quota has been reached";
+        return Ok((StatusCode::PAYLOAD_TOO_LARGE, msg).into_response());
+    }
+
+    // Reject files that are known to be too large before receiving them.
+    let max_file_size = config.max_file_size();
+    if let (Some(max), Some(length)) = (max_file_size, content_length(req.headers())) {
+        if length > max {
+            return Ok(too_large(max));
+        }
+    }
+
+    // Stop reading one byte after the limit, which is enough to know the file is too large.
+    let limit = max_file_size.map_or(u64::MAX, |v| v.saturating_add(1));
+    let content =
+        StreamReader::new(req.into_body().into_data_stream().map_err(io::Error::other)).take(limit);
     let file = file_store
         .create(
             auth.user_id,
             file_name,
-            StreamReader::new(req.into_body().into_data_stream().map_err(io::Error::other)),
+            folder_id,
+            team_id,
+            config.default_expires_at(),
+            content,
         )
         .await
         .map_err(|err| {
@@ -47,12 +319,117 @@ async fn handle_post(
             StatusCode::INTERNAL_SERVER_ERROR
         })?;
 
+    if let Some(max) = max_file_size.filter(|v| file.file_size > *v) {
+        debug!(?auth.user_id, "Rejected upload exceeding the size limit");
+        if let Err(err) = file_store.remove(file.file_id).await {
+            error!("Could not remove file exceeding the size limit: {err:?}");
+        }
+        return Ok(too_large(max));
+    }
+
     info!(?auth.user_id, ?file.file_name, ?file.file_size, "A file was uploaded");
-    Ok(format!(
-        "{}/files/{}/{}",
-        env::var("EXTERNAL_URL").unwrap(),
-        file.file_id,
-        file.file_name,
-    )
-    .into_response())
+
+    // The size of the file is only known once it has been uploaded, so the quota is checked again.
+    if let Some(team_id) = team_id {
+        let remaining = remaining_quota(file_store.db(), team_id)
+            .await
+            .map_err(|err| {
+                error!("Could not check team quota: {err:?}");
+                StatusCode::INTERNAL_SERVER_ERROR
+            })?;
+        if remaining.is_some_and(|v| v < 0) {
+            if let Err(err) = file_store.remove(file.file_id).await {
+                error!("Could not remove file exceeding the team quota: {err:?}");
+            }
+            return Ok((
+                StatusCode::PAYLOAD_TOO_LARGE,
+                "The file exceeds the team's quota",
+            )
+                .into_response());
+        }
+    }
+    let remaining = user::remaining_quota(file_store.db(), auth.user_id, user_quota)
+        .await
+        .map_err(|err| {
+            error!("Could not check quota: {err:?}");
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    if remaining.is_some_and(|v| v < 0) {
+        if let Err(err) = file_store.remove(file.file_id).await {
+            error!("Could not remove file exceeding the quota: {err:?}");
+        }
+        let msg = "The file exceeds your quota";
+        return Ok((StatusCode::PAYLOAD_TOO_LARGE, msg).into_response());
+    }
+
+    if !tags.is_empty() {
+        let result = async {
+            let mut tx = file_store.db().begin().await?;
+            add_tags(&mut tx, file.file_id, &tags).await?;
+            tx.commit().await?;
+            Ok::<_, TagError>(())
+        }
+        .await;
+        if let Err(err) = result {
+            error!("Could not add tags to file: {err:?}");
+            if let Err(err) = file_store.remove(file.file_id).await {
+                error!("Could not remove file after failing to add tags: {err:?}");
+            }
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    }
+
+    AuditEvent::new(AuditAction::FileUploaded)
+        .by(&auth)
+        .target("file", file.file_id)
+        .ip(client.ip)
+        .details(serde_json::json!({
+            "file_name": file.file_name,
+            "file_size": file.file_size,
+            "team_id": file.team_id,
+        }))
+        .record(file_store.db())
+        .await;
+
+    let external_url = &config.external_url;
+
+    let Some(requested_slug) = requested_slug else {
+        return Ok(file.share_link(external_url).into_response());
+    };
+
+    let result = async {
+        let mut tx = file_store.db().begin().await?;
+        let slug = create_slug(
+            &mut tx,
+            file.file_id,
+            auth.user_id,
+            requested_slug.as_deref(),
+            config.max_slugs_per_user,
+        )
+        .await?;
+        tx.commit().await?;
+        Ok(slug)
+    }
+    .await;
+
+    match result {
+        Ok(slug) => Ok(short_link(external_url, &slug).into_response()),
+        Err(err) => {
+            // The slug may have been taken while the file was being uploaded. The file is removed
+            // again so the user can simply retry.
+            if let Err(err) = file_store.remove(file.file_id).await {
+                error!("Could not remove file after failing to create slug: {err:?}");
+            }
+            Ok(slug_error_response(err))
+        }
+    }
+}
+
+/// Converts a [SlugError] into a response, logging it if it is an internal error.
+fn slug_error_response(err: SlugError) -> Response {
+    if let SlugError::Database(err) = &err {
+        error!("Could not create slug: {err:?}");
+        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    }
+    (err.status_code(), err.to_string()).into_response()
 }
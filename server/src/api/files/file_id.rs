@@ -1,9 +1,124 @@
 mod file_name;
 
-use axum::Router;
+use std::{io, sync::Arc};
 
-use crate::state::AppState;
+use axum::{
+    extract::{Path, Request, State},
+    response::{IntoResponse, Response},
+    routing::put,
+    Router,
+};
+use futures::TryStreamExt;
+use http::StatusCode;
+use tokio::io::AsyncReadExt;
+use tokio_util::io::StreamReader;
+use tracing::info;
+
+use crate::{
+    api::{content_length, too_large},
+    audit::{AuditAction, AuditEvent},
+    auth::Authentication,
+    client::ClientInfo,
+    config::Config,
+    error,
+    file::{ContentTooLarge, FileDb, FileId, QuotaExceeded},
+    rate_limit::{Policy, RateLimiter},
+    shutdown::AcceptingUploads,
+    state::AppState,
+    team::can_modify_file,
+};
 
 pub(super) fn router() -> Router<AppState> {
-    Router::new().nest("/:file_name", file_name::router())
+    Router::new()
+        .nest("/:file_name", file_name::router())
+        .route("/", put(handle_put))
+}
+
+/// Replace the content of a file, keeping the previous content as an older version.
+#[allow(clippy::too_many_arguments)]
+async fn handle_put(
+    auth: Authentication,
+    _: AcceptingUploads,
+    client: ClientInfo,
+    State(file_store): State<Arc<FileDb>>,
+    State(config): State<Arc<Config>>,
+    State(limiter): State<Arc<RateLimiter>>,
+    Path(file_id): Path<FileId>,
+    req: Request,
+) -> error::Result<Response> {
+    if let Err(limited) = limiter
+        .check(Policy::Uploads, client.ip, Some(&auth.user_id.to_string()))
+        .await
+    {
+        return Ok(limited.into_response());
+    }
+
+    // Only those that can modify the file may replace it, as the share link may have been sent
+    // to others.
+    match can_modify_file(file_store.db(), &auth, file_id, None).await? {
+        None => return Ok(StatusCode::NOT_FOUND.into_response()),
+        Some(false) => return Ok(StatusCode::FORBIDDEN.into_response()),
+        Some(true) => {}
+    }
+
+    // Files owned by a team count towards the quota of the team, other files towards the quota
+    // of their uploader.
+    let user_quota = config.user_quota();
+    if file_store
+        .remaining_quota(file_id, user_quota)
+        .await?
+        .is_some_and(|v| v <= 0)
+    {
+        let msg = "The quota of the file's owner has been reached";
+        return Ok((StatusCode::PAYLOAD_TOO_LARGE, msg).into_response());
+    }
+
+    // Reject content that is known to be too large before receiving it.
+    let max_file_size = config.max_file_size();
+    if let (Some(max), Some(length)) = (max_file_size, content_length(req.headers())) {
+        if length > max {
+            return Ok(too_large(max));
+        }
+    }
+
+    // Stop reading one byte after the limit, which is enough to know the content is too large.
+    let limit = max_file_size.map_or(u64::MAX, |v| v.saturating_add(1));
+    let content =
+        StreamReader::new(req.into_body().into_data_stream().map_err(io::Error::other)).take(limit);
+    let result = file_store
+        .replace(
+            file_id,
+            content,
+            config.max_file_versions,
+            max_file_size,
+            user_quota,
+        )
+        .await;
+    let file = match result {
+        Ok(Some(file)) => file,
+        // The file was removed while the new content was being uploaded.
+        Ok(None) => return Ok(StatusCode::NOT_FOUND.into_response()),
+        Err(err) if err.is::<QuotaExceeded>() => {
+            let msg = "The file exceeds the quota of its owner";
+            return Ok((StatusCode::PAYLOAD_TOO_LARGE, msg).into_response());
+        }
+        Err(err) => match err.downcast_ref::<ContentTooLarge>() {
+            Some(ContentTooLarge(max)) => return Ok(too_large(*max)),
+            None => return Err(err.into()),
+        },
+    };
+
+    info!(?auth.user_id, ?file.file_id, ?file.version, ?file.file_size, "A file was replaced");
+    AuditEvent::new(AuditAction::FileUploaded)
+        .by(&auth)
+        .target("file", file.file_id)
+        .ip(client.ip)
+        .details(serde_json::json!({
+            "file_name": file.file_name,
+            "file_size": file.file_size,
+            "version": file.version,
+        }))
+        .record(file_store.db())
+        .await;
+    Ok(file.share_link(&config.external_url).into_response())
 }
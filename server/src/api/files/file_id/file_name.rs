@@ -1,29 +1,123 @@
 mod content;
+mod folder;
+mod preview;
+mod raw;
+mod slugs;
+mod stats;
+mod tags;
+mod team;
+mod versions;
 
 use std::sync::Arc;
 
 use axum::{
-    extract::{Path, State},
-    http::StatusCode,
+    async_trait,
+    extract::{FromRef, FromRequestParts, OriginalUri, Path, State},
+    http::{header, request::Parts, StatusCode},
     response::{IntoResponse, Response},
-    routing::{delete, get},
+    routing::get,
     Json, Router,
 };
-use tracing::{error, info};
+use serde::Deserialize;
+use tracing::{debug, error, info};
 
 use crate::state::AppState;
-use crate::{auth::Authentication, file::FileId, FileDb};
+use crate::{
+    audit::{AuditAction, AuditEvent},
+    auth::Authentication,
+    client::ClientInfo,
+    file::{encode_path_segment, normalize_file_name, FileId},
+    team::can_modify_file,
+    FileDb,
+};
+
+/// The maximum length of a file description in bytes.
+const MAX_DESCRIPTION_LEN: usize = 4096;
 
 pub(super) fn router() -> Router<AppState> {
     Router::new()
         .nest("/content", content::router())
-        .route("/", get(handle_get))
-        .route("/", delete(handle_delete))
+        .nest("/folder", folder::router())
+        .nest("/preview", preview::router())
+        .nest("/raw", raw::router())
+        .nest("/slugs", slugs::router())
+        .nest("/stats", stats::router())
+        .nest("/tags", tags::router())
+        .nest("/team", team::router())
+        .nest("/versions", versions::router())
+        .route(
+            "/",
+            get(handle_get).patch(handle_patch).delete(handle_delete),
+        )
+}
+
+/// The ID and name of a file from the request path.
+///
+/// If the file has been renamed, requests using one of its previous names are redirected to the
+/// same path with the current name, so links that were shared before keep working.
+pub(super) struct FilePath(pub FileId, pub String);
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for FilePath
+where
+    Arc<FileDb>: FromRef<S>,
+{
+    type Rejection = Response;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let Path((file_id, file_name)) = Path::<(FileId, String)>::from_request_parts(parts, state)
+            .await
+            .map_err(IntoResponse::into_response)?;
+
+        let file_store = Arc::<FileDb>::from_ref(state);
+        let current_name = match file_store.renamed_to(file_id, &file_name).await {
+            Ok(Some(v)) => v,
+            Ok(None) => return Ok(FilePath(file_id, file_name)),
+            Err(err) => {
+                error!("Could not look up previous file names: {err:?}");
+                return Err(StatusCode::INTERNAL_SERVER_ERROR.into_response());
+            }
+        };
+
+        // The nested routers only see part of the path, so the location is based on the original
+        // URI. The segment after the file ID is the file name.
+        let uri = OriginalUri::from_request_parts(parts, state)
+            .await
+            .map_err(IntoResponse::into_response)?
+            .0;
+        let mut segments: Vec<String> = uri.path().split('/').map(str::to_string).collect();
+        let Some(name_index) = segments
+            .iter()
+            .position(|v| v.parse::<FileId>().is_ok_and(|id| id == file_id))
+            .map(|i| i + 1)
+            .filter(|i| *i < segments.len())
+        else {
+            return Ok(FilePath(file_id, file_name));
+        };
+        segments[name_index] = encode_path_segment(&current_name);
+
+        let mut location = segments.join("/");
+        if let Some(query) = uri.query() {
+            location = format!("{location}?{query}");
+        }
+
+        debug!(
+            ?file_id,
+            ?file_name,
+            ?current_name,
+            "Redirecting to renamed file"
+        );
+        Err((
+            StatusCode::PERMANENT_REDIRECT,
+            [(header::LOCATION, location)],
+        )
+            .into_response())
+    }
 }
 
 async fn handle_get(
     State(file_store): State<Arc<FileDb>>,
-    Path((file_id, file_name)): Path<(FileId, String)>,
+    FilePath(file_id, file_name): FilePath,
 ) -> Response {
     let file_info = match file_store.file_info(file_id).await {
         Err(err) => {
@@ -43,35 +137,76 @@ async fn handle_get(
     Json(file_info).into_response()
 }
 
-async fn handle_delete(
+#[derive(Deserialize)]
+struct PatchData {
+    /// The new name of the file.
+    file_name: Option<String>,
+    /// The new description of the file. An empty description removes it.
+    description: Option<String>,
+}
+
+/// Rename a file or change its description. Responds with the updated file info.
+async fn handle_patch(
     auth: Authentication,
     State(file_store): State<Arc<FileDb>>,
     Path((file_id, file_name)): Path<(FileId, String)>,
+    Json(data): Json<PatchData>,
 ) -> crate::error::Result<Response> {
-    let mut tx = file_store.db().begin().await?;
+    let new_name = match data.file_name.as_deref().map(normalize_file_name) {
+        None => None,
+        Some(Ok(v)) => Some(v),
+        Some(Err(err)) => {
+            let msg = format!("Invalid file name: {err}");
+            return Ok((StatusCode::BAD_REQUEST, msg).into_response());
+        }
+    };
+    let description = data.description.map(|v| v.trim().to_string());
+    if description
+        .as_ref()
+        .is_some_and(|v| v.len() > MAX_DESCRIPTION_LEN)
+    {
+        let msg = format!("Description must be at most {MAX_DESCRIPTION_LEN} bytes long");
+        return Ok((StatusCode::BAD_REQUEST, msg).into_response());
+    }
 
-    let Some(uploader_id) = sqlx::query!(
-        r#"
-            select uploader_id
-                from files
-                where file_id=$1 and file_name=$2
-        "#,
-        file_id as FileId,
-        file_name
-    )
-    .fetch_optional(&mut *tx)
-    .await?
-    .map(|row| row.uploader_id) else {
-        // The file was not found, return.
-        tx.commit().await?;
+    match can_modify_file(file_store.db(), &auth, file_id, Some(&file_name)).await? {
+        None => return Ok(StatusCode::NOT_FOUND.into_response()),
+        Some(false) => return Ok(StatusCode::FORBIDDEN.into_response()),
+        Some(true) => {}
+    }
+
+    let Some(file) = file_store
+        .update_metadata(file_id, new_name, description)
+        .await?
+    else {
         return Ok(StatusCode::NOT_FOUND.into_response());
     };
 
+    info!(?file_id, ?file_name, ?file.file_name, "Updated file metadata.");
+    Ok(Json(file).into_response())
+}
+
+async fn handle_delete(
+    auth: Authentication,
+    client: ClientInfo,
+    State(file_store): State<Arc<FileDb>>,
+    Path((file_id, file_name)): Path<(FileId, String)>,
+) -> crate::error::Result<Response> {
+    let mut tx = file_store.db().begin().await?;
+
     // The user should only be able to delete a file if they're either the original uploader of the
-    // file or a site-wide admin.
-    if uploader_id != auth.user_id && !auth.is_admin {
-        tx.commit().await?;
-        return Ok(StatusCode::UNAUTHORIZED.into_response());
+    // file, a member of the team that owns it or a site-wide admin.
+    match can_modify_file(&mut *tx, &auth, file_id, Some(&file_name)).await? {
+        None => {
+            // The file was not found, return.
+            tx.commit().await?;
+            return Ok(StatusCode::NOT_FOUND.into_response());
+        }
+        Some(false) => {
+            tx.commit().await?;
+            return Ok(StatusCode::UNAUTHORIZED.into_response());
+        }
+        Some(true) => {}
     }
 
     info!(?file_id, "Deleting file.");
@@ -83,5 +218,29 @@ async fn handle_delete(
     file_store.file_store().remove(file_id).await?;
     tx.commit().await?;
 
+    AuditEvent::new(AuditAction::FileDeleted)
+        .by(&auth)
+        .target("file", file_id)
+        .ip(client.ip)
+        .details(serde_json::json!({ "file_name": file_name }))
+        .record(file_store.db())
+        .await;
+
     Ok(().into_response())
 }
+
+/// Returns the uploader of a file, or `None` if the file does not exist.
+pub(super) async fn file_uploader(
+    db: impl sqlx::PgExecutor<'_>,
+    file_id: FileId,
+    file_name: &str,
+) -> sqlx::Result<Option<uuid::Uuid>> {
+    Ok(sqlx::query!(
+        "select uploader_id from files where file_id=$1 and file_name=$2",
+        file_id as FileId,
+        file_name,
+    )
+    .fetch_optional(db)
+    .await?
+    .map(|row| row.uploader_id))
+}
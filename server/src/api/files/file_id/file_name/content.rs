@@ -2,29 +2,67 @@ use std::sync::Arc;
 
 use axum::{
     body::Body,
-    extract::{Path, State},
-    response::Response,
+    extract::{Query, State},
+    response::{IntoResponse, Response},
     routing::get,
     Router,
 };
-use http::{HeaderName, StatusCode};
+use http::{HeaderName, Method, StatusCode};
+use serde::Deserialize;
 use tokio_util::io::ReaderStream;
 use tracing::error;
 
 use crate::{
-    file::{FileDb, FileId},
+    audit::{AuditAction, AuditEvent},
+    auth::Authentication,
+    client::ClientInfo,
+    file::{content_disposition, FileDb},
+    rate_limit::{Policy, RateLimiter},
     state::AppState,
+    stats::TrackedDownload,
 };
 
+use super::FilePath;
+
 pub(super) fn router() -> Router<AppState> {
     Router::new().route("/", get(handle_get))
 }
 
+#[derive(Deserialize)]
+struct ContentQuery {
+    /// Serve an older version of the file instead of the latest one.
+    version: Option<i32>,
+}
+
 async fn handle_get(
+    method: Method,
+    auth: Option<Authentication>,
+    client: ClientInfo,
     State(file_store): State<Arc<FileDb>>,
-    Path((file_id, file_name)): Path<(FileId, String)>,
+    State(limiter): State<Arc<RateLimiter>>,
+    FilePath(file_id, file_name): FilePath,
+    Query(query): Query<ContentQuery>,
 ) -> Response {
-    let resp_stream = match file_store.content(file_id, &file_name).await {
+    if let Err(limited) = limiter
+        .check(
+            Policy::Downloads,
+            client.ip,
+            auth.as_ref().map(|v| v.user_id.to_string()).as_deref(),
+        )
+        .await
+    {
+        return limited.into_response();
+    }
+
+    let content = match query.version {
+        Some(version) => {
+            file_store
+                .content_version(file_id, &file_name, version)
+                .await
+        }
+        None => file_store.content(file_id, &file_name).await,
+    };
+    let resp_stream = match content {
         Ok(Some(v)) => v,
         Ok(None) => {
             return Response::builder()
@@ -41,15 +79,46 @@ async fn handle_get(
         }
     };
 
+    let file_size = match resp_stream.metadata().await {
+        Ok(metadata) => metadata.len(),
+        Err(err) => {
+            error!("Could not get size of file: {err}");
+            return Response::builder()
+                .status(StatusCode::INTERNAL_SERVER_ERROR)
+                .body(Body::empty())
+                .unwrap();
+        }
+    };
+
+    // The body of a response to a `HEAD` request isn't sent, so the file isn't downloaded.
+    if method != Method::HEAD {
+        let mut event = AuditEvent::new(AuditAction::FileDownloaded)
+            .target("file", file_id)
+            .ip(client.ip);
+        if let Some(version) = query.version {
+            event = event.details(serde_json::json!({ "version": version }));
+        }
+        if let Some(auth) = &auth {
+            event = event.by(auth);
+        }
+        event.record(file_store.db()).await;
+    }
+
     Response::builder()
         .header(
             HeaderName::from_static("content-disposition"),
-            format!("attachment; filename=\"{file_name}\""),
+            content_disposition("attachment", &file_name),
         )
         .header(
             HeaderName::from_static("content-type"),
             "application/octet-stream",
         )
-        .body(Body::from_stream(ReaderStream::new(resp_stream)))
+        .body(Body::from_stream(TrackedDownload::new(
+            ReaderStream::new(resp_stream),
+            file_store.db().clone(),
+            file_id,
+            auth.map(|v| v.user_id),
+            file_size,
+        )))
         .unwrap()
 }
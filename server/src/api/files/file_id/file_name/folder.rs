@@ -0,0 +1,72 @@
+use axum::{
+    extract::{Path, State},
+    response::{IntoResponse, Response},
+    routing::put,
+    Json, Router,
+};
+use http::StatusCode;
+use serde::Deserialize;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    auth::Authentication,
+    error,
+    file::FileId,
+    folder::{check_folder, FolderError},
+    state::AppState,
+};
+
+use super::file_uploader;
+
+pub(super) fn router() -> Router<AppState> {
+    Router::new().route("/", put(handle_put))
+}
+
+#[derive(Deserialize)]
+struct PutData {
+    /// The folder to move the file into, or `None` to move it to the top level.
+    folder_id: Option<Uuid>,
+}
+
+/// Move a file into another folder.
+async fn handle_put(
+    auth: Authentication,
+    State(db): State<PgPool>,
+    Path((file_id, file_name)): Path<(FileId, String)>,
+    Json(data): Json<PutData>,
+) -> error::Result<Response> {
+    let mut tx = db.begin().await?;
+
+    let Some(uploader_id) = file_uploader(&mut *tx, file_id, &file_name).await? else {
+        tx.commit().await?;
+        return Ok(StatusCode::NOT_FOUND.into_response());
+    };
+    if uploader_id != auth.user_id && !auth.is_admin {
+        tx.commit().await?;
+        return Ok(StatusCode::FORBIDDEN.into_response());
+    }
+
+    // Files can only be put in folders of their uploader.
+    if let Some(folder_id) = data.folder_id {
+        match check_folder(&mut *tx, uploader_id, folder_id).await {
+            Ok(()) => {}
+            Err(FolderError::Database(err)) => return Err(err.into()),
+            Err(err) => {
+                tx.commit().await?;
+                return Ok((err.status_code(), err.to_string()).into_response());
+            }
+        }
+    }
+
+    sqlx::query!(
+        "update files set folder_id=$2 where file_id=$1",
+        file_id as FileId,
+        data.folder_id,
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(().into_response())
+}
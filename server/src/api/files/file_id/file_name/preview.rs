@@ -0,0 +1,52 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::State,
+    response::{IntoResponse, Response},
+    routing::get,
+    Router,
+};
+use http::{header, HeaderMap, HeaderValue, StatusCode};
+use tracing::error;
+
+use crate::{file::FileDb, preview::PreviewKind, state::AppState};
+
+use super::FilePath;
+
+pub(super) fn router() -> Router<AppState> {
+    Router::new().route("/", get(handle_get))
+}
+
+/// Get a thumbnail or a highlighted excerpt of a file.
+async fn handle_get(
+    State(file_store): State<Arc<FileDb>>,
+    FilePath(file_id, file_name): FilePath,
+) -> Response {
+    let (kind, preview) = match file_store.preview(file_id, &file_name).await {
+        Ok(Some(v)) => v,
+        Ok(None) => return StatusCode::NOT_FOUND.into_response(),
+        Err(err) => {
+            error!("Could not get preview for file: {err:?}");
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static(kind.content_type()),
+    );
+    headers.insert(
+        header::X_CONTENT_TYPE_OPTIONS,
+        HeaderValue::from_static("nosniff"),
+    );
+    if kind == PreviewKind::Html {
+        // The excerpt only uses inline styles, anything else should never be loaded.
+        headers.insert(
+            header::CONTENT_SECURITY_POLICY,
+            HeaderValue::from_static("default-src 'none'; style-src 'unsafe-inline'"),
+        );
+    }
+
+    (headers, preview).into_response()
+}
@@ -0,0 +1,102 @@
+use std::sync::Arc;
+
+use axum::{
+    body::Body,
+    extract::State,
+    response::{IntoResponse, Response},
+    routing::get,
+    Router,
+};
+use http::{header, HeaderValue, Method, StatusCode};
+use tokio_util::io::ReaderStream;
+use tracing::error;
+
+use crate::{
+    audit::{AuditAction, AuditEvent},
+    auth::Authentication,
+    client::ClientInfo,
+    file::{content_disposition, FileDb},
+    rate_limit::{Policy, RateLimiter},
+    state::AppState,
+    stats::TrackedDownload,
+};
+
+use super::FilePath;
+
+pub(super) fn router() -> Router<AppState> {
+    Router::new().route("/", get(handle_get))
+}
+
+/// Serve the content of a file as plain text, so it can be viewed directly in a browser.
+async fn handle_get(
+    method: Method,
+    auth: Option<Authentication>,
+    client: ClientInfo,
+    State(file_store): State<Arc<FileDb>>,
+    State(limiter): State<Arc<RateLimiter>>,
+    FilePath(file_id, file_name): FilePath,
+) -> Response {
+    if let Err(limited) = limiter
+        .check(
+            Policy::Downloads,
+            client.ip,
+            auth.as_ref().map(|v| v.user_id.to_string()).as_deref(),
+        )
+        .await
+    {
+        return limited.into_response();
+    }
+
+    let resp_stream = match file_store.content(file_id, &file_name).await {
+        Ok(Some(v)) => v,
+        Ok(None) => return StatusCode::NOT_FOUND.into_response(),
+        Err(err) => {
+            error!("Could not get file from file store: {err}");
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+
+    let file_size = match resp_stream.metadata().await {
+        Ok(metadata) => metadata.len(),
+        Err(err) => {
+            error!("Could not get size of file: {err}");
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+
+    // The body of a response to a `HEAD` request isn't sent, so the file isn't downloaded.
+    if method != Method::HEAD {
+        let mut event = AuditEvent::new(AuditAction::FileDownloaded)
+            .target("file", file_id)
+            .ip(client.ip);
+        if let Some(auth) = &auth {
+            event = event.by(auth);
+        }
+        event.record(file_store.db()).await;
+    }
+
+    (
+        [
+            (
+                header::CONTENT_TYPE,
+                HeaderValue::from_static("text/plain; charset=utf-8"),
+            ),
+            (
+                header::X_CONTENT_TYPE_OPTIONS,
+                HeaderValue::from_static("nosniff"),
+            ),
+            (
+                header::CONTENT_DISPOSITION,
+                content_disposition("inline", &file_name),
+            ),
+        ],
+        Body::from_stream(TrackedDownload::new(
+            ReaderStream::new(resp_stream),
+            file_store.db().clone(),
+            file_id,
+            auth.map(|v| v.user_id),
+            file_size,
+        )),
+    )
+        .into_response()
+}
@@ -0,0 +1,105 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, State},
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+use chrono::{serde::ts_milliseconds, DateTime, Utc};
+use http::StatusCode;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+
+use crate::{
+    auth::Authentication,
+    config::Config,
+    error,
+    file::FileId,
+    slug::{create_slug, short_link, SlugError},
+    state::AppState,
+    team::can_modify_file,
+};
+
+pub(super) fn router() -> Router<AppState> {
+    Router::new().route("/", get(handle_get).post(handle_post))
+}
+
+#[derive(Serialize)]
+struct SlugInfo {
+    slug: String,
+    #[serde(with = "ts_milliseconds")]
+    created_at: DateTime<Utc>,
+}
+
+/// List the slugs that resolve to a file.
+async fn handle_get(
+    auth: Authentication,
+    State(db): State<PgPool>,
+    Path((file_id, file_name)): Path<(FileId, String)>,
+) -> error::Result<Response> {
+    match can_modify_file(&db, &auth, file_id, Some(&file_name)).await? {
+        None => return Ok(StatusCode::NOT_FOUND.into_response()),
+        Some(false) => return Ok(StatusCode::FORBIDDEN.into_response()),
+        Some(true) => {}
+    }
+
+    let slugs = sqlx::query_as!(
+        SlugInfo,
+        "select slug, created_at from slugs where file_id=$1 order by created_at",
+        file_id as FileId,
+    )
+    .fetch_all(&db)
+    .await?;
+
+    Ok(Json(slugs).into_response())
+}
+
+#[derive(Deserialize)]
+struct PostData {
+    /// The requested slug. A random slug is generated if this is not set.
+    slug: Option<String>,
+}
+
+/// Create a new slug for a file. Responds with the short link.
+async fn handle_post(
+    auth: Authentication,
+    State(db): State<PgPool>,
+    State(config): State<Arc<Config>>,
+    Path((file_id, file_name)): Path<(FileId, String)>,
+    Json(data): Json<PostData>,
+) -> error::Result<Response> {
+    let mut tx = db.begin().await?;
+
+    match can_modify_file(&mut *tx, &auth, file_id, Some(&file_name)).await? {
+        None => {
+            tx.commit().await?;
+            return Ok(StatusCode::NOT_FOUND.into_response());
+        }
+        Some(false) => {
+            tx.commit().await?;
+            return Ok(StatusCode::FORBIDDEN.into_response());
+        }
+        Some(true) => {}
+    }
+
+    let result = create_slug(
+        &mut tx,
+        file_id,
+        auth.user_id,
+        data.slug.as_deref(),
+        config.max_slugs_per_user,
+    )
+    .await;
+    let slug = match result {
+        Ok(slug) => slug,
+        Err(SlugError::Database(err)) => return Err(err.into()),
+        Err(err) => {
+            tx.rollback().await?;
+            return Ok((err.status_code(), err.to_string()).into_response());
+        }
+    };
+
+    tx.commit().await?;
+    Ok(short_link(&config.external_url, &slug).into_response())
+}
@@ -0,0 +1,32 @@
+use axum::{
+    extract::{Path, State},
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+use http::StatusCode;
+use sqlx::PgPool;
+
+use crate::{
+    auth::Authentication, error, file::FileId, state::AppState, stats::file_stats,
+    team::can_modify_file,
+};
+
+pub(super) fn router() -> Router<AppState> {
+    Router::new().route("/", get(handle_get))
+}
+
+/// Get how often a file was downloaded, including whether recent downloads were completed.
+async fn handle_get(
+    auth: Authentication,
+    State(db): State<PgPool>,
+    Path((file_id, file_name)): Path<(FileId, String)>,
+) -> error::Result<Response> {
+    match can_modify_file(&db, &auth, file_id, Some(&file_name)).await? {
+        None => return Ok(StatusCode::NOT_FOUND.into_response()),
+        Some(false) => return Ok(StatusCode::FORBIDDEN.into_response()),
+        Some(true) => {}
+    }
+
+    Ok(Json(file_stats(&db, file_id).await?).into_response())
+}
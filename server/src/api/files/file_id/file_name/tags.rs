@@ -0,0 +1,86 @@
+mod tag;
+
+use axum::{
+    extract::{Path, State},
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+use http::StatusCode;
+use serde::Deserialize;
+use sqlx::PgPool;
+
+use crate::{
+    auth::Authentication,
+    error,
+    file::FileId,
+    state::AppState,
+    tag::{add_tags, file_tags, normalize_tag, TagError},
+    team::can_modify_file,
+};
+
+pub(super) fn router() -> Router<AppState> {
+    Router::new()
+        .nest("/:tag", tag::router())
+        .route("/", get(handle_get).post(handle_post))
+}
+
+/// List the tags of a file.
+async fn handle_get(
+    auth: Authentication,
+    State(db): State<PgPool>,
+    Path((file_id, file_name)): Path<(FileId, String)>,
+) -> error::Result<Response> {
+    match can_modify_file(&db, &auth, file_id, Some(&file_name)).await? {
+        None => return Ok(StatusCode::NOT_FOUND.into_response()),
+        Some(false) => return Ok(StatusCode::FORBIDDEN.into_response()),
+        Some(true) => {}
+    }
+
+    Ok(Json(file_tags(&db, file_id).await?).into_response())
+}
+
+#[derive(Deserialize)]
+struct PostData {
+    tag: String,
+}
+
+/// Add a tag to a file. Responds with all tags of the file.
+async fn handle_post(
+    auth: Authentication,
+    State(db): State<PgPool>,
+    Path((file_id, file_name)): Path<(FileId, String)>,
+    Json(data): Json<PostData>,
+) -> error::Result<Response> {
+    let tag = match normalize_tag(&data.tag) {
+        Ok(v) => v,
+        Err(err) => return Ok((err.status_code(), err.to_string()).into_response()),
+    };
+
+    let mut tx = db.begin().await?;
+
+    match can_modify_file(&mut *tx, &auth, file_id, Some(&file_name)).await? {
+        None => {
+            tx.commit().await?;
+            return Ok(StatusCode::NOT_FOUND.into_response());
+        }
+        Some(false) => {
+            tx.commit().await?;
+            return Ok(StatusCode::FORBIDDEN.into_response());
+        }
+        Some(true) => {}
+    }
+
+    match add_tags(&mut tx, file_id, &[tag]).await {
+        Ok(()) => {}
+        Err(TagError::Database(err)) => return Err(err.into()),
+        Err(err) => {
+            tx.rollback().await?;
+            return Ok((err.status_code(), err.to_string()).into_response());
+        }
+    }
+
+    let tags = file_tags(&mut *tx, file_id).await?;
+    tx.commit().await?;
+    Ok(Json(tags).into_response())
+}
@@ -0,0 +1,40 @@
+use axum::{
+    extract::{Path, State},
+    response::{IntoResponse, Response},
+    routing::delete,
+    Router,
+};
+use http::StatusCode;
+use sqlx::PgPool;
+
+use crate::{auth::Authentication, error, file::FileId, state::AppState, team::can_modify_file};
+
+pub(super) fn router() -> Router<AppState> {
+    Router::new().route("/", delete(handle_delete))
+}
+
+/// Remove a tag from a file.
+async fn handle_delete(
+    auth: Authentication,
+    State(db): State<PgPool>,
+    Path((file_id, file_name, tag)): Path<(FileId, String, String)>,
+) -> error::Result<Response> {
+    match can_modify_file(&db, &auth, file_id, Some(&file_name)).await? {
+        None => return Ok(StatusCode::NOT_FOUND.into_response()),
+        Some(false) => return Ok(StatusCode::FORBIDDEN.into_response()),
+        Some(true) => {}
+    }
+
+    let result = sqlx::query!(
+        "delete from file_tags where file_id=$1 and tag=$2",
+        file_id as FileId,
+        tag.trim().to_lowercase(),
+    )
+    .execute(&db)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Ok(StatusCode::NOT_FOUND.into_response());
+    }
+    Ok(().into_response())
+}
@@ -0,0 +1,95 @@
+use axum::{
+    extract::{Path, State},
+    response::{IntoResponse, Response},
+    routing::put,
+    Json, Router,
+};
+use http::StatusCode;
+use serde::Deserialize;
+use sqlx::PgPool;
+use tracing::info;
+use uuid::Uuid;
+
+use crate::{
+    auth::Authentication,
+    error,
+    file::FileId,
+    state::AppState,
+    team::{can_modify_file, member_role, remaining_quota},
+};
+
+pub(super) fn router() -> Router<AppState> {
+    Router::new().route("/", put(handle_put))
+}
+
+#[derive(Deserialize)]
+struct PutData {
+    /// The team that should own the file, or `None` to make it a personal file of its uploader.
+    team_id: Option<Uuid>,
+}
+
+/// Transfer a file to a team, or back to its uploader.
+async fn handle_put(
+    auth: Authentication,
+    State(db): State<PgPool>,
+    Path((file_id, file_name)): Path<(FileId, String)>,
+    Json(data): Json<PutData>,
+) -> error::Result<Response> {
+    let mut tx = db.begin().await?;
+
+    match can_modify_file(&mut *tx, &auth, file_id, Some(&file_name)).await? {
+        None => {
+            tx.commit().await?;
+            return Ok(StatusCode::NOT_FOUND.into_response());
+        }
+        Some(false) => {
+            tx.commit().await?;
+            return Ok(StatusCode::FORBIDDEN.into_response());
+        }
+        Some(true) => {}
+    }
+
+    if let Some(team_id) = data.team_id {
+        let role = member_role(&mut *tx, team_id, auth.user_id).await?;
+        if !(auth.is_admin || role.is_some_and(|v| v.can_manage_files())) {
+            tx.commit().await?;
+            return Ok((
+                StatusCode::FORBIDDEN,
+                "You can not transfer files to this team",
+            )
+                .into_response());
+        }
+    }
+
+    let result = sqlx::query!(
+        "update files set team_id=$2 where file_id=$1",
+        file_id as FileId,
+        data.team_id,
+    )
+    .execute(&mut *tx)
+    .await;
+    match result {
+        Ok(_) => {}
+        Err(sqlx::Error::Database(err)) if err.is_foreign_key_violation() => {
+            return Ok(StatusCode::NOT_FOUND.into_response());
+        }
+        Err(err) => return Err(err.into()),
+    }
+
+    if let Some(team_id) = data.team_id {
+        if remaining_quota(&mut *tx, team_id)
+            .await?
+            .is_some_and(|v| v < 0)
+        {
+            return Ok((
+                StatusCode::PAYLOAD_TOO_LARGE,
+                "The file exceeds the team's quota",
+            )
+                .into_response());
+        }
+    }
+
+    tx.commit().await?;
+    info!(?file_id, ?data.team_id, "Transferred file.");
+    Ok(().into_response())
+}
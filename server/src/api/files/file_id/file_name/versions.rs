@@ -0,0 +1,67 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, State},
+    response::{IntoResponse, Response},
+    routing::{delete, get},
+    Json, Router,
+};
+use http::StatusCode;
+use tracing::info;
+
+use crate::{
+    audit::{AuditAction, AuditEvent},
+    auth::Authentication,
+    client::ClientInfo,
+    error,
+    file::{FileDb, FileId},
+    state::AppState,
+    team::can_modify_file,
+};
+
+use super::FilePath;
+
+pub(super) fn router() -> Router<AppState> {
+    Router::new()
+        .route("/", get(handle_get))
+        .route("/:version", delete(handle_delete))
+}
+
+/// List all versions of a file, oldest first.
+async fn handle_get(
+    State(file_store): State<Arc<FileDb>>,
+    FilePath(file_id, file_name): FilePath,
+) -> error::Result<Response> {
+    match file_store.versions(file_id, &file_name).await? {
+        Some(versions) => Ok(Json(versions).into_response()),
+        None => Ok(StatusCode::NOT_FOUND.into_response()),
+    }
+}
+
+/// Permanently remove an older version of a file.
+async fn handle_delete(
+    auth: Authentication,
+    client: ClientInfo,
+    State(file_store): State<Arc<FileDb>>,
+    Path((file_id, file_name, version)): Path<(FileId, String, i32)>,
+) -> error::Result<Response> {
+    match can_modify_file(file_store.db(), &auth, file_id, Some(&file_name)).await? {
+        None => return Ok(StatusCode::NOT_FOUND.into_response()),
+        Some(false) => return Ok(StatusCode::FORBIDDEN.into_response()),
+        Some(true) => {}
+    }
+
+    if !file_store.remove_version(file_id, version).await? {
+        return Ok(StatusCode::NOT_FOUND.into_response());
+    }
+
+    info!(?file_id, version, "Removed file version.");
+    AuditEvent::new(AuditAction::FileDeleted)
+        .by(&auth)
+        .target("file", file_id)
+        .ip(client.ip)
+        .details(serde_json::json!({ "file_name": file_name, "version": version }))
+        .record(file_store.db())
+        .await;
+    Ok(().into_response())
+}
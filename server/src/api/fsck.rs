@@ -0,0 +1,39 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::State,
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+use http::StatusCode;
+
+use crate::{auth::Authentication, error, file::FileDb, fsck, state::AppState};
+
+pub(super) fn router() -> Router<AppState> {
+    Router::new().route("/", get(handle_get).post(handle_post))
+}
+
+/// Check the file store against the database without changing anything.
+async fn handle_get(
+    auth: Authentication,
+    State(file_store): State<Arc<FileDb>>,
+) -> error::Result<Response> {
+    if !auth.is_admin {
+        return Ok(StatusCode::FORBIDDEN.into_response());
+    }
+
+    Ok(Json(fsck::check(&file_store, false).await?).into_response())
+}
+
+/// Check the file store against the database and repair the problems that were found.
+async fn handle_post(
+    auth: Authentication,
+    State(file_store): State<Arc<FileDb>>,
+) -> error::Result<Response> {
+    if !auth.is_admin {
+        return Ok(StatusCode::FORBIDDEN.into_response());
+    }
+
+    Ok(Json(fsck::check(&file_store, true).await?).into_response())
+}
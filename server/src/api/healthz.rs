@@ -0,0 +1,13 @@
+use axum::{routing::get, Json, Router};
+use serde_json::{json, Value};
+
+use crate::state::AppState;
+
+pub(super) fn router() -> Router<AppState> {
+    Router::new().route("/", get(handle_get))
+}
+
+/// Report that the server is alive. This doesn't check any dependencies, see `/readyz` for that.
+async fn handle_get() -> Json<Value> {
+    Json(json!({ "status": "ok" }))
+}
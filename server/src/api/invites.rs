@@ -9,7 +9,14 @@ use http::StatusCode;
 use serde::Deserialize;
 use sqlx::PgPool;
 
-use crate::{auth::Authentication, error, state::AppState};
+use crate::{
+    audit::{AuditAction, AuditEvent},
+    auth::Authentication,
+    client::ClientInfo,
+    error,
+    invite::create_invite,
+    state::AppState,
+};
 
 pub(super) fn router() -> Router<AppState> {
     Router::new().route("/", post(handle_post))
@@ -26,6 +33,7 @@ struct PostData {
 /// Create a new invite code.
 async fn handle_post(
     auth: Authentication,
+    client: ClientInfo,
     State(db): State<PgPool>,
     Json(data): Json<PostData>,
 ) -> error::Result<Response> {
@@ -43,33 +51,25 @@ async fn handle_post(
     }
     let expires_on = Utc::now() + Duration::seconds(data.valid_for as i64);
 
-    let mut tx = db.begin().await?;
-
-    let exists = sqlx::query!(
-        r#"select exists(select * from invites where invite=$1) as "exists!: bool""#,
+    if !create_invite(
+        &db,
         &data.invite_code,
+        data.max_uses,
+        expires_on,
+        auth.user_id,
     )
-    .fetch_one(&mut *tx)
-    .await?;
-
-    if exists.exists {
-        tx.commit().await?;
+    .await?
+    {
         return Ok(StatusCode::CONFLICT.into_response());
     }
 
-    sqlx::query!(
-        r#"
-            insert into invites (invite, max_uses, valid_until, created_by)
-                values ($1, $2, $3, $4)
-        "#,
-        data.invite_code,
-        data.max_uses as i32,
-        expires_on,
-        auth.user_id,
-    )
-    .execute(&mut *tx)
-    .await?;
+    AuditEvent::new(AuditAction::InviteCreated)
+        .by(&auth)
+        .target("invite", &data.invite_code)
+        .ip(client.ip)
+        .details(serde_json::json!({ "max_uses": data.max_uses, "valid_until": expires_on }))
+        .record(&db)
+        .await;
 
-    tx.commit().await?;
     Ok(().into_response())
 }
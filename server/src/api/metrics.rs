@@ -0,0 +1,43 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::State,
+    response::{IntoResponse, Response},
+    routing::get,
+    Router,
+};
+use http::header;
+use num_traits::ToPrimitive;
+use sqlx::PgPool;
+
+use crate::{auth::ssh::SSHAuthState, error, metrics::METRICS, state::AppState};
+
+pub(super) fn router() -> Router<AppState> {
+    Router::new().route("/", get(handle_get))
+}
+
+/// Export the metrics of the server in the Prometheus text format.
+async fn handle_get(
+    State(db): State<PgPool>,
+    State(ssh): State<Arc<SSHAuthState>>,
+) -> error::Result<Response> {
+    // Gauges that describe the current state are updated when they are scraped.
+    let storage = sqlx::query!(
+        r#"select count(*) as "file_count!", sum(file_size) as "total_size" from files"#
+    )
+    .fetch_one(&db)
+    .await?;
+    METRICS.stored_files.set(storage.file_count);
+    METRICS
+        .storage_bytes
+        .set(storage.total_size.and_then(|v| v.to_i64()).unwrap_or(0));
+    METRICS.db_connections.set(db.size().into());
+    METRICS.db_connections_idle.set(db.num_idle() as i64);
+    METRICS.ssh_tickets.set(ssh.ticket_count().await as i64);
+
+    Ok((
+        [(header::CONTENT_TYPE, prometheus::TEXT_FORMAT)],
+        METRICS.encode(),
+    )
+        .into_response())
+}
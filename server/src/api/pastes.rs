@@ -0,0 +1,127 @@
+use std::{io::Cursor, sync::Arc};
+
+use anyhow::Context;
+use axum::{
+    extract::State,
+    response::{IntoResponse, Response},
+    routing::post,
+    Json, Router,
+};
+use chrono::{Duration, Utc};
+use http::StatusCode;
+use serde::Deserialize;
+use tracing::info;
+
+use crate::{
+    api::too_large,
+    audit::{AuditAction, AuditEvent},
+    auth::Authentication,
+    client::ClientInfo,
+    config::Config,
+    error,
+    file::FileDb,
+    preview,
+    rate_limit::{Policy, RateLimiter},
+    shutdown::AcceptingUploads,
+    state::AppState,
+    user::remaining_quota,
+};
+
+pub(super) fn router() -> Router<AppState> {
+    Router::new().route("/", post(handle_post))
+}
+
+#[derive(Deserialize)]
+struct PostData {
+    /// The text to share.
+    content: String,
+    /// The language of the text, used for syntax highlighting. This can either be the name of the
+    /// language or a file extension.
+    language: Option<String>,
+    /// The amount of seconds after which the paste should no longer be available.
+    expires_in: Option<u32>,
+}
+
+/// Share a snippet of text. Responds with the link to the paste.
+async fn handle_post(
+    auth: Authentication,
+    _: AcceptingUploads,
+    client: ClientInfo,
+    State(file_db): State<Arc<FileDb>>,
+    State(config): State<Arc<Config>>,
+    State(limiter): State<Arc<RateLimiter>>,
+    Json(data): Json<PostData>,
+) -> error::Result<Response> {
+    if let Err(limited) = limiter
+        .check(Policy::Uploads, client.ip, Some(&auth.user_id.to_string()))
+        .await
+    {
+        return Ok(limited.into_response());
+    }
+    if data.content.is_empty() {
+        return Ok((StatusCode::BAD_REQUEST, "A paste can not be empty").into_response());
+    }
+
+    let extension = match &data.language {
+        Some(language) => {
+            let Some(extension) = preview::extension_for_language(language) else {
+                let msg = format!("Unknown language `{language}`");
+                return Ok((StatusCode::BAD_REQUEST, msg).into_response());
+            };
+            extension
+        }
+        None => "txt",
+    };
+
+    let expires_at = match data.expires_in {
+        Some(expires_in) => {
+            let expires_in = Duration::seconds(expires_in as i64);
+            if expires_in > Duration::days(365) || expires_in <= Duration::zero() {
+                return Ok((
+                    StatusCode::BAD_REQUEST,
+                    "`expires_in` field must be between 0 and 365 days (in seconds)",
+                )
+                    .into_response());
+            }
+            Some(Utc::now() + expires_in)
+        }
+        None => config.default_expires_at(),
+    };
+
+    let size = data.content.len() as u64;
+    if let Some(max) = config.max_file_size().filter(|v| size > *v) {
+        return Ok(too_large(max));
+    }
+    if remaining_quota(file_db.db(), auth.user_id, config.user_quota())
+        .await?
+        .is_some_and(|v| v < size as i64)
+    {
+        let msg = "The paste exceeds your quota";
+        return Ok((StatusCode::PAYLOAD_TOO_LARGE, msg).into_response());
+    }
+
+    let file = file_db
+        .create(
+            auth.user_id,
+            format!("paste.{extension}"),
+            None,
+            None,
+            expires_at,
+            Cursor::new(data.content.into_bytes()),
+        )
+        .await
+        .context("could not store paste")?;
+
+    info!(?auth.user_id, ?file.file_id, ?file.file_size, "A paste was created");
+    AuditEvent::new(AuditAction::FileUploaded)
+        .by(&auth)
+        .target("file", file.file_id)
+        .ip(client.ip)
+        .details(serde_json::json!({
+            "file_name": file.file_name,
+            "file_size": file.file_size,
+        }))
+        .record(file_db.db())
+        .await;
+    Ok(file.share_link(&config.external_url).into_response())
+}
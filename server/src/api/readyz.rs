@@ -0,0 +1,38 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::State,
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+use http::StatusCode;
+
+use crate::{config::Config, file::FileDb, health::readiness, shutdown::Shutdown, state::AppState};
+
+pub(super) fn router() -> Router<AppState> {
+    Router::new().route("/", get(handle_get))
+}
+
+/// Check if the server is ready to handle requests. Responds with the result of every check,
+/// using SERVICE_UNAVAILABLE if any of them failed or the server is shutting down.
+async fn handle_get(
+    State(file_store): State<Arc<FileDb>>,
+    State(config): State<Arc<Config>>,
+    State(shutdown): State<Shutdown>,
+) -> Response {
+    let readiness = readiness(
+        file_store.db(),
+        file_store.file_store(),
+        config.min_free_space,
+        shutdown.is_draining(),
+    )
+    .await;
+
+    let status = if readiness.ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (status, Json(readiness)).into_response()
+}
@@ -0,0 +1,125 @@
+use std::sync::Arc;
+
+use anyhow::Context;
+use axum::{
+    extract::State,
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+use chrono::Duration;
+use http::StatusCode;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use tower_cookies::Cookies;
+use tracing::{info, warn};
+
+use crate::{
+    audit::{AuditAction, AuditEvent},
+    client::ClientInfo,
+    config::Config,
+    error,
+    metrics::METRICS,
+    rate_limit::{Policy, RateLimiter},
+    session::{create_session, store_session},
+    setup::SetupState,
+    state::AppState,
+    user::{admin_exists, create_user, hash_password},
+};
+
+pub(super) fn router() -> Router<AppState> {
+    Router::new().route("/", get(handle_get).post(handle_post))
+}
+
+#[derive(Serialize)]
+struct SetupStatus {
+    /// True if the first administrator still has to be created.
+    required: bool,
+}
+
+async fn handle_get(State(setup): State<Arc<SetupState>>) -> Json<SetupStatus> {
+    Json(SetupStatus {
+        required: setup.is_pending().await,
+    })
+}
+
+#[derive(Deserialize)]
+struct PostData {
+    /// The token that was logged when the server started.
+    setup_token: String,
+    username: String,
+    password: String,
+}
+
+/// Create the first administrator of the instance, logging them in.
+async fn handle_post(
+    cookies: Cookies,
+    client: ClientInfo,
+    State(db): State<PgPool>,
+    State(setup): State<Arc<SetupState>>,
+    State(config): State<Arc<Config>>,
+    State(limiter): State<Arc<RateLimiter>>,
+    Json(data): Json<PostData>,
+) -> error::Result<Response> {
+    if let Err(limited) = limiter.check(Policy::Auth, client.ip, None).await {
+        return Ok(limited.into_response());
+    }
+    if !setup.is_pending().await {
+        return Ok((StatusCode::CONFLICT, "Setup has already been completed").into_response());
+    }
+    let Some(guard) = setup.start(&data.setup_token).await else {
+        warn!("Setup was attempted with a wrong token");
+        METRICS.auth_failure("setup");
+        return Ok((StatusCode::UNAUTHORIZED, "Wrong setup token").into_response());
+    };
+    if data.username.is_empty() || data.password.is_empty() {
+        let msg = "The username and password can not be empty";
+        return Ok((StatusCode::BAD_REQUEST, msg).into_response());
+    }
+
+    let mut tx = db.begin().await?;
+
+    // An administrator may have been created using the command line in the meantime.
+    if admin_exists(&mut *tx).await? {
+        guard.complete();
+        return Ok((StatusCode::CONFLICT, "Setup has already been completed").into_response());
+    }
+
+    let password_hash = hash_password(data.password).await?;
+    let Some(user_id) = create_user(&mut *tx, &data.username, &password_hash, true)
+        .await
+        .context("failed to create user in database")?
+    else {
+        return Ok((StatusCode::BAD_REQUEST, "Username is taken.").into_response());
+    };
+
+    let session = create_session(
+        &mut tx,
+        user_id,
+        Duration::seconds(config.sessions.login_lifetime as i64),
+    )
+    .await
+    .context("error while creating session")?;
+    tx.commit().await?;
+    guard.complete();
+
+    store_session(&cookies, &session, client.https)?;
+
+    info!(?user_id, data.username, "Created the first administrator.");
+    AuditEvent::new(AuditAction::AdminChanged)
+        .actor(Some(user_id), &data.username)
+        .target("user", user_id)
+        .ip(client.ip)
+        .details(serde_json::json!({ "is_admin": true, "setup": true }))
+        .record(&db)
+        .await;
+    AuditEvent::new(AuditAction::SessionCreated)
+        .actor(Some(user_id), &data.username)
+        .target("user", user_id)
+        .ip(client.ip)
+        .details(serde_json::json!({ "valid_until": session.valid_until }))
+        .record(&db)
+        .await;
+
+    Ok(Json(session).into_response())
+}
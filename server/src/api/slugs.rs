@@ -0,0 +1,14 @@
+mod slug;
+
+use axum::{routing::get, Router};
+
+use crate::state::AppState;
+
+pub(super) fn router() -> Router<AppState> {
+    Router::new().nest("/:slug", slug::router())
+}
+
+/// Routes for the short links that are shared with other people.
+pub(super) fn redirect_router() -> Router<AppState> {
+    Router::new().route("/:slug", get(slug::handle_redirect))
+}
@@ -0,0 +1,98 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, State},
+    response::{IntoResponse, Redirect, Response},
+    routing::get,
+    Json, Router,
+};
+use http::StatusCode;
+use serde::Serialize;
+use sqlx::PgPool;
+
+use crate::{
+    auth::Authentication,
+    config::Config,
+    error,
+    file::{FileDb, FileId, FileInfo},
+    state::AppState,
+};
+
+pub(super) fn router() -> Router<AppState> {
+    Router::new().route("/", get(handle_get).delete(handle_delete))
+}
+
+#[derive(Serialize)]
+struct ResolvedSlug {
+    file_id: FileId,
+    file_name: String,
+}
+
+/// Resolve a slug to the file it belongs to.
+async fn handle_get(
+    State(file_db): State<Arc<FileDb>>,
+    Path(slug): Path<String>,
+) -> error::Result<Response> {
+    let Some(file) = resolve(&file_db, &slug).await? else {
+        return Ok(StatusCode::NOT_FOUND.into_response());
+    };
+
+    Ok(Json(ResolvedSlug {
+        file_id: file.file_id,
+        file_name: file.file_name,
+    })
+    .into_response())
+}
+
+/// Redirect to the page of the file a slug belongs to.
+pub(super) async fn handle_redirect(
+    State(file_db): State<Arc<FileDb>>,
+    State(config): State<Arc<Config>>,
+    Path(slug): Path<String>,
+) -> error::Result<Response> {
+    let Some(file) = resolve(&file_db, &slug).await? else {
+        return Ok(StatusCode::NOT_FOUND.into_response());
+    };
+
+    Ok(Redirect::temporary(&file.share_link(&config.external_url)).into_response())
+}
+
+/// Remove a slug. Only the creator of the slug and admins can do this.
+async fn handle_delete(
+    auth: Authentication,
+    State(db): State<PgPool>,
+    Path(slug): Path<String>,
+) -> error::Result<Response> {
+    let row = sqlx::query!(
+        r#"
+            delete from slugs
+                where slug=$1 and (created_by=$2 or $3)
+                returning true as "found!"
+        "#,
+        slug.to_ascii_lowercase(),
+        auth.user_id,
+        auth.is_admin,
+    )
+    .fetch_optional(&db)
+    .await?;
+
+    if row.is_none() {
+        return Ok(StatusCode::NOT_FOUND.into_response());
+    }
+    Ok(().into_response())
+}
+
+async fn resolve(file_db: &FileDb, slug: &str) -> anyhow::Result<Option<FileInfo>> {
+    let row = sqlx::query!(
+        r#"select file_id as "file_id: FileId" from slugs where slug=$1"#,
+        slug.to_ascii_lowercase(),
+    )
+    .fetch_optional(file_db.db())
+    .await?;
+
+    match row {
+        // Expired files are not returned by `file_info`.
+        Some(row) => file_db.file_info(row.file_id).await,
+        None => Ok(None),
+    }
+}
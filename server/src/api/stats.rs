@@ -0,0 +1,23 @@
+use axum::{
+    extract::State,
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+use http::StatusCode;
+use sqlx::PgPool;
+
+use crate::{auth::Authentication, error, state::AppState, stats::instance_summary};
+
+pub(super) fn router() -> Router<AppState> {
+    Router::new().route("/", get(handle_get))
+}
+
+/// Get statistics about the whole instance.
+async fn handle_get(auth: Authentication, State(db): State<PgPool>) -> error::Result<Response> {
+    if !auth.is_admin {
+        return Ok(StatusCode::FORBIDDEN.into_response());
+    }
+
+    Ok(Json(instance_summary(&db).await?).into_response())
+}
@@ -0,0 +1,59 @@
+mod team_id;
+
+use axum::{
+    extract::State,
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+use http::StatusCode;
+use serde::Deserialize;
+use sqlx::PgPool;
+
+use crate::{
+    auth::Authentication,
+    error,
+    state::AppState,
+    team::{create_team, list_teams, team_info, TeamError},
+};
+
+pub(super) fn router() -> Router<AppState> {
+    Router::new()
+        .nest("/:team_id", team_id::router())
+        .route("/", get(handle_get).post(handle_post))
+}
+
+/// List the teams the user is a member of. Admins get all teams.
+async fn handle_get(auth: Authentication, State(db): State<PgPool>) -> error::Result<Response> {
+    let member = (!auth.is_admin).then_some(auth.user_id);
+    Ok(Json(list_teams(&db, member).await?).into_response())
+}
+
+#[derive(Deserialize)]
+struct PostData {
+    name: String,
+    /// The maximum total size of the team's files in bytes. Only admins can set this.
+    quota: Option<u64>,
+}
+
+/// Create a new team, with the user creating it as its owner.
+async fn handle_post(
+    auth: Authentication,
+    State(db): State<PgPool>,
+    Json(data): Json<PostData>,
+) -> error::Result<Response> {
+    if data.quota.is_some() && !auth.is_admin {
+        return Ok((StatusCode::FORBIDDEN, "Only admins can set team quotas").into_response());
+    }
+
+    let mut tx = db.begin().await?;
+    let team_id = match create_team(&mut tx, auth.user_id, &data.name, data.quota).await {
+        Ok(v) => v,
+        Err(TeamError::Database(err)) => return Err(err.into()),
+        Err(err) => return Ok((err.status_code(), err.to_string()).into_response()),
+    };
+    let team = team_info(&mut *tx, team_id).await?;
+    tx.commit().await?;
+
+    Ok((StatusCode::CREATED, Json(team)).into_response())
+}
@@ -0,0 +1,107 @@
+mod files;
+mod members;
+
+use axum::{
+    extract::{Path, State},
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+use http::StatusCode;
+use serde::{Deserialize, Deserializer};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    auth::Authentication,
+    error,
+    state::AppState,
+    team::{member_role, remove_team, team_info, update_team, TeamError, TeamRole},
+};
+
+pub(super) fn router() -> Router<AppState> {
+    Router::new()
+        .nest("/files", files::router())
+        .nest("/members", members::router())
+        .route(
+            "/",
+            get(handle_get).patch(handle_patch).delete(handle_delete),
+        )
+}
+
+/// Get information about a team.
+async fn handle_get(
+    auth: Authentication,
+    State(db): State<PgPool>,
+    Path(team_id): Path<Uuid>,
+) -> error::Result<Response> {
+    let role = member_role(&db, team_id, auth.user_id).await?;
+    if !(auth.is_admin || role.is_some()) {
+        return Ok(StatusCode::FORBIDDEN.into_response());
+    }
+
+    match team_info(&db, team_id).await? {
+        Some(team) => Ok(Json(team).into_response()),
+        None => Ok(StatusCode::NOT_FOUND.into_response()),
+    }
+}
+
+#[derive(Deserialize)]
+struct PatchData {
+    /// The new name of the team.
+    name: Option<String>,
+    /// The new quota of the team in bytes. `null` removes the quota, leaving this out keeps the
+    /// current quota. Only admins can change this.
+    #[serde(default, deserialize_with = "present")]
+    quota: Option<Option<u64>>,
+}
+
+/// Distinguishes between a field that is `null` and a field that is missing.
+fn present<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Option<u64>>, D::Error> {
+    Option::deserialize(deserializer).map(Some)
+}
+
+/// Rename a team or change its quota.
+async fn handle_patch(
+    auth: Authentication,
+    State(db): State<PgPool>,
+    Path(team_id): Path<Uuid>,
+    Json(data): Json<PatchData>,
+) -> error::Result<Response> {
+    let role = member_role(&db, team_id, auth.user_id).await?;
+    if !(auth.is_admin || role == Some(TeamRole::Owner)) {
+        return Ok(StatusCode::FORBIDDEN.into_response());
+    }
+    if data.quota.is_some() && !auth.is_admin {
+        return Ok((StatusCode::FORBIDDEN, "Only admins can set team quotas").into_response());
+    }
+
+    match update_team(&db, team_id, data.name.as_deref(), data.quota).await {
+        Ok(()) => {}
+        Err(TeamError::Database(err)) => return Err(err.into()),
+        Err(err) => return Ok((err.status_code(), err.to_string()).into_response()),
+    }
+
+    match team_info(&db, team_id).await? {
+        Some(team) => Ok(Json(team).into_response()),
+        None => Ok(StatusCode::NOT_FOUND.into_response()),
+    }
+}
+
+/// Remove a team. The team's files have to be transferred or removed first.
+async fn handle_delete(
+    auth: Authentication,
+    State(db): State<PgPool>,
+    Path(team_id): Path<Uuid>,
+) -> error::Result<Response> {
+    let role = member_role(&db, team_id, auth.user_id).await?;
+    if !(auth.is_admin || role == Some(TeamRole::Owner)) {
+        return Ok(StatusCode::FORBIDDEN.into_response());
+    }
+
+    match remove_team(&db, team_id).await {
+        Ok(()) => Ok(().into_response()),
+        Err(TeamError::Database(err)) => Err(err.into()),
+        Err(err) => Ok((err.status_code(), err.to_string()).into_response()),
+    }
+}
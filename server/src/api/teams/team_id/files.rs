@@ -0,0 +1,43 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, Query, State},
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+use http::StatusCode;
+use uuid::Uuid;
+
+use crate::{
+    auth::Authentication,
+    error,
+    file::{FileDb, FileFilter, Page, QueryError},
+    state::AppState,
+    team::member_role,
+};
+
+pub(super) fn router() -> Router<AppState> {
+    Router::new().route("/", get(handle_get))
+}
+
+/// List the files owned by a team, one page at a time.
+async fn handle_get(
+    auth: Authentication,
+    State(file_store): State<Arc<FileDb>>,
+    Path(team_id): Path<Uuid>,
+    Query(mut filter): Query<FileFilter>,
+    Query(page): Query<Page>,
+) -> error::Result<Response> {
+    let role = member_role(file_store.db(), team_id, auth.user_id).await?;
+    if !(auth.is_admin || role.is_some()) {
+        return Ok(StatusCode::FORBIDDEN.into_response());
+    }
+
+    filter.team = Some(team_id);
+    match file_store.list(&filter, &page).await {
+        Ok(files) => Ok(Json(files).into_response()),
+        Err(QueryError::Database(err)) => Err(err.into()),
+        Err(err) => Ok((err.status_code(), err.to_string()).into_response()),
+    }
+}
@@ -0,0 +1,38 @@
+mod user_id;
+
+use axum::{
+    extract::{Path, State},
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+use http::StatusCode;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    auth::Authentication,
+    error,
+    state::AppState,
+    team::{list_members, member_role},
+};
+
+pub(super) fn router() -> Router<AppState> {
+    Router::new()
+        .nest("/:user_id", user_id::router())
+        .route("/", get(handle_get))
+}
+
+/// List the members of a team.
+async fn handle_get(
+    auth: Authentication,
+    State(db): State<PgPool>,
+    Path(team_id): Path<Uuid>,
+) -> error::Result<Response> {
+    let role = member_role(&db, team_id, auth.user_id).await?;
+    if !(auth.is_admin || role.is_some()) {
+        return Ok(StatusCode::FORBIDDEN.into_response());
+    }
+
+    Ok(Json(list_members(&db, team_id).await?).into_response())
+}
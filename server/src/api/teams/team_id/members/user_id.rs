@@ -0,0 +1,79 @@
+use axum::{
+    extract::{Path, State},
+    response::{IntoResponse, Response},
+    routing::put,
+    Json, Router,
+};
+use http::StatusCode;
+use serde::Deserialize;
+use sqlx::PgPool;
+use tracing::info;
+use uuid::Uuid;
+
+use crate::{
+    auth::Authentication,
+    error,
+    state::AppState,
+    team::{member_role, remove_member, set_member_role, TeamError, TeamRole},
+};
+
+pub(super) fn router() -> Router<AppState> {
+    Router::new().route("/", put(handle_put).delete(handle_delete))
+}
+
+#[derive(Deserialize)]
+struct PutData {
+    role: TeamRole,
+}
+
+/// Add a user to a team, or change the role of an existing member.
+async fn handle_put(
+    auth: Authentication,
+    State(db): State<PgPool>,
+    Path((team_id, user_id)): Path<(Uuid, Uuid)>,
+    Json(data): Json<PutData>,
+) -> error::Result<Response> {
+    let mut tx = db.begin().await?;
+
+    let role = member_role(&mut *tx, team_id, auth.user_id).await?;
+    if !(auth.is_admin || role == Some(TeamRole::Owner)) {
+        tx.commit().await?;
+        return Ok(StatusCode::FORBIDDEN.into_response());
+    }
+
+    match set_member_role(&mut tx, team_id, user_id, data.role).await {
+        Ok(()) => {}
+        Err(TeamError::Database(err)) => return Err(err.into()),
+        Err(err) => return Ok((err.status_code(), err.to_string()).into_response()),
+    }
+    tx.commit().await?;
+
+    info!(?team_id, ?user_id, %data.role, "Changed team member role.");
+    Ok(().into_response())
+}
+
+/// Remove a user from a team. Members can always leave a team themselves.
+async fn handle_delete(
+    auth: Authentication,
+    State(db): State<PgPool>,
+    Path((team_id, user_id)): Path<(Uuid, Uuid)>,
+) -> error::Result<Response> {
+    let mut tx = db.begin().await?;
+
+    let role = member_role(&mut *tx, team_id, auth.user_id).await?;
+    if !(auth.is_admin || auth.user_id == user_id || role == Some(TeamRole::Owner)) {
+        tx.commit().await?;
+        return Ok(StatusCode::FORBIDDEN.into_response());
+    }
+
+    match remove_member(&mut tx, team_id, user_id).await {
+        Ok(true) => {}
+        Ok(false) => return Ok(StatusCode::NOT_FOUND.into_response()),
+        Err(TeamError::Database(err)) => return Err(err.into()),
+        Err(err) => return Ok((err.status_code(), err.to_string()).into_response()),
+    }
+    tx.commit().await?;
+
+    info!(?team_id, ?user_id, "Removed team member.");
+    Ok(().into_response())
+}
@@ -0,0 +1,182 @@
+mod request_id;
+
+use std::sync::Arc;
+
+use anyhow::Context;
+use argon2::{password_hash::SaltString, Argon2, PasswordHasher};
+use axum::{
+    extract::State,
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+use chrono::{Duration, Utc};
+use http::StatusCode;
+use rand::rngs::OsRng;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use tracing::info;
+use uuid::Uuid;
+
+use crate::{
+    auth::Authentication,
+    config::Config,
+    error,
+    folder::{check_folder, FolderError},
+    state::AppState,
+    upload_request::{
+        list_upload_requests, random_request_id, upload_link, upload_request, UploadRequest,
+    },
+};
+
+/// The maximum length of an upload request description in bytes.
+const MAX_DESCRIPTION_LEN: usize = 4096;
+
+pub(super) fn router() -> Router<AppState> {
+    Router::new()
+        .nest("/:request_id", request_id::router())
+        .route("/", get(handle_get).post(handle_post))
+}
+
+#[derive(Serialize)]
+struct UploadRequestData {
+    #[serde(flatten)]
+    request: UploadRequest,
+    /// The link to share with the people that should upload files.
+    link: String,
+}
+
+/// List the upload requests of the user.
+async fn handle_get(
+    auth: Authentication,
+    State(db): State<PgPool>,
+    State(config): State<Arc<Config>>,
+) -> error::Result<Response> {
+    let requests: Vec<_> = list_upload_requests(&db, auth.user_id)
+        .await?
+        .into_iter()
+        .map(|request| UploadRequestData {
+            link: upload_link(&config.external_url, &request.request_id),
+            request,
+        })
+        .collect();
+
+    Ok(Json(requests).into_response())
+}
+
+#[derive(Deserialize)]
+struct PostData {
+    /// Shown to the people uploading, such as what files are expected.
+    description: Option<String>,
+    /// The folder uploaded files are put in.
+    folder_id: Option<Uuid>,
+    /// If set, uploading requires this password.
+    password: Option<String>,
+    /// The maximum amount of files that can be uploaded using the link.
+    max_files: Option<u32>,
+    /// The maximum size of a single uploaded file in bytes.
+    max_file_size: Option<u64>,
+    /// The amount of seconds after which the link can no longer be used.
+    expires_in: Option<u32>,
+}
+
+/// Create a link that allows people without an account to upload files to the user.
+async fn handle_post(
+    auth: Authentication,
+    State(db): State<PgPool>,
+    State(config): State<Arc<Config>>,
+    Json(data): Json<PostData>,
+) -> error::Result<Response> {
+    let description = data
+        .description
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty());
+    if description
+        .as_ref()
+        .is_some_and(|v| v.len() > MAX_DESCRIPTION_LEN)
+    {
+        let msg = format!("Description must be at most {MAX_DESCRIPTION_LEN} bytes long");
+        return Ok((StatusCode::BAD_REQUEST, msg).into_response());
+    }
+    if data.max_files == Some(0) || data.max_file_size == Some(0) {
+        let msg = "`max_files` and `max_file_size` must be larger than 0";
+        return Ok((StatusCode::BAD_REQUEST, msg).into_response());
+    }
+    if data.password.as_ref().is_some_and(|v| v.is_empty()) {
+        let msg = "Password must not be empty";
+        return Ok((StatusCode::BAD_REQUEST, msg).into_response());
+    }
+
+    let expires_at = match data.expires_in {
+        Some(expires_in) => {
+            let expires_in = Duration::seconds(expires_in as i64);
+            if expires_in > Duration::days(365) || expires_in <= Duration::zero() {
+                return Ok((
+                    StatusCode::BAD_REQUEST,
+                    "`expires_in` field must be between 0 and 365 days (in seconds)",
+                )
+                    .into_response());
+            }
+            Some(Utc::now() + expires_in)
+        }
+        None => None,
+    };
+
+    if let Some(folder_id) = data.folder_id {
+        match check_folder(&db, auth.user_id, folder_id).await {
+            Ok(()) => {}
+            Err(FolderError::Database(err)) => return Err(err.into()),
+            Err(err) => return Ok((err.status_code(), err.to_string()).into_response()),
+        }
+    }
+
+    let password_hash = match data.password {
+        Some(password) => Some(
+            tokio::task::spawn_blocking(move || {
+                let salt = SaltString::generate(&mut OsRng);
+                Argon2::default()
+                    .hash_password(password.as_bytes(), &salt)
+                    .map(|hash| hash.to_string())
+            })
+            .await
+            .context("could not join hasher thread")?
+            .context("failed to hash password")?,
+        ),
+        None => None,
+    };
+
+    let request_id = random_request_id();
+    sqlx::query!(
+        r#"
+            insert into upload_requests (
+                request_id, owner_id, description, folder_id, password_hash, max_files,
+                max_file_size, expires_at
+            )
+                values ($1, $2, $3, $4, $5, $6, $7, $8)
+        "#,
+        request_id,
+        auth.user_id,
+        description,
+        data.folder_id,
+        password_hash,
+        data.max_files.map(|v| v.min(i32::MAX as u32) as i32),
+        data.max_file_size.map(|v| v.min(i64::MAX as u64) as i64),
+        expires_at,
+    )
+    .execute(&db)
+    .await?;
+
+    info!(?auth.user_id, ?request_id, "Created upload request.");
+
+    let request = upload_request(&db, &request_id)
+        .await?
+        .context("upload request was removed while it was being created")?;
+    Ok((
+        StatusCode::CREATED,
+        Json(UploadRequestData {
+            link: upload_link(&config.external_url, &request_id),
+            request,
+        }),
+    )
+        .into_response())
+}
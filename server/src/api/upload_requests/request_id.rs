@@ -0,0 +1,278 @@
+use std::{io, sync::Arc};
+
+use anyhow::Context;
+use argon2::{Argon2, PasswordHash, PasswordVerifier};
+use axum::{
+    extract::{Path, Request, State},
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+use chrono::{serde::ts_milliseconds_option, DateTime, Utc};
+use futures::TryStreamExt;
+use http::StatusCode;
+use serde::Serialize;
+use tokio::io::AsyncReadExt;
+use tokio_util::io::StreamReader;
+use tracing::{debug, error, info, warn};
+
+use crate::{
+    api::{content_length, too_large},
+    audit::{AuditAction, AuditEvent},
+    auth::Authentication,
+    client::ClientInfo,
+    config::Config,
+    file::{normalize_file_name, FileDb},
+    metrics::METRICS,
+    rate_limit::{Policy, RateLimiter},
+    shutdown::AcceptingUploads,
+    state::AppState,
+    upload_request::{claim_upload, release_upload, upload_request, UploadRequest},
+    user::remaining_quota,
+};
+
+pub(super) fn router() -> Router<AppState> {
+    Router::new().route("/", get(handle_get).post(handle_post).delete(handle_delete))
+}
+
+/// The information about an upload request that is shown to the people uploading.
+#[derive(Serialize)]
+struct PublicUploadRequest {
+    owner_name: String,
+    description: Option<String>,
+    has_password: bool,
+    max_file_size: Option<u64>,
+    /// How many more files can be uploaded, if there is a limit.
+    remaining_files: Option<u32>,
+    #[serde(with = "ts_milliseconds_option")]
+    expires_at: Option<DateTime<Utc>>,
+}
+
+/// Get the information needed to upload files using a link. This does not require an account.
+async fn handle_get(
+    State(file_store): State<Arc<FileDb>>,
+    State(config): State<Arc<Config>>,
+    Path(request_id): Path<String>,
+) -> crate::error::Result<Response> {
+    let Some(request) = upload_request(file_store.db(), &request_id).await? else {
+        return Ok(StatusCode::NOT_FOUND.into_response());
+    };
+    if !request.is_open() {
+        return Ok(StatusCode::GONE.into_response());
+    }
+
+    Ok(Json(PublicUploadRequest {
+        max_file_size: max_file_size(&request, &config),
+        remaining_files: request.remaining_files(),
+        owner_name: request.owner_name,
+        description: request.description,
+        has_password: request.has_password,
+        expires_at: request.expires_at,
+    })
+    .into_response())
+}
+
+/// The maximum size of files uploaded using a request, which can be limited by both the request
+/// and the instance.
+fn max_file_size(request: &UploadRequest, config: &Config) -> Option<u64> {
+    match (request.max_file_size, config.max_file_size()) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    }
+}
+
+/// Upload a file using a link. This does not require an account, the file is added to the files
+/// of the user that created the link.
+///
+/// The file name is sent using the `file_name` header, and the password of the link, if any, using
+/// the `password` header.
+async fn handle_post(
+    _: AcceptingUploads,
+    client: ClientInfo,
+    State(file_store): State<Arc<FileDb>>,
+    State(config): State<Arc<Config>>,
+    State(limiter): State<Arc<RateLimiter>>,
+    Path(request_id): Path<String>,
+    req: Request,
+) -> crate::error::Result<Response> {
+    let start = tokio::time::Instant::now();
+
+    // Uploads using a link are anonymous, so only the address of the client is limited.
+    if let Err(limited) = limiter.check(Policy::Uploads, client.ip, None).await {
+        return Ok(limited.into_response());
+    }
+
+    let Some(request) = upload_request(file_store.db(), &request_id).await? else {
+        return Ok(StatusCode::NOT_FOUND.into_response());
+    };
+    if !request.is_open() {
+        return Ok(StatusCode::GONE.into_response());
+    }
+    let max_file_size = max_file_size(&request, &config);
+
+    if let Some(password_hash) = request.password_hash {
+        let password = req
+            .headers()
+            .get("password")
+            .map(|v| v.as_bytes().to_vec())
+            .unwrap_or_default();
+        let hash_check = tokio::task::spawn_blocking(move || {
+            Argon2::default().verify_password(
+                &password,
+                &PasswordHash::new(&password_hash).context("failed to parse password hash")?,
+            )?;
+            anyhow::Result::<()>::Ok(())
+        })
+        .await
+        .context("could not join hasher thread")?;
+
+        if let Err(err) = hash_check {
+            warn!(?request_id, "Upload request password check failed: {err:?}");
+            // Slow down attempts to guess the password.
+            tokio::time::sleep_until(start + tokio::time::Duration::from_secs(1)).await;
+            METRICS.auth_failure("upload_request");
+            return Ok(StatusCode::UNAUTHORIZED.into_response());
+        }
+    }
+
+    // Header values are not required to be ASCII, so the raw bytes are decoded to allow for
+    // non-ASCII file names.
+    let raw_file_name = req
+        .headers()
+        .get("file_name")
+        .map(|v| std::str::from_utf8(v.as_bytes()))
+        .unwrap_or(Ok("file"));
+    let Ok(raw_file_name) = raw_file_name else {
+        return Ok((StatusCode::BAD_REQUEST, "File name must be valid UTF-8").into_response());
+    };
+    let file_name = match normalize_file_name(raw_file_name) {
+        Ok(v) => v,
+        Err(err) => {
+            let msg = format!("Invalid file name: {err}");
+            return Ok((StatusCode::BAD_REQUEST, msg).into_response());
+        }
+    };
+
+    // Reject files that are known to be too large before receiving them.
+    if let (Some(max), Some(length)) = (max_file_size, content_length(req.headers())) {
+        if length > max {
+            return Ok(too_large(max));
+        }
+    }
+
+    // The file is owned by the creator of the upload request, so it counts towards their quota.
+    if remaining_quota(file_store.db(), request.owner_id, config.user_quota())
+        .await?
+        .is_some_and(|v| v <= 0)
+    {
+        let msg = "The recipient's quota has been reached";
+        return Ok((StatusCode::PAYLOAD_TOO_LARGE, msg).into_response());
+    }
+
+    if !claim_upload(file_store.db(), &request_id).await? {
+        return Ok(StatusCode::GONE.into_response());
+    }
+
+    // Stop reading one byte after the limit, which is enough to know the file is too large.
+    let limit = max_file_size.map_or(u64::MAX, |v| v.saturating_add(1));
+    let content =
+        StreamReader::new(req.into_body().into_data_stream().map_err(io::Error::other)).take(limit);
+    let result = file_store
+        .create(
+            request.owner_id,
+            file_name,
+            request.folder_id,
+            None,
+            config.default_expires_at(),
+            content,
+        )
+        .await;
+    let file = match result {
+        Ok(v) => v,
+        Err(err) => {
+            error!("Could not store file: {err:?}");
+            release_upload(file_store.db(), &request_id).await?;
+            return Ok(StatusCode::INTERNAL_SERVER_ERROR.into_response());
+        }
+    };
+
+    if let Some(max) = max_file_size.filter(|v| file.file_size > *v) {
+        debug!(?request_id, "Rejected upload exceeding the size limit");
+        if let Err(err) = file_store.remove(file.file_id).await {
+            error!("Could not remove file exceeding the size limit: {err:?}");
+        }
+        release_upload(file_store.db(), &request_id).await?;
+        return Ok(too_large(max));
+    }
+
+    // The size of the file is only known once it has been uploaded, so the quota is checked again.
+    if remaining_quota(file_store.db(), request.owner_id, config.user_quota())
+        .await?
+        .is_some_and(|v| v < 0)
+    {
+        debug!(?request_id, "Rejected upload exceeding the quota");
+        if let Err(err) = file_store.remove(file.file_id).await {
+            error!("Could not remove file exceeding the quota: {err:?}");
+        }
+        release_upload(file_store.db(), &request_id).await?;
+        let msg = "The file exceeds the recipient's quota";
+        return Ok((StatusCode::PAYLOAD_TOO_LARGE, msg).into_response());
+    }
+
+    info!(
+        ?request_id,
+        ?request.owner_id,
+        ?file.file_name,
+        ?file.file_size,
+        "A file was uploaded using an upload request"
+    );
+    AuditEvent::new(AuditAction::FileUploaded)
+        .target("file", file.file_id)
+        .ip(client.ip)
+        .details(serde_json::json!({
+            "file_name": file.file_name,
+            "file_size": file.file_size,
+            "upload_request": request_id,
+            "owner_id": request.owner_id,
+        }))
+        .record(file_store.db())
+        .await;
+
+    // The uploader only learns that the upload succeeded, the file is not shared with them.
+    Ok(Json(UploadedFile {
+        file_name: file.file_name,
+        file_size: file.file_size,
+    })
+    .into_response())
+}
+
+#[derive(Serialize)]
+struct UploadedFile {
+    file_name: String,
+    file_size: u64,
+}
+
+/// Remove an upload request, so the link can no longer be used. Files that were already uploaded
+/// are kept.
+async fn handle_delete(
+    auth: Authentication,
+    State(file_store): State<Arc<FileDb>>,
+    Path(request_id): Path<String>,
+) -> crate::error::Result<Response> {
+    let Some(request) = upload_request(file_store.db(), &request_id).await? else {
+        return Ok(StatusCode::NOT_FOUND.into_response());
+    };
+    if !(auth.is_admin || auth.user_id == request.owner_id) {
+        return Ok(StatusCode::FORBIDDEN.into_response());
+    }
+
+    sqlx::query!(
+        "delete from upload_requests where request_id=$1",
+        request_id
+    )
+    .execute(file_store.db())
+    .await?;
+
+    info!(?auth.user_id, ?request_id, "Removed upload request.");
+    Ok(().into_response())
+}
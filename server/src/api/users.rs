@@ -3,7 +3,6 @@ mod user_id;
 use std::sync::Arc;
 
 use anyhow::Context as _;
-use argon2::{password_hash::SaltString, Argon2, PasswordHasher};
 use axum::{
     extract::State,
     response::{IntoResponse, Response},
@@ -13,18 +12,21 @@ use axum::{
 use chrono::{DateTime, Duration, Utc};
 use http::StatusCode;
 use num_traits::ToPrimitive;
-use rand::rngs::OsRng;
 use serde::{Deserialize, Serialize};
 use sqlx::PgPool;
 use tower_cookies::Cookies;
 use uuid::Uuid;
 
 use crate::{
+    audit::{AuditAction, AuditEvent},
     auth::Authentication,
+    client::ClientInfo,
     config::Config,
     error,
+    rate_limit::{Policy, RateLimiter},
     session::{create_session, store_session},
     state::AppState,
+    user::{create_user, hash_password},
 };
 
 pub(super) fn router() -> Router<AppState> {
@@ -79,13 +81,18 @@ struct CreateUser {
 
 async fn handle_post(
     cookies: Cookies,
+    client: ClientInfo,
     State(db): State<PgPool>,
     State(config): State<Arc<Config>>,
+    State(limiter): State<Arc<RateLimiter>>,
     Json(request): Json<CreateUser>,
 ) -> error::Result<Response> {
-    if !config.public_config.allow_registering {
+    if !config.allow_registering {
         return Ok((StatusCode::FORBIDDEN, "User registering has been disabled").into_response());
     }
+    if let Err(limited) = limiter.check(Policy::Registration, client.ip, None).await {
+        return Ok(limited.into_response());
+    }
 
     let mut tx = db.begin().await?;
 
@@ -101,33 +108,17 @@ async fn handle_post(
         return Ok((StatusCode::BAD_REQUEST, "Username is taken.").into_response());
     }
 
-    // Hash the password in a separate blocking thread as it is an expensive CPU bound
-    // operation.
-    let password = request.password;
-    let password_hash = tokio::task::spawn_blocking(move || {
-        let salt = SaltString::generate(&mut OsRng);
-        Argon2::default()
-            .hash_password(password.as_bytes(), &salt)
-            .map(|hash| hash.to_string())
-    })
-    .await
-    .context("could not join hasher thread")?
-    .context("failed to hash password")?;
+    let password_hash = hash_password(request.password).await?;
 
-    let row = sqlx::query!(
-        r#"
-        insert into users (user_id, username, password_hash) values(gen_random_uuid(), $1, $2)
-            returning user_id
-        "#,
-        request.username,
-        password_hash,
-    )
-    .fetch_one(&mut *tx)
-    .await
-    .context("failed to create user in database")?;
+    let Some(user_id) = create_user(&mut *tx, &request.username, &password_hash, false)
+        .await
+        .context("failed to create user in database")?
+    else {
+        return Ok((StatusCode::BAD_REQUEST, "Username is taken.").into_response());
+    };
 
     // If the user provided an invite code, check this invite code and use it.
-    if let Some(invite_code) = request.invite_code {
+    if let Some(invite_code) = &request.invite_code {
         let invite_row = sqlx::query!(
             r#"
                 update invites
@@ -147,12 +138,12 @@ async fn handle_post(
 
         sqlx::query!(
             "update users set invite_used=$2 where user_id=$1",
-            row.user_id,
+            user_id,
             invite_row.invite,
         )
         .execute(&mut *tx)
         .await?;
-    } else if !config.public_config.disable_invite_codes {
+    } else if !config.disable_invite_codes {
         // Don't allow singing up without a valid invite code if invite codes are enabled.
         tx.rollback().await?;
         return Ok((
@@ -163,12 +154,33 @@ async fn handle_post(
     }
 
     // The user has been created, now make a session for the user.
-    let session = create_session(&mut tx, row.user_id, Duration::weeks(2))
-        .await
-        .context("failed ot create session")?;
+    let session = create_session(
+        &mut tx,
+        user_id,
+        Duration::seconds(config.sessions.registration_lifetime as i64),
+    )
+    .await
+    .context("failed ot create session")?;
 
     tx.commit().await?;
 
-    store_session(&cookies, &session)?;
+    store_session(&cookies, &session, client.https)?;
+
+    if let Some(invite_code) = &request.invite_code {
+        AuditEvent::new(AuditAction::InviteUsed)
+            .actor(Some(user_id), &request.username)
+            .target("invite", invite_code)
+            .ip(client.ip)
+            .record(&db)
+            .await;
+    }
+    AuditEvent::new(AuditAction::SessionCreated)
+        .actor(Some(user_id), &request.username)
+        .target("user", user_id)
+        .ip(client.ip)
+        .details(serde_json::json!({ "valid_until": session.valid_until }))
+        .record(&db)
+        .await;
+
     Ok(Json(session).into_response())
 }
@@ -1,37 +1,45 @@
 mod admin;
 mod files;
+mod folders;
 mod password;
 mod ssh_keys;
+mod stats;
 mod username;
 
 use std::sync::Arc;
 
 use anyhow::Context;
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     response::{IntoResponse, Response},
     routing::{delete, get},
     Json, Router,
 };
 use http::StatusCode;
 use num_traits::cast::ToPrimitive;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use sqlx::PgPool;
 use uuid::Uuid;
 
 use crate::{
+    audit::{AuditAction, AuditEvent},
     auth::Authentication,
+    client::ClientInfo,
     error,
-    file::{FileDb, FileId},
+    file::FileDb,
     state::AppState,
+    team::{member_role, remaining_quota},
+    user::{delete_user, remove_contents, remove_files, transfer_files, transfer_team_files},
 };
 
 pub(super) fn router() -> Router<AppState> {
     Router::new()
         .nest("/admin", admin::router())
         .nest("/files", files::router())
+        .nest("/folders", folders::router())
         .nest("/password", password::router())
         .nest("/ssh-keys", ssh_keys::router())
+        .nest("/stats", stats::router())
         .nest("/username", username::router())
         .route("/", get(handle_get))
         .route("/", delete(handle_delete))
@@ -76,11 +84,23 @@ async fn handle_get(
     .into_response())
 }
 
+#[derive(Deserialize)]
+struct DeleteQuery {
+    /// Transfer the user's files to another user instead of removing them. Only admins can do this.
+    transfer_to_user: Option<Uuid>,
+    /// Transfer the user's files to a team instead of removing them.
+    transfer_to_team: Option<Uuid>,
+}
+
+/// Remove a user. Their personal files are removed as well, unless they are transferred to another
+/// user or a team. Files the user uploaded to a team stay in that team.
 async fn handle_delete(
     auth: Authentication,
+    client: ClientInfo,
     State(db): State<PgPool>,
     State(file_db): State<Arc<FileDb>>,
     Path(user_id): Path<Uuid>,
+    Query(query): Query<DeleteQuery>,
 ) -> error::Result<Response> {
     if !(auth.is_admin || auth.user_id == user_id) {
         return Ok(StatusCode::FORBIDDEN.into_response());
@@ -88,26 +108,99 @@ async fn handle_delete(
 
     let mut tx = db.begin().await?;
 
-    let files = sqlx::query!(
-        r#"delete from files where uploader_id=$1 returning file_id as "file_id: FileId""#,
-        user_id,
-    )
-    .fetch_all(&mut *tx)
-    .await?;
+    let mut removed = Vec::new();
+    match (query.transfer_to_user, query.transfer_to_team) {
+        (Some(_), Some(_)) => {
+            let msg = "Files can either be transferred to a user or to a team, not both";
+            return Ok((StatusCode::BAD_REQUEST, msg).into_response());
+        }
+        (Some(new_owner), None) => {
+            if !auth.is_admin {
+                return Ok(StatusCode::FORBIDDEN.into_response());
+            }
+            if new_owner == user_id {
+                let msg = "Files can not be transferred to the user being removed";
+                return Ok((StatusCode::BAD_REQUEST, msg).into_response());
+            }
+
+            let exists = sqlx::query!(
+                r#"select exists(select * from users where user_id=$1) as "exists!""#,
+                new_owner,
+            )
+            .fetch_one(&mut *tx)
+            .await?
+            .exists;
+            if !exists {
+                return Ok((StatusCode::NOT_FOUND, "User to transfer to not found").into_response());
+            }
+
+            transfer_files(&mut tx, user_id, new_owner, None).await?;
+        }
+        (None, Some(team_id)) => {
+            let role = member_role(&mut *tx, team_id, auth.user_id).await?;
+            if !(auth.is_admin || role.is_some_and(|v| v.can_manage_files())) {
+                return Ok((
+                    StatusCode::FORBIDDEN,
+                    "You can not transfer files to this team",
+                )
+                    .into_response());
+            }
+
+            // The files keep an uploader, which becomes the longest standing owner of the team
+            // other than the user being removed.
+            let Some(new_owner) = sqlx::query!(
+                r#"
+                    select user_id
+                        from team_members
+                        where team_id=$1 and role='owner' and user_id!=$2
+                        order by added_at
+                        limit 1
+                "#,
+                team_id,
+                user_id,
+            )
+            .fetch_optional(&mut *tx)
+            .await?
+            .map(|row| row.user_id) else {
+                let msg = "The team has no other owner to transfer the files to";
+                return Ok((StatusCode::CONFLICT, msg).into_response());
+            };
+
+            transfer_files(&mut tx, user_id, new_owner, Some(team_id)).await?;
 
-    for file in files {
-        _ = file_db.file_store().remove(file.file_id).await;
+            if remaining_quota(&mut *tx, team_id)
+                .await?
+                .is_some_and(|v| v < 0)
+            {
+                let msg = "The files exceed the team's quota";
+                return Ok((StatusCode::PAYLOAD_TOO_LARGE, msg).into_response());
+            }
+        }
+        (None, None) => {
+            removed = remove_files(&mut tx, user_id).await?;
+        }
     }
 
-    sqlx::query!(
-        r#"
-            delete from users where user_id=$1
-        "#,
-        user_id,
-    )
-    .execute(&mut *tx)
-    .await?;
+    if !transfer_team_files(&mut tx, user_id).await? {
+        let msg = "The user is the last member of a team that owns files they uploaded";
+        return Ok((StatusCode::CONFLICT, msg).into_response());
+    }
+
+    delete_user(&mut tx, user_id).await?;
 
     tx.commit().await?;
+    remove_contents(file_db.file_store(), &removed).await;
+
+    AuditEvent::new(AuditAction::UserDeleted)
+        .by(&auth)
+        .target("user", user_id)
+        .ip(client.ip)
+        .details(serde_json::json!({
+            "transfer_to_user": query.transfer_to_user,
+            "transfer_to_team": query.transfer_to_team,
+        }))
+        .record(&db)
+        .await;
+
     Ok(().into_response())
 }
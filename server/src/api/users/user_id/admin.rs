@@ -5,13 +5,21 @@ use axum::{
     Json, Router,
 };
 use http::StatusCode;
+use serde::Deserialize;
 use sqlx::PgPool;
+use tracing::info;
 use uuid::Uuid;
 
-use crate::{auth::Authentication, error, state::AppState};
+use crate::{
+    audit::{AuditAction, AuditEvent},
+    auth::Authentication,
+    client::ClientInfo,
+    error,
+    state::AppState,
+};
 
 pub(super) fn router() -> Router<AppState> {
-    Router::new().route("/", get(handle_get))
+    Router::new().route("/", get(handle_get).put(handle_put))
 }
 
 async fn handle_get(
@@ -32,3 +40,48 @@ async fn handle_get(
 
     Ok(Json(row.is_admin).into_response())
 }
+
+#[derive(Deserialize)]
+struct PutData {
+    is_admin: bool,
+}
+
+/// Give a user administrator permissions or take them away. Only available to admins.
+async fn handle_put(
+    auth: Authentication,
+    client: ClientInfo,
+    State(db): State<PgPool>,
+    Path(user_id): Path<Uuid>,
+    Json(data): Json<PutData>,
+) -> error::Result<Response> {
+    if !auth.is_admin {
+        return Ok(StatusCode::FORBIDDEN.into_response());
+    }
+    // Prevents admins from locking themselves out by accident.
+    if user_id == auth.user_id {
+        let msg = "You can not change your own admin permissions";
+        return Ok((StatusCode::BAD_REQUEST, msg).into_response());
+    }
+
+    let result = sqlx::query!(
+        "update users set is_admin=$2 where user_id=$1",
+        user_id,
+        data.is_admin,
+    )
+    .execute(&db)
+    .await?;
+    if result.rows_affected() == 0 {
+        return Ok(StatusCode::NOT_FOUND.into_response());
+    }
+
+    info!(?auth.user_id, ?user_id, data.is_admin, "Changed admin permissions.");
+    AuditEvent::new(AuditAction::AdminChanged)
+        .by(&auth)
+        .target("user", user_id)
+        .ip(client.ip)
+        .details(serde_json::json!({ "is_admin": data.is_admin }))
+        .record(&db)
+        .await;
+
+    Ok(().into_response())
+}
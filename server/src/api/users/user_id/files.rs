@@ -1,18 +1,18 @@
-use anyhow::Context;
+use std::sync::Arc;
+
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     response::{IntoResponse, Response},
     routing::get,
     Json, Router,
 };
 use http::StatusCode;
-use sqlx::PgPool;
 use uuid::Uuid;
 
 use crate::{
     auth::Authentication,
     error,
-    file::{FileId, FileInfo},
+    file::{FileDb, FileFilter, Page, QueryError},
     state::AppState,
 };
 
@@ -20,33 +20,22 @@ pub(super) fn router() -> Router<AppState> {
     Router::new().route("/", get(handle_get))
 }
 
+/// List the files of a user, one page at a time.
 async fn handle_get(
     auth: Authentication,
-    State(db): State<PgPool>,
+    State(file_store): State<Arc<FileDb>>,
     Path(user_id): Path<Uuid>,
+    Query(mut filter): Query<FileFilter>,
+    Query(page): Query<Page>,
 ) -> error::Result<Response> {
-    if auth.user_id != user_id {
+    if !(auth.is_admin || auth.user_id == user_id) {
         return Ok(StatusCode::UNAUTHORIZED.into_response());
     }
 
-    let files = sqlx::query!(
-        r#"select file_id as "file_id: FileId", file_name, file_size, upload_date from files where uploader_id = $1"#,
-        user_id,
-    )
-    .try_map(|row| {
-        Ok(FileInfo {
-            file_id: row.file_id,
-            file_name: row.file_name,
-            file_size: row
-                .file_size
-                .try_into()
-                .context("invalid file size")
-                .map_err(|err| sqlx::Error::Decode(err.into()))?,
-            upload_date: row.upload_date,
-        })
-    })
-    .fetch_all(&db)
-    .await?;
-
-    Ok(Json(files).into_response())
+    filter.uploader = Some(user_id);
+    match file_store.list(&filter, &page).await {
+        Ok(files) => Ok(Json(files).into_response()),
+        Err(QueryError::Database(err)) => Err(err.into()),
+        Err(err) => Ok((err.status_code(), err.to_string()).into_response()),
+    }
 }
@@ -0,0 +1,69 @@
+mod folder_id;
+
+use axum::{
+    extract::{Path, State},
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+use http::StatusCode;
+use serde::Deserialize;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    auth::Authentication,
+    error,
+    folder::{create_folder, list_folders, FolderError},
+    state::AppState,
+};
+
+pub(super) fn router() -> Router<AppState> {
+    Router::new()
+        .nest("/:folder_id", folder_id::router())
+        .route("/", get(handle_get).post(handle_post))
+}
+
+/// List all folders of a user. Folders are returned as a flat list, the hierarchy can be built
+/// using their `parent_id`.
+async fn handle_get(
+    auth: Authentication,
+    State(db): State<PgPool>,
+    Path(user_id): Path<Uuid>,
+) -> error::Result<Response> {
+    if !(auth.is_admin || auth.user_id == user_id) {
+        return Ok(StatusCode::FORBIDDEN.into_response());
+    }
+
+    Ok(Json(list_folders(&db, user_id).await?).into_response())
+}
+
+#[derive(Deserialize)]
+struct PostData {
+    name: String,
+    /// The folder to create the new folder in. The folder is created at the top level if this is
+    /// not set.
+    parent_id: Option<Uuid>,
+}
+
+/// Create a new folder.
+async fn handle_post(
+    auth: Authentication,
+    State(db): State<PgPool>,
+    Path(user_id): Path<Uuid>,
+    Json(data): Json<PostData>,
+) -> error::Result<Response> {
+    if !(auth.is_admin || auth.user_id == user_id) {
+        return Ok(StatusCode::FORBIDDEN.into_response());
+    }
+
+    let mut tx = db.begin().await?;
+    let folder = match create_folder(&mut tx, user_id, data.parent_id, &data.name).await {
+        Ok(v) => v,
+        Err(FolderError::Database(err)) => return Err(err.into()),
+        Err(err) => return Ok((err.status_code(), err.to_string()).into_response()),
+    };
+    tx.commit().await?;
+
+    Ok((StatusCode::CREATED, Json(folder)).into_response())
+}
@@ -0,0 +1,82 @@
+use axum::{
+    extract::{Path, State},
+    response::{IntoResponse, Response},
+    routing::patch,
+    Json, Router,
+};
+use http::StatusCode;
+use serde::{Deserialize, Deserializer};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    auth::Authentication,
+    error,
+    folder::{remove_folder, update_folder, FolderError},
+    state::AppState,
+};
+
+pub(super) fn router() -> Router<AppState> {
+    Router::new().route("/", patch(handle_patch).delete(handle_delete))
+}
+
+#[derive(Deserialize)]
+struct PatchData {
+    /// The new name of the folder.
+    name: Option<String>,
+    /// The folder to move this folder into. `null` moves the folder to the top level, leaving
+    /// this out keeps the folder where it is.
+    #[serde(default, deserialize_with = "present")]
+    parent_id: Option<Option<Uuid>>,
+}
+
+/// Distinguishes between a field that is `null` and a field that is missing.
+fn present<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Option<Uuid>>, D::Error> {
+    Option::deserialize(deserializer).map(Some)
+}
+
+/// Rename or move a folder.
+async fn handle_patch(
+    auth: Authentication,
+    State(db): State<PgPool>,
+    Path((user_id, folder_id)): Path<(Uuid, Uuid)>,
+    Json(data): Json<PatchData>,
+) -> error::Result<Response> {
+    if !(auth.is_admin || auth.user_id == user_id) {
+        return Ok(StatusCode::FORBIDDEN.into_response());
+    }
+
+    let mut tx = db.begin().await?;
+    let result = update_folder(
+        &mut tx,
+        user_id,
+        folder_id,
+        data.name.as_deref(),
+        data.parent_id,
+    )
+    .await;
+    let folder = match result {
+        Ok(v) => v,
+        Err(FolderError::Database(err)) => return Err(err.into()),
+        Err(err) => return Ok((err.status_code(), err.to_string()).into_response()),
+    };
+    tx.commit().await?;
+
+    Ok(Json(folder).into_response())
+}
+
+/// Remove a folder and its subfolders. The files inside are moved to the top level.
+async fn handle_delete(
+    auth: Authentication,
+    State(db): State<PgPool>,
+    Path((user_id, folder_id)): Path<(Uuid, Uuid)>,
+) -> error::Result<Response> {
+    if !(auth.is_admin || auth.user_id == user_id) {
+        return Ok(StatusCode::FORBIDDEN.into_response());
+    }
+
+    if !remove_folder(&db, user_id, folder_id).await? {
+        return Ok(StatusCode::NOT_FOUND.into_response());
+    }
+    Ok(().into_response())
+}
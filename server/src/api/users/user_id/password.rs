@@ -1,5 +1,5 @@
 use anyhow::Context;
-use argon2::{password_hash::SaltString, Argon2, PasswordHash, PasswordHasher, PasswordVerifier};
+use argon2::{Argon2, PasswordHash, PasswordVerifier};
 use axum::{
     extract::{Path, State},
     response::{IntoResponse, Response},
@@ -7,13 +7,19 @@ use axum::{
     Json, Router,
 };
 use http::StatusCode;
-use rand::rngs::OsRng;
 use serde::Deserialize;
 use sqlx::PgPool;
 use tracing::warn;
 use uuid::Uuid;
 
-use crate::{auth::Authentication, error, state::AppState};
+use crate::{
+    audit::{AuditAction, AuditEvent},
+    auth::Authentication,
+    client::ClientInfo,
+    error,
+    state::AppState,
+    user::hash_password,
+};
 
 pub(super) fn router() -> Router<AppState> {
     Router::new().route("/", put(handle_put))
@@ -30,6 +36,7 @@ struct PutData {
 /// Update a user's password.
 async fn handle_put(
     auth: Authentication,
+    client: ClientInfo,
     State(db): State<PgPool>,
     Path(user_id): Path<Uuid>,
     Json(data): Json<PutData>,
@@ -79,26 +86,25 @@ async fn handle_put(
         if let Err(err) = hash_check {
             tx.commit().await?;
             warn!("Authentication for user `{}` failed: {err:?}", row.username);
+            AuditEvent::new(AuditAction::PasswordChanged)
+                .by(&auth)
+                .target("user", user_id)
+                .ip(client.ip)
+                .failed()
+                .details(serde_json::json!({ "reason": "wrong password" }))
+                .record(&db)
+                .await;
 
             return Ok(StatusCode::UNAUTHORIZED.into_response());
         }
     }
 
-    let password = data.target_new_password;
-    let password_hash = tokio::task::spawn_blocking(move || {
-        let salt = SaltString::generate(&mut OsRng);
-        Argon2::default()
-            .hash_password(password.as_bytes(), &salt)
-            .map(|hash| hash.to_string())
-    })
-    .await
-    .context("could not join hasher thread")?
-    .context("failed to hash password")?;
+    let password_hash = hash_password(data.target_new_password).await?;
 
     sqlx::query!(
         r#"
             update users
-                set password_hash=$2
+                set password_hash=$2, must_change_password=false
                 where user_id=$1
         "#,
         user_id,
@@ -113,5 +119,13 @@ async fn handle_put(
         .await?;
 
     tx.commit().await?;
+
+    AuditEvent::new(AuditAction::PasswordChanged)
+        .by(&auth)
+        .target("user", user_id)
+        .ip(client.ip)
+        .record(&db)
+        .await;
+
     Ok(().into_response())
 }
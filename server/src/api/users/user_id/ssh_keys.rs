@@ -17,7 +17,13 @@ use sqlx::PgPool;
 use ssh_key::PublicKey;
 use uuid::Uuid;
 
-use crate::{auth::Authentication, error, state::AppState};
+use crate::{
+    audit::{AuditAction, AuditEvent},
+    auth::Authentication,
+    client::ClientInfo,
+    error,
+    state::AppState,
+};
 
 pub(super) fn router() -> Router<AppState> {
     Router::new()
@@ -73,6 +79,7 @@ struct PostData {
 
 async fn handle_post(
     auth: Authentication,
+    client: ClientInfo,
     State(db): State<PgPool>,
     Path(user_id): Path<Uuid>,
     Json(data): Json<PostData>,
@@ -129,5 +136,13 @@ async fn handle_post(
 
     tx.commit().await?;
 
+    AuditEvent::new(AuditAction::SshKeyAdded)
+        .by(&auth)
+        .target("user", user_id)
+        .ip(client.ip)
+        .details(serde_json::json!({ "name": data.name, "fingerprint": fingerprint }))
+        .record(&db)
+        .await;
+
     Ok(().into_response())
 }
@@ -9,7 +9,13 @@ use sqlx::PgPool;
 use ssh_key::Fingerprint;
 use uuid::Uuid;
 
-use crate::{auth::Authentication, error, state::AppState};
+use crate::{
+    audit::{AuditAction, AuditEvent},
+    auth::Authentication,
+    client::ClientInfo,
+    error,
+    state::AppState,
+};
 
 pub(super) fn router() -> Router<AppState> {
     Router::new().route("/", delete(handle_delete))
@@ -17,6 +23,7 @@ pub(super) fn router() -> Router<AppState> {
 
 async fn handle_delete(
     auth: Authentication,
+    client: ClientInfo,
     State(db): State<PgPool>,
     Path((user_id, fingerprint)): Path<(Uuid, Fingerprint)>,
 ) -> error::Result<Response> {
@@ -40,5 +47,13 @@ async fn handle_delete(
         return Ok(StatusCode::NOT_FOUND.into_response());
     }
 
+    AuditEvent::new(AuditAction::SshKeyRemoved)
+        .by(&auth)
+        .target("user", user_id)
+        .ip(client.ip)
+        .details(serde_json::json!({ "fingerprint": fingerprint.to_string() }))
+        .record(&db)
+        .await;
+
     Ok(().into_response())
 }
@@ -1,9 +1,50 @@
-use axum::Router;
+use axum::{
+    extract::{Path, Query, State},
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+use chrono::{serde::ts_milliseconds_option, DateTime, Duration, Utc};
+use http::StatusCode;
+use serde::Deserialize;
+use sqlx::PgPool;
+use uuid::Uuid;
 
-use crate::{auth::Authentication, state::AppState};
+use crate::{
+    auth::Authentication,
+    error,
+    state::AppState,
+    stats::{user_stats, Bucket},
+};
 
 pub(super) fn router() -> Router<AppState> {
-    Router::new()
+    Router::new().route("/", get(handle_get))
 }
 
-async fn handle_get(auth: Authentication, )
+#[derive(Deserialize)]
+struct StatsQuery {
+    /// The size of the time buckets downloads are grouped in.
+    #[serde(default)]
+    bucket: Bucket,
+    /// Only include downloads after this time in the buckets. Defaults to the last 30 days.
+    #[serde(default, with = "ts_milliseconds_option")]
+    since: Option<DateTime<Utc>>,
+}
+
+/// Get statistics about the files of a user and how often they were downloaded.
+async fn handle_get(
+    auth: Authentication,
+    State(db): State<PgPool>,
+    Path(user_id): Path<Uuid>,
+    Query(query): Query<StatsQuery>,
+) -> error::Result<Response> {
+    if !(auth.is_admin || auth.user_id == user_id) {
+        return Ok(StatusCode::UNAUTHORIZED.into_response());
+    }
+
+    let since = query
+        .since
+        .unwrap_or_else(|| Utc::now() - Duration::days(30));
+    let stats = user_stats(&db, user_id, query.bucket, since).await?;
+    Ok(Json(stats).into_response())
+}
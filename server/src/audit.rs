@@ -0,0 +1,431 @@
+use std::{fmt, net::IpAddr};
+
+use chrono::{
+    serde::{ts_milliseconds, ts_milliseconds_option},
+    DateTime, Utc,
+};
+use http::StatusCode;
+use serde::{Deserialize, Serialize};
+use sqlx::{PgExecutor, Postgres, QueryBuilder};
+use thiserror::Error;
+use tracing::error;
+use uuid::Uuid;
+
+use crate::auth::Authentication;
+
+/// The amount of events returned per page if no limit is requested.
+const DEFAULT_LIMIT: u32 = 100;
+/// The maximum amount of events that can be requested per page.
+pub const MAX_LIMIT: u32 = 1000;
+
+/// The name recorded as the actor of actions performed through the command line of the server.
+const OPERATOR_NAME: &str = "(operator)";
+
+/// A security-relevant or administrative action that is recorded in the audit log.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditAction {
+    /// A login attempt, either successful or not. The method is stored in the details.
+    Login,
+    SessionCreated,
+    PasswordChanged,
+    SshKeyAdded,
+    SshKeyRemoved,
+    InviteCreated,
+    InviteUsed,
+    UserDeleted,
+    /// A user was given or lost administrator permissions.
+    AdminChanged,
+    FileUploaded,
+    FileDownloaded,
+    FileDeleted,
+    /// The configuration file was read again while the server was running.
+    ConfigReloaded,
+    /// An administrator changed the settings stored in the database.
+    SettingsChanged,
+}
+
+impl AuditAction {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AuditAction::Login => "login",
+            AuditAction::SessionCreated => "session_created",
+            AuditAction::PasswordChanged => "password_changed",
+            AuditAction::SshKeyAdded => "ssh_key_added",
+            AuditAction::SshKeyRemoved => "ssh_key_removed",
+            AuditAction::InviteCreated => "invite_created",
+            AuditAction::InviteUsed => "invite_used",
+            AuditAction::UserDeleted => "user_deleted",
+            AuditAction::AdminChanged => "admin_changed",
+            AuditAction::FileUploaded => "file_uploaded",
+            AuditAction::FileDownloaded => "file_downloaded",
+            AuditAction::FileDeleted => "file_deleted",
+            AuditAction::ConfigReloaded => "config_reloaded",
+            AuditAction::SettingsChanged => "settings_changed",
+        }
+    }
+}
+
+impl fmt::Display for AuditAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// An event that is about to be recorded in the audit log.
+///
+/// ```ignore
+/// AuditEvent::new(AuditAction::FileDeleted)
+///     .by(&auth)
+///     .target("file", file_id)
+///     .ip(client.ip)
+///     .record(&db)
+///     .await;
+/// ```
+#[derive(Debug)]
+pub struct AuditEvent {
+    action: AuditAction,
+    actor_id: Option<Uuid>,
+    actor_name: Option<String>,
+    target_type: Option<&'static str>,
+    target_id: Option<String>,
+    ip: Option<IpAddr>,
+    success: bool,
+    details: Option<serde_json::Value>,
+}
+
+impl AuditEvent {
+    pub fn new(action: AuditAction) -> Self {
+        Self {
+            action,
+            actor_id: None,
+            actor_name: None,
+            target_type: None,
+            target_id: None,
+            ip: None,
+            success: true,
+            details: None,
+        }
+    }
+
+    /// Sets the authenticated user that performed the action.
+    pub fn by(self, auth: &Authentication) -> Self {
+        self.actor(Some(auth.user_id), &auth.username)
+    }
+
+    /// Sets the user that performed the action. The user ID is not known for failed logins, in
+    /// which case only the name that was used is recorded.
+    pub fn actor(mut self, user_id: Option<Uuid>, username: &str) -> Self {
+        self.actor_id = user_id;
+        self.actor_name = Some(username.to_string());
+        self
+    }
+
+    /// Marks the action as performed by an operator using the command line of the server.
+    pub fn by_operator(mut self) -> Self {
+        self.actor_name = Some(OPERATOR_NAME.to_string());
+        self
+    }
+
+    /// Sets the user that performed the action when only their ID is known.
+    pub fn actor_id(mut self, user_id: Uuid) -> Self {
+        self.actor_id = Some(user_id);
+        self
+    }
+
+    /// Sets what the action was performed on, such as `("file", file_id)`.
+    pub fn target(mut self, kind: &'static str, id: impl ToString) -> Self {
+        self.target_type = Some(kind);
+        self.target_id = Some(id.to_string());
+        self
+    }
+
+    /// Sets the IP address the request came from.
+    pub fn ip(mut self, ip: IpAddr) -> Self {
+        self.ip = Some(ip);
+        self
+    }
+
+    /// Marks the action as having failed, such as a login with the wrong password.
+    pub fn failed(mut self) -> Self {
+        self.success = false;
+        self
+    }
+
+    /// Sets additional information about the action.
+    pub fn details(mut self, details: serde_json::Value) -> Self {
+        self.details = Some(details);
+        self
+    }
+
+    /// Stores the event. Failing to store an event is logged, but does not fail the action itself.
+    pub async fn record(self, db: impl PgExecutor<'_>) {
+        let result = sqlx::query!(
+            r#"
+                insert into audit_log (
+                    action, actor_id, actor_name, target_type, target_id, ip, success, details
+                )
+                    values ($1, $2, $3, $4, $5, $6::text::inet, $7, $8)
+            "#,
+            self.action.as_str(),
+            self.actor_id,
+            self.actor_name,
+            self.target_type,
+            self.target_id,
+            self.ip.map(|v| v.to_string()),
+            self.success,
+            self.details,
+        )
+        .execute(db)
+        .await;
+
+        if let Err(err) = result {
+            error!(?self, "Could not record audit event: {err:?}");
+        }
+    }
+}
+
+/// An event stored in the audit log.
+#[derive(Debug, Serialize)]
+pub struct AuditEntry {
+    pub event_id: i64,
+    #[serde(with = "ts_milliseconds")]
+    pub created_at: DateTime<Utc>,
+    pub action: String,
+    pub actor_id: Option<Uuid>,
+    pub actor_name: Option<String>,
+    pub target_type: Option<String>,
+    pub target_id: Option<String>,
+    pub ip: Option<String>,
+    pub success: bool,
+    pub details: Option<serde_json::Value>,
+}
+
+/// An error that occurred while querying the audit log.
+#[derive(Debug, Error)]
+pub enum AuditQueryError {
+    #[error("the limit must be between 1 and {MAX_LIMIT}")]
+    InvalidLimit,
+    #[error("invalid IP address or network `{0}`")]
+    InvalidIp(String),
+    #[error(transparent)]
+    Database(#[from] sqlx::Error),
+}
+
+impl AuditQueryError {
+    /// The status code of the response when the error is returned to the user.
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            AuditQueryError::Database(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            _ => StatusCode::BAD_REQUEST,
+        }
+    }
+}
+
+/// Selects a subset of the audit log. All conditions that are set must match.
+#[derive(Debug, Default, Clone, Deserialize)]
+pub struct AuditFilter {
+    pub action: Option<AuditAction>,
+    /// Only include events performed by this user.
+    pub actor: Option<Uuid>,
+    /// Only include events on targets of this type, such as `file` or `user`.
+    pub target_type: Option<String>,
+    pub target_id: Option<String>,
+    /// Only include events from this IP address or network, such as `10.0.0.0/8`.
+    pub ip: Option<String>,
+    /// Only include successful or failed actions.
+    pub success: Option<bool>,
+    #[serde(default, with = "ts_milliseconds_option")]
+    pub after: Option<DateTime<Utc>>,
+    #[serde(default, with = "ts_milliseconds_option")]
+    pub before: Option<DateTime<Utc>>,
+}
+
+/// Pagination options for the audit log. Events are listed newest first.
+#[derive(Debug, Default, Deserialize)]
+pub struct AuditPage {
+    /// The maximum amount of events to return.
+    pub limit: Option<u32>,
+    /// The `next` cursor of the previous page. The first page is returned if this is not set.
+    pub cursor: Option<i64>,
+}
+
+/// A single page of the audit log.
+#[derive(Debug, Serialize)]
+pub struct AuditLogPage {
+    pub events: Vec<AuditEntry>,
+    /// The cursor to request the next page with, or `None` if this is the last page.
+    pub next: Option<i64>,
+}
+
+/// Lists a page of audit log events matching a filter.
+pub async fn list_events(
+    db: impl PgExecutor<'_>,
+    filter: &AuditFilter,
+    page: &AuditPage,
+) -> Result<AuditLogPage, AuditQueryError> {
+    let limit = page.limit.unwrap_or(DEFAULT_LIMIT);
+    if !(1..=MAX_LIMIT).contains(&limit) {
+        return Err(AuditQueryError::InvalidLimit);
+    }
+
+    let mut query = QueryBuilder::<Postgres>::new(
+        r#"
+            select event_id, created_at, action, actor_id, actor_name, target_type, target_id,
+                    host(ip) as ip, success, details
+                from audit_log
+                where true
+        "#,
+    );
+
+    if let Some(action) = filter.action {
+        query.push(" and action = ").push_bind(action.as_str());
+    }
+    if let Some(actor) = filter.actor {
+        query.push(" and actor_id = ").push_bind(actor);
+    }
+    if let Some(target_type) = &filter.target_type {
+        query
+            .push(" and target_type = ")
+            .push_bind(target_type.clone());
+    }
+    if let Some(target_id) = &filter.target_id {
+        query.push(" and target_id = ").push_bind(target_id.clone());
+    }
+    if let Some(ip) = &filter.ip {
+        if !is_valid_network(ip) {
+            return Err(AuditQueryError::InvalidIp(ip.clone()));
+        }
+        query
+            .push(" and ip <<= ")
+            .push_bind(ip.clone())
+            .push("::text::inet");
+    }
+    if let Some(success) = filter.success {
+        query.push(" and success = ").push_bind(success);
+    }
+    if let Some(after) = filter.after {
+        query.push(" and created_at >= ").push_bind(after);
+    }
+    if let Some(before) = filter.before {
+        query.push(" and created_at < ").push_bind(before);
+    }
+    if let Some(cursor) = page.cursor {
+        query.push(" and event_id < ").push_bind(cursor);
+    }
+
+    // One more event than requested is fetched to find out whether there is a next page.
+    query
+        .push(" order by event_id desc limit ")
+        .push_bind(limit as i64 + 1);
+
+    let mut events: Vec<AuditEntry> = query
+        .build_query_as::<AuditEntryRow>()
+        .fetch_all(db)
+        .await?
+        .into_iter()
+        .map(Into::into)
+        .collect();
+
+    let next = if events.len() > limit as usize {
+        events.truncate(limit as usize);
+        events.last().map(|v| v.event_id)
+    } else {
+        None
+    };
+
+    Ok(AuditLogPage { events, next })
+}
+
+#[derive(sqlx::FromRow)]
+struct AuditEntryRow {
+    event_id: i64,
+    created_at: DateTime<Utc>,
+    action: String,
+    actor_id: Option<Uuid>,
+    actor_name: Option<String>,
+    target_type: Option<String>,
+    target_id: Option<String>,
+    ip: Option<String>,
+    success: bool,
+    details: Option<serde_json::Value>,
+}
+
+impl From<AuditEntryRow> for AuditEntry {
+    fn from(row: AuditEntryRow) -> Self {
+        AuditEntry {
+            event_id: row.event_id,
+            created_at: row.created_at,
+            action: row.action,
+            actor_id: row.actor_id,
+            actor_name: row.actor_name,
+            target_type: row.target_type,
+            target_id: row.target_id,
+            ip: row.ip,
+            success: row.success,
+            details: row.details,
+        }
+    }
+}
+
+/// Checks that a string is an IP address, optionally followed by a prefix length such as `/24`.
+fn is_valid_network(network: &str) -> bool {
+    let (addr, prefix) = match network.split_once('/') {
+        Some((addr, prefix)) => (addr, Some(prefix)),
+        None => (network, None),
+    };
+    let Ok(addr) = addr.parse::<IpAddr>() else {
+        return false;
+    };
+    let max_prefix = if addr.is_ipv4() { 32 } else { 128 };
+    prefix.is_none_or(|v| v.parse::<u8>().is_ok_and(|v| v <= max_prefix))
+}
+
+/// The column names of an audit log CSV export.
+pub const CSV_HEADER: &str =
+    "event_id,created_at,action,actor_id,actor_name,target_type,target_id,ip,success,details\r\n";
+
+impl AuditEntry {
+    /// Formats the event as a line of CSV, in the column order of [CSV_HEADER].
+    pub fn to_csv_line(&self) -> String {
+        let fields = [
+            self.event_id.to_string(),
+            self.created_at.to_rfc3339(),
+            self.action.clone(),
+            self.actor_id.map(|v| v.to_string()).unwrap_or_default(),
+            self.actor_name.clone().unwrap_or_default(),
+            self.target_type.clone().unwrap_or_default(),
+            self.target_id.clone().unwrap_or_default(),
+            self.ip.clone().unwrap_or_default(),
+            self.success.to_string(),
+            self.details
+                .as_ref()
+                .map(|v| v.to_string())
+                .unwrap_or_default(),
+        ];
+
+        let mut line = fields
+            .iter()
+            .map(|v| csv_field(v))
+            .collect::<Vec<_>>()
+            .join(",");
+        line.push_str("\r\n");
+        line
+    }
+}
+
+/// Escapes a CSV field. Fields that a spreadsheet would interpret as a formula are prefixed with
+/// a quote, as usernames and file names are chosen by users.
+fn csv_field(value: &str) -> String {
+    let value = if value.starts_with(['=', '+', '-', '@', '\t', '\r']) {
+        format!("'{value}")
+    } else {
+        value.to_string()
+    };
+
+    if value.contains([',', '"', '\r', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value
+    }
+}
@@ -1,6 +1,6 @@
 pub mod ssh;
 
-use std::collections::HashMap;
+use std::{collections::HashMap, sync::Arc, time::Duration};
 
 use axum::{
     async_trait,
@@ -9,11 +9,14 @@ use axum::{
 };
 use axum_extra::extract::CookieJar;
 use sqlx::PgPool;
-use tokio::sync::Mutex;
+use tokio::{
+    sync::Mutex,
+    time::{sleep, Instant},
+};
 use tracing::{debug, error, trace, warn};
 use uuid::Uuid;
 
-use crate::session::SessionToken;
+use crate::{metrics::METRICS, session::SessionToken};
 
 /// A HTTP extractor that checks if the user is authorized and provides the user info if this is
 /// the case. Returns an UNAUTHORIZED response otherwise.
@@ -49,6 +52,7 @@ where
             .parse::<SessionToken>()
             .map_err(|err| {
                 debug!("Unable to parse `session-token` cookie: {err}");
+                METRICS.auth_failure("session");
                 StatusCode::UNAUTHORIZED
             })?;
 
@@ -71,6 +75,7 @@ where
         })?
         .ok_or_else(|| {
             warn!("Unknown session token used");
+            METRICS.auth_failure("session");
             StatusCode::UNAUTHORIZED
         })?;
 
@@ -83,8 +88,39 @@ where
     }
 }
 
+/// The time after the last failed authentication attempt at which the failures of a user are
+/// forgotten.
+pub const AUTH_FAILURE_RESET: Duration = Duration::from_secs(60 * 60);
+
 #[derive(Default)]
 pub struct UserAuthFailures {
     /// Maps users to the amount of authentication failures and the last time a failure happened.
     pub users: Mutex<HashMap<Uuid, (u16, tokio::time::Instant)>>,
 }
+
+impl UserAuthFailures {
+    /// Initialize a new [UserAuthFailures] along with a cleanup task that removes users whose
+    /// failures have been reset.
+    pub fn init() -> Arc<Self> {
+        let failures = Arc::new(Self::default());
+
+        let weak_failures = Arc::downgrade(&failures);
+        tokio::spawn(async move {
+            loop {
+                sleep(Duration::from_secs(60)).await;
+                let Some(failures) = weak_failures.upgrade() else {
+                    return;
+                };
+
+                let now = Instant::now();
+                failures
+                    .users
+                    .lock()
+                    .await
+                    .retain(|_, (_, last_failure)| now - *last_failure <= AUTH_FAILURE_RESET);
+            }
+        });
+
+        failures
+    }
+}
@@ -81,6 +81,11 @@ impl SSHAuthState {
         }
     }
 
+    /// The amount of tickets that have been handed out, but not used or cleaned up yet.
+    pub async fn ticket_count(&self) -> usize {
+        self.tickets.lock().await.len()
+    }
+
     /// Check if a ticket sent by a client is valid.
     pub async fn validate_response_ticket(
         &self,
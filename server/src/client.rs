@@ -0,0 +1,144 @@
+use std::{
+    convert::Infallible,
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    sync::Arc,
+};
+
+use axum::{
+    async_trait,
+    extract::{ConnectInfo, FromRef, FromRequestParts},
+    http::{request::Parts, HeaderMap, HeaderName},
+};
+use tracing::trace;
+
+use crate::config::Config;
+
+const X_FORWARDED_FOR: HeaderName = HeaderName::from_static("x-forwarded-for");
+const X_FORWARDED_PROTO: HeaderName = HeaderName::from_static("x-forwarded-proto");
+
+/// A HTTP extractor for the client that made a request.
+///
+/// Requests from one of the `trusted_proxies` are attributed to the client the proxy forwarded
+/// them for, as given by the `Forwarded` or `X-Forwarded-For` header. These headers are ignored
+/// for every other request, as clients could claim any address using them.
+#[derive(Clone, Copy, Debug)]
+pub struct ClientInfo {
+    /// The address of the client.
+    pub ip: IpAddr,
+    /// True if the client used HTTPS. Requests that don't say otherwise are assumed to use the
+    /// scheme of `external_url`, or HTTPS if the server serves it itself.
+    pub https: bool,
+}
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for ClientInfo
+where
+    Arc<Config>: FromRef<S>,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let config = Arc::<Config>::from_ref(state);
+        // Only requests to the metrics address lack the address of the peer, and they don't use
+        // it.
+        let peer = parts
+            .extensions
+            .get::<ConnectInfo<SocketAddr>>()
+            .map_or(IpAddr::V4(Ipv4Addr::UNSPECIFIED), |v| {
+                v.0.ip().to_canonical()
+            });
+
+        Ok(client_info(peer, &parts.headers, &config))
+    }
+}
+
+/// Determines the client of a request received from `peer`.
+fn client_info(peer: IpAddr, headers: &HeaderMap, config: &Config) -> ClientInfo {
+    let default_https = config.tls.files().is_some() || config.external_url.starts_with("https://");
+    if !config.is_trusted_proxy(peer) {
+        return ClientInfo {
+            ip: peer,
+            https: default_https,
+        };
+    }
+
+    let forwarded = header_values(headers, &http::header::FORWARDED);
+    let (chain, proto) = if forwarded.is_empty() {
+        let chain = header_values(headers, &X_FORWARDED_FOR);
+        let proto = header_values(headers, &X_FORWARDED_PROTO).pop();
+        (chain, proto)
+    } else {
+        let elements: Vec<_> = forwarded.iter().map(|v| parse_forwarded(v)).collect();
+        let chain = elements
+            .iter()
+            .map(|(node, _)| node.clone().unwrap_or_default())
+            .collect();
+        let proto = elements.last().and_then(|(_, proto)| proto.clone());
+        (chain, proto)
+    };
+
+    // Each proxy appends the address it received the request from, so the client is the last
+    // address that isn't a trusted proxy. Addresses that can't be parsed, such as `unknown`,
+    // can't be checked, so the proxy that added them is used instead.
+    let mut ip = peer;
+    for node in chain.iter().rev() {
+        let Some(addr) = parse_node(node) else {
+            trace!(node, "Could not parse forwarded address");
+            break;
+        };
+        ip = addr.to_canonical();
+        if !config.is_trusted_proxy(addr) {
+            break;
+        }
+    }
+
+    let https = match proto {
+        Some(proto) => proto.eq_ignore_ascii_case("https"),
+        None => default_https,
+    };
+    ClientInfo { ip, https }
+}
+
+/// Returns the comma separated values of every occurrence of a header.
+fn header_values(headers: &HeaderMap, name: &HeaderName) -> Vec<String> {
+    headers
+        .get_all(name)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+        .collect()
+}
+
+/// Parses an element of the `Forwarded` header, such as `for=192.0.2.60;proto=https`, into its
+/// `for` and `proto` parameters.
+fn parse_forwarded(element: &str) -> (Option<String>, Option<String>) {
+    let (mut node, mut proto) = (None, None);
+    for pair in element.split(';') {
+        let Some((key, value)) = pair.split_once('=') else {
+            continue;
+        };
+        let value = value.trim().trim_matches('"').to_string();
+        match key.trim().to_ascii_lowercase().as_str() {
+            "for" => node = Some(value),
+            "proto" => proto = Some(value),
+            _ => {}
+        }
+    }
+    (node, proto)
+}
+
+/// Parses a forwarded address, which may include a port, such as `192.0.2.60:4711` or
+/// `[2001:db8::1]:4711`.
+fn parse_node(node: &str) -> Option<IpAddr> {
+    node.parse::<IpAddr>()
+        .ok()
+        .or_else(|| node.parse::<SocketAddr>().ok().map(|v| v.ip()))
+        .or_else(|| {
+            node.strip_prefix('[')
+                .and_then(|v| v.strip_suffix(']'))?
+                .parse()
+                .ok()
+        })
+}
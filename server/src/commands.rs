@@ -0,0 +1,121 @@
+pub mod invite;
+pub mod user;
+
+use anyhow::{anyhow, Context};
+use clap::Args;
+use sqlx::PgPool;
+
+use crate::{
+    file::FileDb,
+    fsck::{self, BrokenContent, ORPHAN_GRACE_PERIOD},
+    health, session, MIGRATOR,
+};
+
+/// Applies all pending migrations to the database.
+pub async fn migrate(db: &PgPool) -> anyhow::Result<()> {
+    let pending = health::pending_migrations(db).await?;
+    MIGRATOR
+        .run(db)
+        .await
+        .context("error while migrating database")?;
+
+    if pending.is_empty() {
+        println!("The database is up to date.");
+    } else {
+        println!("Applied {} migrations.", pending.len());
+    }
+    Ok(())
+}
+
+/// Fails if the database has pending migrations, as the commands expect an up to date schema.
+pub async fn ensure_migrated(db: &PgPool) -> anyhow::Result<()> {
+    let pending = health::pending_migrations(db).await?;
+    if !pending.is_empty() {
+        return Err(anyhow!(
+            "the database has {} pending migrations, run `beacon-server migrate` first",
+            pending.len()
+        ));
+    }
+    Ok(())
+}
+
+/// Check that the file store matches the database.
+#[derive(Args)]
+pub struct FsckArgs {
+    /// Quarantine orphaned content and mark files with missing or damaged content as broken.
+    #[arg(long)]
+    fix: bool,
+    /// Print the report as JSON.
+    #[arg(long)]
+    json: bool,
+}
+
+/// Checks the file store, failing if problems were found that have not been fixed.
+pub async fn fsck(file_db: &FileDb, args: FsckArgs) -> anyhow::Result<()> {
+    let report = fsck::check(file_db, args.fix).await?;
+
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        println!("Checked {} files and versions.", report.checked);
+        for path in &report.orphans {
+            println!("orphaned: {}", path.display());
+        }
+        for content in &report.missing {
+            println!("missing: {}", describe(content));
+        }
+        for content in &report.size_mismatches {
+            println!(
+                "size mismatch: {}, expected {} bytes, found {}",
+                describe(content),
+                content.expected_size,
+                content.actual_size.unwrap_or(0),
+            );
+        }
+        for file_id in &report.recovered {
+            println!("recovered: {file_id}");
+        }
+        if let Some(dir) = &report.quarantine_dir {
+            println!("Moved orphaned content to `{}`.", dir.display());
+        }
+    }
+
+    let problems = report.problem_count();
+    if problems > 0 && !report.fixed {
+        return Err(anyhow!(
+            "found {problems} problems, run with `--fix` to repair them"
+        ));
+    }
+    Ok(())
+}
+
+/// Describes a file or older version, such as `1a2b3c version 2`.
+fn describe(content: &BrokenContent) -> String {
+    match content.version {
+        Some(version) => format!("{} version {version}", content.file_id),
+        None => content.file_id.to_string(),
+    }
+}
+
+/// Removes expired files and sessions, as well as orphaned content in the file store.
+///
+/// Orphaned content is only removed once it is old enough to not belong to an upload that is
+/// still in progress, so this can be run while the server is running.
+pub async fn gc(file_db: &FileDb) -> anyhow::Result<()> {
+    let files = file_db
+        .remove_expired()
+        .await
+        .context("could not remove expired files")?;
+    let sessions = session::remove_expired(file_db.db())
+        .await
+        .context("could not remove expired sessions")?;
+    let orphans = file_db
+        .remove_orphans(ORPHAN_GRACE_PERIOD)
+        .await
+        .context("could not remove orphaned content")?;
+
+    println!(
+        "Removed {files} expired files, {sessions} expired sessions and {orphans} orphaned entries."
+    );
+    Ok(())
+}
@@ -0,0 +1,73 @@
+use std::time::Duration;
+
+use anyhow::{anyhow, Context};
+use chrono::Utc;
+use clap::{Args, Subcommand};
+use sqlx::PgPool;
+
+use super::user::find_user;
+use crate::{
+    audit::{AuditAction, AuditEvent},
+    invite::{create_invite, random_code},
+};
+
+/// Manage invite codes.
+#[derive(Subcommand)]
+pub enum InviteCommand {
+    /// Create a new invite code.
+    Create(CreateArgs),
+}
+
+#[derive(Args)]
+pub struct CreateArgs {
+    /// The invite code. A random code is generated if unset.
+    code: Option<String>,
+    /// How long the invite remains valid, for example `12h` or `7d`.
+    #[arg(long, value_parser = humantime::parse_duration, default_value = "7d")]
+    valid_for: Duration,
+    /// How many accounts can be registered using the invite.
+    #[arg(long, default_value_t = 1)]
+    max_uses: u16,
+    /// The name or ID of the user the invite is created by. Defaults to the oldest administrator.
+    #[arg(long, value_name = "USER")]
+    created_by: Option<String>,
+}
+
+pub async fn run(db: &PgPool, command: InviteCommand) -> anyhow::Result<()> {
+    match command {
+        InviteCommand::Create(args) => create(db, args).await,
+    }
+}
+
+async fn create(db: &PgPool, args: CreateArgs) -> anyhow::Result<()> {
+    let created_by = match &args.created_by {
+        Some(user) => find_user(db, user).await?.0,
+        None => sqlx::query!(
+            "select user_id from users where is_admin order by created_at limit 1"
+        )
+        .fetch_optional(db)
+        .await?
+        .map(|row| row.user_id)
+        .context(
+            "there is no administrator, create one using `beacon-server user create --admin`",
+        )?,
+    };
+
+    let valid_until = Utc::now()
+        + chrono::Duration::from_std(args.valid_for).context("validity duration is too long")?;
+    let invite_code = args.code.unwrap_or_else(random_code);
+
+    if !create_invite(db, &invite_code, args.max_uses, valid_until, created_by).await? {
+        return Err(anyhow!("the invite `{invite_code}` already exists"));
+    }
+
+    AuditEvent::new(AuditAction::InviteCreated)
+        .by_operator()
+        .target("invite", &invite_code)
+        .details(serde_json::json!({ "max_uses": args.max_uses, "valid_until": valid_until }))
+        .record(db)
+        .await;
+
+    println!("{invite_code}");
+    Ok(())
+}
@@ -0,0 +1,288 @@
+use std::io::{self, BufRead, IsTerminal};
+
+use anyhow::{anyhow, Context};
+use clap::{Args, Subcommand};
+use num_traits::ToPrimitive;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    audit::{AuditAction, AuditEvent},
+    file::{FileDb, FileStore},
+    user::{
+        create_user, delete_user, hash_password, remove_contents, remove_files, transfer_files,
+        transfer_team_files,
+    },
+};
+
+/// Manage users.
+#[derive(Subcommand)]
+pub enum UserCommand {
+    /// Create a new user.
+    Create(CreateArgs),
+    /// List all users.
+    List,
+    /// Remove a user along with their files.
+    Delete(DeleteArgs),
+    /// Give a user administrator permissions or take them away.
+    SetAdmin(SetAdminArgs),
+    /// Set a new password for a user. The user is logged out everywhere.
+    ResetPassword(ResetPasswordArgs),
+}
+
+#[derive(Args)]
+pub struct CreateArgs {
+    /// The name of the new user.
+    username: String,
+    /// Give the user administrator permissions.
+    #[arg(long)]
+    admin: bool,
+    /// Read the password from stdin instead of asking for it.
+    #[arg(long)]
+    password_stdin: bool,
+}
+
+#[derive(Args)]
+pub struct DeleteArgs {
+    /// The name or ID of the user.
+    user: String,
+    /// Transfer the user's files to another user instead of removing them.
+    #[arg(long, value_name = "USER")]
+    transfer_to: Option<String>,
+}
+
+#[derive(Args)]
+pub struct SetAdminArgs {
+    /// The name or ID of the user.
+    user: String,
+    /// Take administrator permissions away instead of giving them.
+    #[arg(long)]
+    revoke: bool,
+}
+
+#[derive(Args)]
+pub struct ResetPasswordArgs {
+    /// The name or ID of the user.
+    user: String,
+    /// Read the password from stdin instead of asking for it.
+    #[arg(long)]
+    password_stdin: bool,
+}
+
+pub async fn run(file_db: &FileDb, command: UserCommand) -> anyhow::Result<()> {
+    let db = file_db.db();
+    match command {
+        UserCommand::Create(args) => create(db, args).await,
+        UserCommand::List => list(db).await,
+        UserCommand::Delete(args) => delete(db, file_db.file_store(), args).await,
+        UserCommand::SetAdmin(args) => set_admin(db, args).await,
+        UserCommand::ResetPassword(args) => reset_password(db, args).await,
+    }
+}
+
+async fn create(db: &PgPool, args: CreateArgs) -> anyhow::Result<()> {
+    if args.username.is_empty() {
+        return Err(anyhow!("the username can not be empty"));
+    }
+    let password_hash = hash_password(read_password(args.password_stdin)?).await?;
+
+    let user_id = create_user(db, &args.username, &password_hash, args.admin)
+        .await
+        .context("failed to create user in database")?
+        .ok_or_else(|| anyhow!("the username `{}` is taken", args.username))?;
+
+    if args.admin {
+        AuditEvent::new(AuditAction::AdminChanged)
+            .by_operator()
+            .target("user", user_id)
+            .details(serde_json::json!({ "is_admin": true }))
+            .record(db)
+            .await;
+    }
+
+    println!("Created user `{}` with ID {user_id}.", args.username);
+    Ok(())
+}
+
+async fn list(db: &PgPool) -> anyhow::Result<()> {
+    let rows = sqlx::query!(
+        r#"
+            select user_id, username, is_admin, created_at, sum(files.file_size) as "total_size"
+                from users
+                    left outer join files on users.user_id=files.uploader_id
+                group by users.user_id
+                order by created_at
+        "#,
+    )
+    .fetch_all(db)
+    .await
+    .context("could not fetch users")?;
+
+    println!(
+        "{:<36}  {:<24}  {:<5}  {:>14}  CREATED",
+        "ID", "USERNAME", "ADMIN", "STORAGE"
+    );
+    for row in rows {
+        println!(
+            "{:<36}  {:<24}  {:<5}  {:>14}  {}",
+            row.user_id,
+            row.username,
+            row.is_admin,
+            row.total_size.and_then(|v| v.to_u64()).unwrap_or(0),
+            row.created_at.format("%Y-%m-%d %H:%M"),
+        );
+    }
+    Ok(())
+}
+
+async fn delete(db: &PgPool, store: &FileStore, args: DeleteArgs) -> anyhow::Result<()> {
+    let (user_id, username) = find_user(db, &args.user).await?;
+
+    let mut tx = db.begin().await?;
+
+    let mut removed = Vec::new();
+    let (transfer_to, outcome) = match &args.transfer_to {
+        Some(new_owner) => {
+            let (new_owner, new_owner_name) = find_user(db, new_owner).await?;
+            if new_owner == user_id {
+                return Err(anyhow!(
+                    "files can not be transferred to the user being removed"
+                ));
+            }
+            transfer_files(&mut tx, user_id, new_owner, None).await?;
+            (
+                Some(new_owner),
+                format!("their files now belong to `{new_owner_name}`"),
+            )
+        }
+        None => {
+            removed = remove_files(&mut tx, user_id).await?;
+            (None, format!("{} files were removed", removed.len()))
+        }
+    };
+
+    if !transfer_team_files(&mut tx, user_id).await? {
+        return Err(anyhow!(
+            "the user is the last member of a team that owns files they uploaded"
+        ));
+    }
+
+    delete_user(&mut tx, user_id).await?;
+
+    tx.commit().await?;
+    remove_contents(store, &removed).await;
+
+    AuditEvent::new(AuditAction::UserDeleted)
+        .by_operator()
+        .target("user", user_id)
+        .details(serde_json::json!({
+            "transfer_to_user": transfer_to,
+            "transfer_to_team": null,
+        }))
+        .record(db)
+        .await;
+
+    println!("Removed user `{username}`, {outcome}.");
+    Ok(())
+}
+
+async fn set_admin(db: &PgPool, args: SetAdminArgs) -> anyhow::Result<()> {
+    let (user_id, username) = find_user(db, &args.user).await?;
+    let is_admin = !args.revoke;
+
+    sqlx::query!(
+        "update users set is_admin=$2 where user_id=$1",
+        user_id,
+        is_admin,
+    )
+    .execute(db)
+    .await?;
+
+    AuditEvent::new(AuditAction::AdminChanged)
+        .by_operator()
+        .target("user", user_id)
+        .details(serde_json::json!({ "is_admin": is_admin }))
+        .record(db)
+        .await;
+
+    if is_admin {
+        println!("`{username}` is now an administrator.");
+    } else {
+        println!("`{username}` is no longer an administrator.");
+    }
+    Ok(())
+}
+
+async fn reset_password(db: &PgPool, args: ResetPasswordArgs) -> anyhow::Result<()> {
+    let (user_id, username) = find_user(db, &args.user).await?;
+    let password_hash = hash_password(read_password(args.password_stdin)?).await?;
+
+    let mut tx = db.begin().await?;
+
+    sqlx::query!(
+        "update users set password_hash=$2, must_change_password=false where user_id=$1",
+        user_id,
+        password_hash,
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    // Remove all active sessions for the user, forcing them to log in again.
+    sqlx::query!("delete from sessions where user_id=$1", user_id)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+
+    AuditEvent::new(AuditAction::PasswordChanged)
+        .by_operator()
+        .target("user", user_id)
+        .record(db)
+        .await;
+
+    println!("Changed the password of `{username}`.");
+    Ok(())
+}
+
+/// Finds a user by their name or ID, returning both.
+pub(super) async fn find_user(db: &PgPool, user: &str) -> anyhow::Result<(Uuid, String)> {
+    let user_id = user.parse::<Uuid>().ok();
+    let row = sqlx::query!(
+        "select user_id, username from users where user_id=$1 or username=$2",
+        user_id,
+        user,
+    )
+    .fetch_optional(db)
+    .await?;
+
+    match row {
+        Some(row) => Ok((row.user_id, row.username)),
+        None => Err(anyhow!("user `{user}` does not exist")),
+    }
+}
+
+/// Reads a new password, either from a single line on stdin or by asking for it.
+fn read_password(from_stdin: bool) -> anyhow::Result<String> {
+    let password = if from_stdin {
+        let mut line = String::new();
+        io::stdin()
+            .lock()
+            .read_line(&mut line)
+            .context("could not read from stdin")?;
+        line.trim_end_matches(['\r', '\n']).to_string()
+    } else if io::stdin().is_terminal() {
+        dialoguer::Password::new()
+            .with_prompt("Password")
+            .with_confirmation("Repeat password", "The passwords don't match")
+            .interact()?
+    } else {
+        return Err(anyhow!(
+            "stdin is not a terminal, use `--password-stdin` to read the password from it"
+        ));
+    };
+
+    if password.is_empty() {
+        return Err(anyhow!("the password can not be empty"));
+    }
+    Ok(password)
+}
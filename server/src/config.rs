@@ -1,44 +1,664 @@
-use std::{env, io::ErrorKind};
+use std::{
+    collections::HashSet,
+    env,
+    io::ErrorKind,
+    net::{IpAddr, SocketAddr},
+    path::{Path, PathBuf},
+};
 
-use serde::{Deserialize, Serialize};
+use anyhow::{bail, Context};
+use chrono::{DateTime, Duration, Utc};
+use ipnet::IpNet;
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize};
 use tokio::fs;
+use tracing_subscriber::EnvFilter;
+use url::Url;
 
-/// The configuration of the application.
-#[derive(Deserialize)]
-pub struct Config {
-    #[serde(flatten)]
-    pub public_config: PublicConfig,
-}
+use crate::rate_limit::Policy;
 
-/// The publicly visible part of the config. Used for settings that also affect the frontend.
+mod reload;
+mod settings;
+
+pub use reload::{reload_on_sighup, ConfigHandle};
+pub use settings::{Settings, SettingsError};
+
+/// The configuration file used if no other file is given.
+const DEFAULT_CONFIG_PATH: &str = "beacon.toml";
+/// Environment variables that override a single setting.
+const ENV_SETTINGS: &[(&str, &str)] = &[
+    ("BEACON_SERVER_ADDR", "bind_addr"),
+    ("DATABASE_URL", "database.url"),
+    ("FILE_STORE_ROOT", "storage.root"),
+    ("EXTERNAL_URL", "external_url"),
+    ("RUST_LOG", "logging.level"),
+];
+/// Environment variables starting with this prefix override any setting, using `__` to separate
+/// keys, such as `BEACON__DATABASE__POOL_SIZE`.
+const ENV_PREFIX: &str = "BEACON__";
+/// The longest time a session can remain valid, in seconds.
+const MAX_SESSION_LIFETIME: u64 = 10 * 365 * 24 * 60 * 60;
+/// The longest time files can be kept by default, in seconds.
+const MAX_DEFAULT_FILE_EXPIRY: u64 = 365 * 24 * 60 * 60;
+/// The maximum length of the name of the instance in bytes.
+const MAX_INSTANCE_NAME_LEN: usize = 64;
+
+/// The configuration of the application.
 #[derive(Deserialize, Serialize, Clone)]
-pub struct PublicConfig {
+#[serde(deny_unknown_fields)]
+pub struct Config {
     /// Allows new accounts to be registered on the site.
     #[serde(default = "defaults::bool_true")]
     pub allow_registering: bool,
     /// If set to false, users need an invite code in order to register.
     #[serde(default)]
     pub disable_invite_codes: bool,
+    /// The name of the instance shown in the panel.
+    #[serde(default = "defaults::instance_name")]
+    pub instance_name: String,
+    /// The address the server listens on.
+    #[serde(default = "defaults::bind_addr")]
+    pub bind_addr: SocketAddr,
+    /// The URL the instance is reachable at, used to create links. Never ends with a slash.
+    #[serde(default)]
+    pub external_url: String,
+    /// Reverse proxies whose `Forwarded`, `X-Forwarded-For` and `X-Forwarded-Proto` headers are
+    /// used to determine the client of a request. Either addresses or networks such as
+    /// `10.0.0.0/8`.
+    #[serde(default, deserialize_with = "deserialize_networks")]
+    pub trusted_proxies: Vec<IpNet>,
+    /// The maximum amount of slugs a single user can create.
+    #[serde(default = "defaults::max_slugs_per_user")]
+    pub max_slugs_per_user: u32,
+    /// The time in seconds after which uploaded files expire. Files never expire by default if
+    /// this is 0.
+    #[serde(default)]
+    pub default_file_expiry: u64,
+    /// The maximum size of a single file in bytes, or 0 for no limit.
+    #[serde(default)]
+    pub max_file_size: u64,
+    /// The maximum total size in bytes of the files a user owns outside of teams, including their
+    /// older versions, or 0 for no limit.
+    #[serde(default)]
+    pub user_quota: u64,
+    /// The maximum amount of older versions kept for a single file.
+    #[serde(default = "defaults::max_file_versions")]
+    pub max_file_versions: u32,
+    /// The minimum amount of free space in bytes in the file store. The server reports that it is
+    /// not ready when less space is available.
+    #[serde(default = "defaults::min_free_space")]
+    pub min_free_space: u64,
+    /// The time in seconds in-flight requests are given to finish when shutting down. Requests
+    /// that take longer are aborted.
+    #[serde(default = "defaults::drain_timeout")]
+    pub drain_timeout: u64,
+    #[serde(default)]
+    pub database: DatabaseConfig,
+    #[serde(default)]
+    pub storage: StorageConfig,
+    #[serde(default)]
+    pub sessions: SessionConfig,
+    #[serde(default)]
+    pub limits: LimitsConfig,
+    #[serde(default)]
+    pub rate_limits: RateLimitsConfig,
+    #[serde(default)]
+    pub logging: LoggingConfig,
+    /// Exporting metrics in the Prometheus format.
+    #[serde(default)]
+    pub metrics: MetricsConfig,
+    /// Serving HTTPS directly, without a reverse proxy.
+    #[serde(default)]
+    pub tls: TlsConfig,
+}
+
+/// Settings for the connection to the database.
+#[derive(Deserialize, Serialize, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct DatabaseConfig {
+    /// The URL of the PostgreSQL database.
+    pub url: String,
+    /// The maximum amount of connections kept open.
+    pub pool_size: u32,
+    /// The time in seconds to wait for a connection before failing a request.
+    pub acquire_timeout: u64,
+}
+
+impl Default for DatabaseConfig {
+    fn default() -> Self {
+        Self {
+            url: String::new(),
+            pool_size: 10,
+            acquire_timeout: 30,
+        }
+    }
+}
+
+/// Settings for storing files.
+#[derive(Deserialize, Serialize, Default, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct StorageConfig {
+    /// The directory the content of files is stored in.
+    pub root: PathBuf,
+}
+
+/// How long sessions remain valid, in seconds.
+#[derive(Deserialize, Serialize, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct SessionConfig {
+    /// Sessions created by logging in with a password.
+    pub login_lifetime: u64,
+    /// Sessions created by registering an account.
+    pub registration_lifetime: u64,
+    /// Sessions created by the command line client using an SSH key. These are only used for a
+    /// single upload.
+    pub ssh_lifetime: u64,
+}
+
+impl Default for SessionConfig {
+    fn default() -> Self {
+        Self {
+            // 1 week.
+            login_lifetime: 7 * 24 * 60 * 60,
+            // 2 weeks.
+            registration_lifetime: 14 * 24 * 60 * 60,
+            ssh_lifetime: 60,
+        }
+    }
+}
+
+/// Limits on the size of requests.
+#[derive(Deserialize, Serialize, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct LimitsConfig {
+    /// The maximum size in bytes of request bodies that are read at once, such as JSON. Uploads
+    /// are streamed and not limited by this.
+    pub max_body_size: usize,
+}
+
+impl Default for LimitsConfig {
+    fn default() -> Self {
+        Self {
+            // 2 MiB.
+            max_body_size: 2 * 1024 * 1024,
+        }
+    }
+}
+
+/// Limits on how often requests can be made, each consisting of a limit per client address and a
+/// limit per user.
+#[derive(Deserialize, Serialize, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct RateLimitsConfig {
+    /// Whether requests are limited at all.
+    pub enabled: bool,
+    /// Logging in and the first-run setup. The user is the username that is logged in as.
+    pub auth: RateLimitPolicy,
+    /// Creating accounts, which also limits guessing invite codes.
+    pub registration: RateLimitPolicy,
+    /// Uploading files and pastes.
+    pub uploads: RateLimitPolicy,
+    /// Downloading the content of files.
+    pub downloads: RateLimitPolicy,
+}
+
+impl Default for RateLimitsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            auth: RateLimitPolicy {
+                per_ip: RateLimit::new(20, 10),
+                per_user: RateLimit::new(10, 5),
+            },
+            registration: RateLimitPolicy {
+                per_ip: RateLimit::new(5, 1),
+                per_user: RateLimit::default(),
+            },
+            uploads: RateLimitPolicy {
+                per_ip: RateLimit::new(60, 30),
+                per_user: RateLimit::new(60, 30),
+            },
+            downloads: RateLimitPolicy {
+                per_ip: RateLimit::new(300, 120),
+                per_user: RateLimit::default(),
+            },
+        }
+    }
+}
+
+impl RateLimitsConfig {
+    /// The limits of a policy.
+    pub fn policy(&self, policy: Policy) -> &RateLimitPolicy {
+        match policy {
+            Policy::Auth => &self.auth,
+            Policy::Registration => &self.registration,
+            Policy::Uploads => &self.uploads,
+            Policy::Downloads => &self.downloads,
+        }
+    }
+}
+
+/// The limits of a group of endpoints.
+#[derive(Deserialize, Serialize, Default, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct RateLimitPolicy {
+    /// The limit for each client address. IPv6 addresses are limited per /64 network.
+    pub per_ip: RateLimit,
+    /// The limit for each user.
+    pub per_user: RateLimit,
+}
+
+/// A token bucket, allowing `burst` requests at once and `per_minute` requests after that. A
+/// `burst` of 0 disables the limit.
+#[derive(Deserialize, Serialize, Default, Clone, Copy)]
+#[serde(default, deny_unknown_fields)]
+pub struct RateLimit {
+    /// The amount of requests that can be made at once.
+    pub burst: u32,
+    /// The amount of requests that become available again every minute.
+    pub per_minute: u32,
+}
+
+impl RateLimit {
+    fn new(burst: u32, per_minute: u32) -> Self {
+        Self { burst, per_minute }
+    }
+
+    /// Returns true if requests are limited.
+    pub fn is_enabled(&self) -> bool {
+        self.burst != 0
+    }
+
+    /// The amount of requests that become available again every second.
+    pub fn per_second(&self) -> f64 {
+        self.per_minute as f64 / 60.0
+    }
+}
+
+/// Settings for the logs of the server.
+#[derive(Deserialize, Serialize, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct LoggingConfig {
+    /// Which logs are written, using the syntax of `RUST_LOG`, such as `info` or
+    /// `warn,beacon_server=debug`.
+    pub level: String,
+    pub format: LogFormat,
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        Self {
+            level: "info".to_string(),
+            format: LogFormat::default(),
+        }
+    }
+}
+
+/// How logs are formatted.
+#[derive(Deserialize, Serialize, Default, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// Human readable logs.
+    #[default]
+    Full,
+    /// Shorter human readable logs.
+    Compact,
+    /// A JSON object per line.
+    Json,
+}
+
+/// Settings for the Prometheus metrics endpoint.
+#[derive(Deserialize, Serialize, Default, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct MetricsConfig {
+    /// Serve metrics at `/metrics`.
+    #[serde(default)]
+    pub enabled: bool,
+    /// Serve metrics on this address instead of the main one, so they aren't publicly reachable.
+    pub bind_addr: Option<SocketAddr>,
+}
+
+/// Settings for serving HTTPS. TLS is enabled when both a certificate and a key are given.
+#[derive(Deserialize, Serialize, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct TlsConfig {
+    /// The PEM file containing the certificate chain.
+    pub cert: Option<PathBuf>,
+    /// The PEM file containing the private key.
+    pub key: Option<PathBuf>,
+    /// Also listen for plain HTTP on this address, redirecting every request to `external_url`.
+    pub redirect_addr: Option<SocketAddr>,
+    /// How often in seconds the certificate files are checked for changes, or 0 to only reload
+    /// them on SIGHUP.
+    pub reload_interval: u64,
+}
+
+impl Default for TlsConfig {
+    fn default() -> Self {
+        Self {
+            cert: None,
+            key: None,
+            redirect_addr: None,
+            // 1 hour.
+            reload_interval: 60 * 60,
+        }
+    }
+}
+
+impl TlsConfig {
+    /// The certificate and key files, if TLS is enabled.
+    pub fn files(&self) -> Option<(&Path, &Path)> {
+        Some((self.cert.as_deref()?, self.key.as_deref()?))
+    }
+}
+
+/// The publicly visible part of the config. Used for settings that also affect the frontend.
+#[derive(Serialize, Clone)]
+pub struct PublicConfig {
+    pub allow_registering: bool,
+    pub disable_invite_codes: bool,
+    pub instance_name: String,
+    /// The maximum size of a single file in bytes, if there is a limit.
+    pub max_file_size: Option<u64>,
+    /// The time in seconds after which uploaded files expire, if they expire by default.
+    pub default_file_expiry: Option<u64>,
 }
 
 impl Config {
-    /// Read the configuration file.
-    pub async fn read() -> anyhow::Result<Self> {
-        let config_path =
-            env::var("BEACON_SERVER_CONFIG").unwrap_or_else(|_| "beacon.toml".to_string());
-
-        let config_str = match fs::read_to_string(config_path).await {
-            Ok(v) => v,
-            Err(err) if err.kind() == ErrorKind::NotFound => String::new(),
-            Err(err) => return Err(err.into()),
+    /// Reads the configuration file and applies overrides from the environment, followed by the
+    /// `overrides` given on the command line in the form of `key=value`.
+    ///
+    /// The file is read from `path`, `BEACON_SERVER_CONFIG` or `beacon.toml`, in that order. Only
+    /// the last one may be missing.
+    pub async fn read(path: Option<PathBuf>, overrides: &[String]) -> anyhow::Result<Self> {
+        let path = path.or_else(|| env::var_os("BEACON_SERVER_CONFIG").map(PathBuf::from));
+        let config_str = match &path {
+            Some(path) => fs::read_to_string(path)
+                .await
+                .with_context(|| format!("could not read config file `{}`", path.display()))?,
+            None => match fs::read_to_string(DEFAULT_CONFIG_PATH).await {
+                Ok(v) => v,
+                Err(err) if err.kind() == ErrorKind::NotFound => String::new(),
+                Err(err) => {
+                    return Err(err).context(format!(
+                        "could not read config file `{DEFAULT_CONFIG_PATH}`"
+                    ))
+                }
+            },
+        };
+        let path = path.as_deref().unwrap_or(Path::new(DEFAULT_CONFIG_PATH));
+
+        let mut table: toml::Table = toml::from_str(&config_str)
+            .with_context(|| format!("could not parse config file `{}`", path.display()))?;
+
+        // The original values of the overridden settings, in the order they were applied.
+        let mut raw_values = Vec::new();
+        for (var, key) in ENV_SETTINGS {
+            if let Ok(value) = env::var(var) {
+                set_key(&mut table, key, parse_value(&value))
+                    .with_context(|| format!("invalid `{var}`"))?;
+                raw_values.push((key.to_string(), value));
+            }
+        }
+        for (var, value) in env::vars() {
+            if let Some(key) = var.strip_prefix(ENV_PREFIX) {
+                let key = key.to_lowercase().replace("__", ".");
+                set_key(&mut table, &key, parse_value(&value))
+                    .with_context(|| format!("invalid `{var}`"))?;
+                raw_values.push((key, value));
+            }
+        }
+        for setting in overrides {
+            let (key, value) = setting
+                .split_once('=')
+                .with_context(|| format!("invalid setting `{setting}`, expected `KEY=VALUE`"))?;
+            let (key, value) = (key.trim(), value.trim());
+            set_key(&mut table, key, parse_value(value))?;
+            raw_values.push((key.to_string(), value.to_string()));
+        }
+
+        let mut config = deserialize_config(table, &raw_values)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// The part of the config that is visible to everyone.
+    pub fn public_config(&self) -> PublicConfig {
+        PublicConfig {
+            allow_registering: self.allow_registering,
+            disable_invite_codes: self.disable_invite_codes,
+            instance_name: self.instance_name.clone(),
+            max_file_size: self.max_file_size(),
+            default_file_expiry: Some(self.default_file_expiry).filter(|v| *v != 0),
+        }
+    }
+
+    /// Returns true if requests from an address are sent by a trusted reverse proxy.
+    pub fn is_trusted_proxy(&self, addr: IpAddr) -> bool {
+        let addr = addr.to_canonical();
+        self.trusted_proxies.iter().any(|v| v.contains(&addr))
+    }
+
+    /// The maximum size of a single file in bytes, if there is a limit.
+    pub fn max_file_size(&self) -> Option<u64> {
+        Some(self.max_file_size).filter(|v| *v != 0)
+    }
+
+    /// The maximum total size in bytes of the files a user owns outside of teams, if there is a
+    /// limit.
+    pub fn user_quota(&self) -> Option<u64> {
+        Some(self.user_quota).filter(|v| *v != 0)
+    }
+
+    /// When a file uploaded now expires if no other expiry is given.
+    pub fn default_expires_at(&self) -> Option<DateTime<Utc>> {
+        if self.default_file_expiry == 0 {
+            return None;
+        }
+        Some(Utc::now() + Duration::seconds(self.default_file_expiry as i64))
+    }
+
+    /// Checks that all settings are valid, normalizing them where needed.
+    fn validate(&mut self) -> anyhow::Result<()> {
+        self.instance_name = self.instance_name.trim().to_string();
+        if self.instance_name.is_empty() || self.instance_name.len() > MAX_INSTANCE_NAME_LEN {
+            bail!("`instance_name` must be between 1 and {MAX_INSTANCE_NAME_LEN} bytes long");
+        }
+        if self.default_file_expiry > MAX_DEFAULT_FILE_EXPIRY {
+            bail!("`default_file_expiry` can be at most {MAX_DEFAULT_FILE_EXPIRY} seconds");
+        }
+
+        if self.external_url.is_empty() {
+            bail!("`external_url` is not set, set it in the config file or using `EXTERNAL_URL`");
+        }
+        let external_url = Url::parse(&self.external_url)
+            .with_context(|| format!("`external_url` is not a valid URL: {}", self.external_url))?;
+        if !matches!(external_url.scheme(), "http" | "https") || !external_url.has_host() {
+            bail!("`external_url` must be a HTTP or HTTPS URL");
+        }
+        if external_url.query().is_some() || external_url.fragment().is_some() {
+            bail!("`external_url` can not contain a query or fragment");
+        }
+        self.external_url = self.external_url.trim_end_matches('/').to_string();
+
+        if self.database.url.is_empty() {
+            bail!("`database.url` is not set, set it in the config file or using `DATABASE_URL`");
+        }
+        if !self.database.url.starts_with("postgres://")
+            && !self.database.url.starts_with("postgresql://")
+        {
+            bail!("`database.url` must be a `postgres://` URL");
+        }
+        if self.database.pool_size == 0 {
+            bail!("`database.pool_size` must be at least 1");
+        }
+
+        if self.storage.root.as_os_str().is_empty() {
+            bail!(
+                "`storage.root` is not set, set it in the config file or using `FILE_STORE_ROOT`"
+            );
+        }
+
+        for (key, lifetime) in [
+            ("sessions.login_lifetime", self.sessions.login_lifetime),
+            (
+                "sessions.registration_lifetime",
+                self.sessions.registration_lifetime,
+            ),
+            ("sessions.ssh_lifetime", self.sessions.ssh_lifetime),
+        ] {
+            if lifetime == 0 || lifetime > MAX_SESSION_LIFETIME {
+                bail!("`{key}` must be between 1 and {MAX_SESSION_LIFETIME} seconds");
+            }
+        }
+
+        if self.limits.max_body_size == 0 {
+            bail!("`limits.max_body_size` must be at least 1");
+        }
+
+        for policy in [
+            Policy::Auth,
+            Policy::Registration,
+            Policy::Uploads,
+            Policy::Downloads,
+        ] {
+            let limits = self.rate_limits.policy(policy);
+            for (name, limit) in [("per_ip", limits.per_ip), ("per_user", limits.per_user)] {
+                if limit.is_enabled() && limit.per_minute == 0 {
+                    let key = format!("rate_limits.{}.{name}", policy.as_str());
+                    bail!("`{key}.per_minute` must be at least 1 if `{key}.burst` is set");
+                }
+            }
+        }
+
+        EnvFilter::try_new(&self.logging.level)
+            .with_context(|| format!("`logging.level` is invalid: {}", self.logging.level))?;
+
+        if self.metrics.enabled && self.metrics.bind_addr == Some(self.bind_addr) {
+            bail!("`metrics.bind_addr` must be different from `bind_addr`");
+        }
+
+        if self.tls.cert.is_some() != self.tls.key.is_some() {
+            bail!("`tls.cert` and `tls.key` must be set together");
+        }
+        if let Some(redirect_addr) = self.tls.redirect_addr {
+            if self.tls.files().is_none() {
+                bail!("`tls.redirect_addr` requires `tls.cert` and `tls.key` to be set");
+            }
+            if external_url.scheme() != "https" {
+                bail!("`tls.redirect_addr` requires `external_url` to be a HTTPS URL");
+            }
+            if redirect_addr == self.bind_addr
+                || (self.metrics.enabled && self.metrics.bind_addr == Some(redirect_addr))
+            {
+                bail!("`tls.redirect_addr` must be different from the other addresses");
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Deserializes a list of networks, where single addresses are treated as a network containing
+/// only that address.
+fn deserialize_networks<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<IpNet>, D::Error> {
+    Vec::<String>::deserialize(deserializer)?
+        .iter()
+        .map(|v| {
+            v.parse::<IpNet>()
+                .or_else(|_| v.parse::<IpAddr>().map(IpNet::from))
+                .map_err(|_| D::Error::custom(format!("`{v}` is not an address or network")))
+        })
+        .collect()
+}
+
+/// Deserializes the configuration. Overridden settings were parsed as TOML values, so a setting
+/// that expects a string, such as `instance_name=2024`, fails to deserialize. Those settings are
+/// set to their original value instead, and the configuration is deserialized again.
+fn deserialize_config(
+    mut table: toml::Table,
+    raw_values: &[(String, String)],
+) -> anyhow::Result<Config> {
+    let mut as_string = HashSet::new();
+    loop {
+        let err = match serde_path_to_error::deserialize(toml::Value::Table(table.clone())) {
+            Ok(config) => return Ok(config),
+            Err(err) => err,
         };
+        let path = err.path().to_string();
+        let raw = raw_values
+            .iter()
+            .rev()
+            .find(|(key, _)| *key == path)
+            .map(|(_, value)| value);
+        match raw {
+            Some(raw) if as_string.insert(path.clone()) => {
+                set_key(&mut table, &path, toml::Value::String(raw.clone()))?;
+            }
+            _ => bail!("invalid setting `{path}`: {}", err.inner().message()),
+        }
+    }
+}
+
+/// Parses the value of an overridden setting as a TOML value if possible, such as `20` or `true`,
+/// and as a string otherwise.
+fn parse_value(value: &str) -> toml::Value {
+    toml::from_str::<toml::Table>(&format!("value = {value}"))
+        .ok()
+        .and_then(|mut v| v.remove("value"))
+        .unwrap_or_else(|| toml::Value::String(value.to_string()))
+}
 
-        Ok(toml::from_str(&config_str)?)
+/// Sets a dotted key such as `database.pool_size` in a table, creating tables along the way.
+fn set_key(table: &mut toml::Table, key: &str, value: toml::Value) -> anyhow::Result<()> {
+    let mut segments: Vec<_> = key.split('.').collect();
+    let last = segments.pop().filter(|v| !v.is_empty());
+    let Some(last) = last else {
+        bail!("invalid setting `{key}`");
+    };
+
+    let mut table = table;
+    for segment in segments {
+        table = table
+            .entry(segment)
+            .or_insert_with(|| toml::Value::Table(Default::default()))
+            .as_table_mut()
+            .with_context(|| format!("invalid setting `{key}`, `{segment}` is not a table"))?;
     }
+    table.insert(last.to_string(), value);
+    Ok(())
 }
 
 pub(super) mod defaults {
+    use std::net::SocketAddr;
+
     pub(super) fn bool_true() -> bool {
         true
     }
+
+    pub(super) fn instance_name() -> String {
+        "Beacon".to_string()
+    }
+
+    pub(super) fn bind_addr() -> SocketAddr {
+        ([127, 0, 0, 1], 4000).into()
+    }
+
+    pub(super) fn max_slugs_per_user() -> u32 {
+        100
+    }
+
+    pub(super) fn max_file_versions() -> u32 {
+        10
+    }
+
+    pub(super) fn drain_timeout() -> u64 {
+        30
+    }
+
+    pub(super) fn min_free_space() -> u64 {
+        // 1 GiB.
+        1024 * 1024 * 1024
+    }
 }
@@ -0,0 +1,232 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::PathBuf,
+    sync::Arc,
+};
+
+use anyhow::Context;
+use arc_swap::ArcSwap;
+use serde::Serialize;
+use sqlx::PgPool;
+use tokio::{
+    signal::unix::{signal, SignalKind},
+    sync::Mutex,
+};
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use super::{Config, Settings, SettingsError};
+use crate::audit::{AuditAction, AuditEvent};
+
+/// Settings that are only used when the server starts. Changes to these, or to settings inside
+/// them, are reported but only take effect after restarting the server.
+const RESTART_SETTINGS: &[&str] = &[
+    "bind_addr",
+    "drain_timeout",
+    "database",
+    "storage",
+    "limits",
+    "logging",
+    "metrics",
+    "tls",
+];
+/// Settings whose values are never logged, as they may contain passwords.
+const SECRET_SETTINGS: &[&str] = &["database.url"];
+
+/// The configuration of the running server, which can be replaced by reloading it or by changing
+/// the settings stored in the database.
+///
+/// Handlers should load the configuration once per request, so the request sees a consistent
+/// configuration even if it is reloaded in the meantime.
+pub struct ConfigHandle {
+    current: ArcSwap<Config>,
+    /// The arguments the configuration was read with, so it can be read the same way again.
+    path: Option<PathBuf>,
+    overrides: Vec<String>,
+    /// What the current configuration is made of. Locked while it is being replaced, so only one
+    /// change happens at a time.
+    layers: Mutex<Layers>,
+}
+
+struct Layers {
+    /// The configuration as read from the file, environment and command line.
+    file: Config,
+    /// The settings stored in the database, which are applied over the file.
+    settings: Settings,
+}
+
+/// The settings that changed when reloading the configuration.
+#[derive(Serialize, Default)]
+pub struct ReloadReport {
+    /// Settings that are in effect now.
+    pub applied: Vec<String>,
+    /// Settings that only take effect after restarting the server.
+    pub requires_restart: Vec<String>,
+}
+
+impl ConfigHandle {
+    pub fn new(config: Config, path: Option<PathBuf>, overrides: Vec<String>) -> Self {
+        Self {
+            current: ArcSwap::from_pointee(config.clone()),
+            path,
+            overrides,
+            layers: Mutex::new(Layers {
+                file: config,
+                settings: Settings::default(),
+            }),
+        }
+    }
+
+    /// Returns the current configuration.
+    pub fn load(&self) -> Arc<Config> {
+        self.current.load_full()
+    }
+
+    /// Returns the settings stored in the database.
+    pub async fn settings(&self) -> Settings {
+        self.layers.lock().await.settings.clone()
+    }
+
+    /// Applies the settings stored in the database. Called when the server starts.
+    pub async fn load_settings(&self, db: &PgPool) -> anyhow::Result<()> {
+        let mut layers = self.layers.lock().await;
+        let settings = Settings::load(db).await?;
+        let config = settings.apply(layers.file.clone())?;
+        layers.settings = settings;
+        self.current.store(Arc::new(config));
+        Ok(())
+    }
+
+    /// Stores new settings in the database and applies them. The settings are not stored if the
+    /// resulting configuration is invalid.
+    pub async fn update_settings(
+        &self,
+        db: &PgPool,
+        settings: Settings,
+        updated_by: Uuid,
+    ) -> Result<(), SettingsError> {
+        let mut layers = self.layers.lock().await;
+        let config = settings.apply(layers.file.clone())?;
+        settings.save(db, updated_by).await?;
+        layers.settings = settings;
+        self.current.store(Arc::new(config));
+        Ok(())
+    }
+
+    /// Reads the configuration again and applies the settings that can be changed while the
+    /// server is running. The current configuration is kept if the new one is invalid.
+    pub async fn reload(&self) -> anyhow::Result<ReloadReport> {
+        let mut layers = self.layers.lock().await;
+        let file = Config::read(self.path.clone(), &self.overrides).await?;
+        let new = layers.settings.apply(file.clone())?;
+        let current = self.load();
+
+        let current_table =
+            toml::Table::try_from(&*current).context("could not serialize current config")?;
+        let new_table = toml::Table::try_from(&new).context("could not serialize new config")?;
+
+        let mut current_settings = BTreeMap::new();
+        flatten("", &current_table, &mut current_settings);
+        let mut new_settings = BTreeMap::new();
+        flatten("", &new_table, &mut new_settings);
+
+        let mut report = ReloadReport::default();
+        let keys: BTreeSet<_> = current_settings.keys().chain(new_settings.keys()).collect();
+        for key in keys {
+            let (old, new) = (current_settings.get(key), new_settings.get(key));
+            if old == new {
+                continue;
+            }
+
+            let restart = requires_restart(key);
+            if SECRET_SETTINGS.contains(&key.as_str()) {
+                info!(restart, "Setting `{key}` changed.");
+            } else {
+                let old = old.map_or("unset".to_string(), ToString::to_string);
+                let new = new.map_or("unset".to_string(), ToString::to_string);
+                info!(restart, "Setting `{key}` changed from {old} to {new}.");
+            }
+
+            if restart {
+                report.requires_restart.push(key.clone());
+            } else {
+                report.applied.push(key.clone());
+            }
+        }
+
+        // Settings that require a restart keep their current value, so the configuration reflects
+        // what the server is actually running with.
+        let mut file_table = toml::Table::try_from(&file).context("could not serialize config")?;
+        for key in RESTART_SETTINGS {
+            match current_table.get(*key) {
+                Some(value) => file_table.insert(key.to_string(), value.clone()),
+                None => file_table.remove(*key),
+            };
+        }
+        let file: Config = file_table
+            .try_into()
+            .context("could not apply new config")?;
+        let config = layers.settings.apply(file.clone())?;
+        layers.file = file;
+        self.current.store(Arc::new(config));
+
+        if !report.requires_restart.is_empty() {
+            warn!(
+                "Restart the server to apply changes to {}.",
+                report.requires_restart.join(", ")
+            );
+        }
+        info!(
+            applied = report.applied.len(),
+            requires_restart = report.requires_restart.len(),
+            "Reloaded configuration."
+        );
+        Ok(report)
+    }
+}
+
+/// Returns true if changing a setting only takes effect after a restart.
+fn requires_restart(key: &str) -> bool {
+    RESTART_SETTINGS.iter().any(|setting| {
+        key.strip_prefix(setting)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('.'))
+    })
+}
+
+/// Collects the settings of a table by their dotted keys, such as `database.pool_size`.
+fn flatten(prefix: &str, table: &toml::Table, settings: &mut BTreeMap<String, toml::Value>) {
+    for (key, value) in table {
+        let key = format!("{prefix}{key}");
+        match value {
+            toml::Value::Table(table) => flatten(&format!("{key}."), table, settings),
+            value => {
+                settings.insert(key, value.clone());
+            }
+        }
+    }
+}
+
+/// Reloads the configuration every time the process receives SIGHUP.
+pub async fn reload_on_sighup(config: Arc<ConfigHandle>, db: PgPool) {
+    let mut sighup = match signal(SignalKind::hangup()) {
+        Ok(sighup) => sighup,
+        Err(err) => {
+            error!("Could not listen for SIGHUP: {err}");
+            return;
+        }
+    };
+
+    while sighup.recv().await.is_some() {
+        info!("Received SIGHUP, reloading configuration.");
+        match config.reload().await {
+            Ok(report) => {
+                AuditEvent::new(AuditAction::ConfigReloaded)
+                    .by_operator()
+                    .details(serde_json::json!(report))
+                    .record(&db)
+                    .await;
+            }
+            Err(err) => error!("Could not reload configuration, keeping the current one: {err:#}"),
+        }
+    }
+}
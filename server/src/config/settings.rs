@@ -0,0 +1,115 @@
+use anyhow::anyhow;
+use http::StatusCode;
+use serde::{Deserialize, Serialize};
+use sqlx::{PgExecutor, PgPool};
+use thiserror::Error;
+use uuid::Uuid;
+
+use super::Config;
+
+/// The reasons changing the settings may fail.
+#[derive(Debug, Error)]
+pub enum SettingsError {
+    #[error("{0}")]
+    Invalid(String),
+    #[error(transparent)]
+    Database(#[from] sqlx::Error),
+}
+
+impl SettingsError {
+    /// The status code to respond with when this error is caused by the user.
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            SettingsError::Database(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            SettingsError::Invalid(_) => StatusCode::BAD_REQUEST,
+        }
+    }
+}
+
+/// Settings administrators can change while the server is running. They are stored in the
+/// database and take precedence over the setting of the same name in the configuration file.
+/// Settings that are unset use the value of the configuration file.
+#[derive(Serialize, Deserialize, Default, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct Settings {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub allow_registering: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub disable_invite_codes: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub instance_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_file_expiry: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_file_size: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user_quota: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_slugs_per_user: Option<u32>,
+}
+
+impl Settings {
+    /// Reads the stored settings from the database.
+    pub async fn load(db: &PgPool) -> anyhow::Result<Self> {
+        let rows = sqlx::query!("select key, value from settings")
+            .fetch_all(db)
+            .await?;
+        let settings = rows.into_iter().map(|row| (row.key, row.value)).collect();
+        serde_path_to_error::deserialize(serde_json::Value::Object(settings))
+            .map_err(|err| anyhow!("invalid stored setting `{}`: {}", err.path(), err.inner()))
+    }
+
+    /// Replaces the stored settings. Settings that are unset are removed from the database.
+    pub async fn save(&self, db: impl PgExecutor<'_>, updated_by: Uuid) -> sqlx::Result<()> {
+        let settings = serde_json::to_value(self).expect("settings can be serialized");
+        sqlx::query!(
+            r#"
+                with new_settings as (
+                    select * from jsonb_each($1)
+                ), removed as (
+                    delete from settings where key not in (select key from new_settings)
+                )
+                insert into settings (key, value, updated_by)
+                    select key, value, $2 from new_settings
+                    on conflict (key) do update
+                        set value=excluded.value, updated_at=now(), updated_by=excluded.updated_by
+                        where settings.value <> excluded.value
+            "#,
+            settings,
+            updated_by,
+        )
+        .execute(db)
+        .await?;
+        Ok(())
+    }
+
+    /// Applies the settings over a configuration, checking that the result is valid.
+    pub fn apply(&self, mut config: Config) -> Result<Config, SettingsError> {
+        if let Some(value) = self.allow_registering {
+            config.allow_registering = value;
+        }
+        if let Some(value) = self.disable_invite_codes {
+            config.disable_invite_codes = value;
+        }
+        if let Some(value) = &self.instance_name {
+            config.instance_name.clone_from(value);
+        }
+        if let Some(value) = self.default_file_expiry {
+            config.default_file_expiry = value;
+        }
+        if let Some(value) = self.max_file_size {
+            config.max_file_size = value;
+        }
+        if let Some(value) = self.user_quota {
+            config.user_quota = value;
+        }
+        if let Some(value) = self.max_slugs_per_user {
+            config.max_slugs_per_user = value;
+        }
+
+        config
+            .validate()
+            .map_err(|err| SettingsError::Invalid(format!("{err:#}")))?;
+        Ok(config)
+    }
+}
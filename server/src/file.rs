@@ -1,20 +1,41 @@
 use std::{
+    collections::HashMap,
     fmt::{self, Display},
     io::ErrorKind,
     num::ParseIntError,
-    path::PathBuf,
+    path::{Path, PathBuf},
     pin::pin,
     str::FromStr,
+    sync::Arc,
+    time::{Duration, SystemTime},
 };
 
 use anyhow::Context;
-use chrono::{serde::ts_milliseconds, DateTime, Utc};
+use chrono::{
+    serde::{ts_milliseconds, ts_milliseconds_option},
+    DateTime, Utc,
+};
+use http::HeaderValue;
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
 use scopeguard::{guard, ScopeGuard};
 use serde::{de::Visitor, Deserialize, Serialize};
-use sqlx::PgPool;
-use tokio::{fs, io::AsyncRead};
+use sqlx::{PgConnection, PgPool};
+use thiserror::Error;
+use tokio::{fs, io::AsyncRead, time::sleep};
+use tracing::{debug, error, info, warn};
+use unicode_normalization::UnicodeNormalization;
 use uuid::Uuid;
 
+use crate::{
+    metrics::{Direction, METRICS},
+    preview::{self, PreviewKind},
+    team, user,
+};
+
+mod query;
+
+pub use query::{FileFilter, Page, QueryError};
+
 /// Information for a file.
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct FileInfo {
@@ -23,6 +44,156 @@ pub struct FileInfo {
     #[serde(with = "ts_milliseconds")]
     pub upload_date: DateTime<Utc>,
     pub file_size: u64,
+    /// The time after which the file is no longer available, if any.
+    #[serde(with = "ts_milliseconds_option")]
+    pub expires_at: Option<DateTime<Utc>>,
+    /// The version of the content, incremented every time the content is replaced.
+    pub version: i32,
+    /// The MIME type of the file, guessed from its name.
+    pub content_type: String,
+    /// The folder the file is in, or `None` if it is at the top level.
+    pub folder_id: Option<Uuid>,
+    /// A free-text description of the file.
+    pub description: Option<String>,
+    /// The team that owns the file, if any.
+    pub team_id: Option<Uuid>,
+    /// Set when a consistency check found the content of the file to be missing or damaged.
+    #[serde(with = "ts_milliseconds_option")]
+    pub broken_at: Option<DateTime<Utc>>,
+}
+
+/// Information about a single version of a file's content.
+#[derive(Debug, Clone, Serialize)]
+pub struct FileVersion {
+    pub version: i32,
+    pub file_size: u64,
+    #[serde(with = "ts_milliseconds")]
+    pub upload_date: DateTime<Utc>,
+    /// True if this is the version that is served by default.
+    pub current: bool,
+}
+
+impl FileInfo {
+    /// The link at which the file can be viewed, given the external URL of the instance.
+    pub fn share_link(&self, external_url: &str) -> String {
+        format!(
+            "{external_url}/files/{}/{}",
+            self.file_id,
+            encode_path_segment(&self.file_name),
+        )
+    }
+}
+
+/// The maximum length of a file name in bytes.
+pub const MAX_FILE_NAME_LEN: usize = 255;
+
+/// Characters that have to be percent-encoded in the `filename*` parameter of a
+/// `Content-Disposition` header. Everything except `attr-char` from RFC 5987 is encoded.
+const ATTR_CHAR_ENCODE_SET: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'!')
+    .remove(b'#')
+    .remove(b'$')
+    .remove(b'&')
+    .remove(b'+')
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'^')
+    .remove(b'_')
+    .remove(b'`')
+    .remove(b'|')
+    .remove(b'~');
+
+/// Characters that have to be percent-encoded when a file name is used as a path segment in a URL.
+const PATH_SEGMENT_ENCODE_SET: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
+/// Returned when the new content of a file is larger than the given amount of bytes.
+#[derive(Debug, Error)]
+#[error("content is larger than {0} bytes")]
+pub struct ContentTooLarge(pub u64);
+
+/// Returned when the new content of a file would exceed the quota of its owner.
+#[derive(Debug, Error)]
+#[error("quota exceeded")]
+pub struct QuotaExceeded;
+
+/// The reasons a file name may be rejected.
+#[derive(Debug, Error)]
+pub enum FileNameError {
+    #[error("file name must not be empty")]
+    Empty,
+    #[error("file name must be at most {MAX_FILE_NAME_LEN} bytes long")]
+    TooLong,
+    #[error("file name must not be `.` or `..`")]
+    Reserved,
+    #[error("file name must not contain path separators")]
+    PathSeparator,
+    #[error("file name must not contain control characters")]
+    ControlCharacter,
+}
+
+/// Validates a user supplied file name and returns its normalized form.
+///
+/// Surrounding whitespace is removed and the name is converted to Unicode normalization form C,
+/// so visually identical names are also stored identically.
+pub fn normalize_file_name(file_name: &str) -> Result<String, FileNameError> {
+    let file_name: String = file_name.trim().nfc().collect();
+
+    if file_name.is_empty() {
+        return Err(FileNameError::Empty);
+    }
+    if file_name.len() > MAX_FILE_NAME_LEN {
+        return Err(FileNameError::TooLong);
+    }
+    if file_name == "." || file_name == ".." {
+        return Err(FileNameError::Reserved);
+    }
+    if file_name.contains(['/', '\\']) {
+        return Err(FileNameError::PathSeparator);
+    }
+    if file_name.chars().any(char::is_control) {
+        return Err(FileNameError::ControlCharacter);
+    }
+
+    Ok(file_name)
+}
+
+/// Encodes a file name so it can be used as a single path segment in a URL.
+pub fn encode_path_segment(file_name: &str) -> String {
+    utf8_percent_encode(file_name, PATH_SEGMENT_ENCODE_SET).to_string()
+}
+
+/// Builds a `Content-Disposition` header value for a file name as described in RFC 6266.
+///
+/// The `filename` parameter contains an ASCII-only fallback for older clients, while the
+/// `filename*` parameter contains the full UTF-8 encoded name.
+pub fn content_disposition(disposition: &str, file_name: &str) -> HeaderValue {
+    let fallback: String = file_name
+        .chars()
+        .map(|c| match c {
+            '"' | '\\' => '_',
+            c if c.is_ascii() && !c.is_ascii_control() => c,
+            _ => '_',
+        })
+        .collect();
+    let encoded = utf8_percent_encode(file_name, ATTR_CHAR_ENCODE_SET);
+
+    HeaderValue::from_str(&format!(
+        "{disposition}; filename=\"{fallback}\"; filename*=UTF-8''{encoded}"
+    ))
+    // Both parameters only consist of visible ASCII characters at this point.
+    .expect("content disposition is always a valid header value")
+}
+
+/// Guesses the MIME type of a file from its name.
+pub fn guess_content_type(file_name: &str) -> String {
+    mime_guess::from_path(file_name)
+        .first_or_octet_stream()
+        .essence_str()
+        .to_string()
 }
 
 /// A unique identifier for a file.
@@ -108,10 +279,15 @@ impl FileDb {
         &self.db
     }
 
+    /// Stores a new file, optionally inside a folder and owned by a team. If `expires_at` is set,
+    /// the file will no longer be available after that time.
     pub async fn create(
         &self,
         owner: Uuid,
         file_name: String,
+        folder_id: Option<Uuid>,
+        team_id: Option<Uuid>,
+        expires_at: Option<DateTime<Utc>>,
         content: impl AsyncRead,
     ) -> anyhow::Result<FileInfo> {
         let mut tx = self.db.begin().await?;
@@ -140,16 +316,24 @@ impl FileDb {
 
         let file_size = self.store.put(file_id, content).await?;
         let file_size_db: i64 = file_size.try_into().context("invalid file size")?;
+        let content_type = guess_content_type(&file_name);
 
         let row = sqlx::query!(
             r#"
-            insert into files(file_id, file_name, file_size, upload_date, uploader_id)
+            insert into files(
+                file_id, file_name, file_size, upload_date, uploader_id, expires_at, content_type,
+                folder_id, team_id
+            )
                 values(
                     $1,
                     $2,
                     $3,
                     now(),
-                    $4
+                    $4,
+                    $5,
+                    $6,
+                    $7,
+                    $8
                 )
                 returning upload_date
             "#,
@@ -157,6 +341,10 @@ impl FileDb {
             file_name,
             file_size_db,
             owner,
+            expires_at,
+            content_type,
+            folder_id,
+            team_id,
         )
         .fetch_one(&mut *tx)
         .await?;
@@ -171,12 +359,24 @@ impl FileDb {
             file_name,
             upload_date: row.upload_date,
             file_size,
+            expires_at,
+            version: 1,
+            content_type,
+            folder_id,
+            description: None,
+            team_id,
+            broken_at: None,
         })
     }
 
     pub async fn file_info(&self, file_id: FileId) -> anyhow::Result<Option<FileInfo>> {
         Ok(sqlx::query!(
-            "select file_name, upload_date, file_size from files where file_id=$1",
+            r#"
+                select file_name, upload_date, file_size, expires_at, version, content_type,
+                    folder_id, description, team_id, broken_at
+                    from files
+                    where file_id=$1 and (expires_at is null or expires_at > now())
+            "#,
             file_id as FileId
         )
         .fetch_optional(&self.db)
@@ -186,16 +386,335 @@ impl FileDb {
             file_name: row.file_name,
             upload_date: row.upload_date,
             file_size: row.file_size.try_into().unwrap_or_default(),
+            expires_at: row.expires_at,
+            version: row.version,
+            content_type: row.content_type,
+            folder_id: row.folder_id,
+            description: row.description,
+            team_id: row.team_id,
+            broken_at: row.broken_at,
+        }))
+    }
+
+    /// Changes the name and/or description of a file. An empty description removes it. The
+    /// previous name is remembered, so links using it keep working. Returns `None` if the file does
+    /// not exist.
+    pub async fn update_metadata(
+        &self,
+        file_id: FileId,
+        file_name: Option<String>,
+        description: Option<String>,
+    ) -> anyhow::Result<Option<FileInfo>> {
+        let mut tx = self.db.begin().await?;
+
+        let Some(current) = sqlx::query!(
+            r#"
+                select file_name
+                    from files
+                    where file_id=$1 and (expires_at is null or expires_at > now())
+                    for update
+            "#,
+            file_id as FileId,
+        )
+        .fetch_optional(&mut *tx)
+        .await?
+        else {
+            tx.commit().await?;
+            return Ok(None);
+        };
+
+        let renamed = file_name
+            .as_ref()
+            .is_some_and(|name| *name != current.file_name);
+        if renamed {
+            sqlx::query!(
+                r#"
+                    insert into file_name_history (file_id, file_name)
+                        values ($1, $2)
+                        on conflict (file_id, file_name) do update set renamed_at=now()
+                "#,
+                file_id as FileId,
+                current.file_name,
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        let file_name = file_name.unwrap_or(current.file_name);
+        sqlx::query!(
+            r#"
+                update files
+                    set file_name=$2,
+                        content_type=$3,
+                        description=case when $4::text is null then description
+                            else nullif($4, '') end
+                    where file_id=$1
+            "#,
+            file_id as FileId,
+            file_name,
+            guess_content_type(&file_name),
+            description,
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        // Previews depend on the file extension, which may have changed.
+        if renamed {
+            if let Err(err) = self.store.remove_previews(file_id).await {
+                error!(
+                    ?file_id,
+                    "Could not remove previews of renamed file: {err:?}"
+                );
+            }
+        }
+
+        self.file_info(file_id).await
+    }
+
+    /// Returns the current name of a file if `file_name` is one of its previous names.
+    pub async fn renamed_to(
+        &self,
+        file_id: FileId,
+        file_name: &str,
+    ) -> anyhow::Result<Option<String>> {
+        Ok(sqlx::query!(
+            r#"
+                select files.file_name
+                    from file_name_history
+                        join files on files.file_id=file_name_history.file_id
+                    where file_name_history.file_id=$1 and file_name_history.file_name=$2
+                        and files.file_name<>$2
+                        and (files.expires_at is null or files.expires_at > now())
+            "#,
+            file_id as FileId,
+            file_name,
+        )
+        .fetch_optional(&self.db)
+        .await?
+        .map(|row| row.file_name))
+    }
+
+    /// Returns how many bytes can still be added to a file before the quota of its owner is
+    /// reached. Files owned by a team count towards the quota of the team, other files towards the
+    /// `user_quota` of their uploader. The result
+    /// is negative if the owner is over the quota, and `None` if there is no quota or the file does
+    /// not exist.
+    pub async fn remaining_quota(
+        &self,
+        file_id: FileId,
+        user_quota: Option<u64>,
+    ) -> sqlx::Result<Option<i64>> {
+        remaining_quota(&mut *self.db.acquire().await?, file_id, user_quota).await
+    }
+
+    /// Replaces the content of a file. The previous content is kept as an older version, of which
+    /// at most `max_versions` are kept. Returns `None` if the file does not exist, and fails with
+    /// [ContentTooLarge] if the content is larger than `max_size` bytes, or with [QuotaExceeded]
+    /// if the file would exceed the quota of its owner, see [FileDb::remaining_quota].
+    pub async fn replace(
+        &self,
+        file_id: FileId,
+        content: impl AsyncRead,
+        max_versions: u32,
+        max_size: Option<u64>,
+        user_quota: Option<u64>,
+    ) -> anyhow::Result<Option<FileInfo>> {
+        // The new content is received before starting the transaction, as the file's row is locked
+        // for the duration of the transaction.
+        let (staged_path, file_size) = self.store.stage(file_id, content).await?;
+        let file_size_db: i64 = file_size.try_into().context("invalid file size")?;
+
+        // Make sure the staged file doesn't stick around if anything fails.
+        let defer = guard(staged_path.clone(), |path| {
+            tokio::spawn(fs::remove_file(path));
+        });
+        if let Some(max) = max_size.filter(|v| file_size > *v) {
+            return Err(ContentTooLarge(max).into());
+        }
+
+        let mut tx = self.db.begin().await?;
+
+        let Some(current) = sqlx::query!(
+            r#"
+                select file_name, file_size, upload_date, expires_at, version, content_type,
+                    folder_id, description, team_id
+                    from files
+                    where file_id=$1 and (expires_at is null or expires_at > now())
+                    for update
+            "#,
+            file_id as FileId,
+        )
+        .fetch_optional(&mut *tx)
+        .await?
+        else {
+            tx.commit().await?;
+            return Ok(None);
+        };
+
+        sqlx::query!(
+            r#"
+                insert into file_versions (file_id, version, file_size, upload_date)
+                    values ($1, $2, $3, $4)
+            "#,
+            file_id as FileId,
+            current.version,
+            current.file_size,
+            current.upload_date,
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        let row = sqlx::query!(
+            r#"
+                update files
+                    set version=version + 1, file_size=$2, upload_date=now(), broken_at=null
+                    where file_id=$1
+                    returning version, upload_date
+            "#,
+            file_id as FileId,
+            file_size_db,
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        let pruned = sqlx::query!(
+            "delete from file_versions where file_id=$1 and version<=$2 returning version",
+            file_id as FileId,
+            row.version - 1 - max_versions as i32,
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+
+        // The size of the content is only known once it has been received, so the quota is
+        // checked with the new content and versions in place. Dropping the transaction undoes them.
+        if remaining_quota(&mut tx, file_id, user_quota)
+            .await?
+            .is_some_and(|v| v < 0)
+        {
+            return Err(QuotaExceeded.into());
+        }
+
+        self.store
+            .swap_in(file_id, current.version, &staged_path)
+            .await?;
+        ScopeGuard::into_inner(defer);
+
+        if let Err(err) = tx.commit().await {
+            // Try to put the previous content back, so it matches the database again.
+            if let Err(err) = self.store.swap_out(file_id, current.version).await {
+                error!(?file_id, "Could not restore file content: {err:?}");
+            }
+            return Err(err.into());
+        }
+
+        // The old previews no longer match the content. The new content is already in place, so
+        // failing to clean up doesn't fail the replacement.
+        if let Err(err) = self.store.remove_previews(file_id).await {
+            error!(
+                ?file_id,
+                "Could not remove previews of replaced file: {err:?}"
+            );
+        }
+        for version in pruned {
+            if let Err(err) = self.store.remove_version(file_id, version.version).await {
+                error!(
+                    ?file_id,
+                    version.version, "Could not remove pruned version: {err:?}"
+                );
+            }
+        }
+
+        Ok(Some(FileInfo {
+            file_id,
+            file_name: current.file_name,
+            upload_date: row.upload_date,
+            file_size,
+            expires_at: current.expires_at,
+            version: row.version,
+            content_type: current.content_type,
+            folder_id: current.folder_id,
+            description: current.description,
+            team_id: current.team_id,
+            broken_at: None,
         }))
     }
 
+    /// Lists all versions of a file, including the current version. Returns `None` if the file
+    /// does not exist.
+    pub async fn versions(
+        &self,
+        file_id: FileId,
+        file_name: &str,
+    ) -> anyhow::Result<Option<Vec<FileVersion>>> {
+        let Some(current) = self.file_info(file_id).await? else {
+            return Ok(None);
+        };
+        if current.file_name != file_name {
+            return Ok(None);
+        }
+
+        let mut versions = sqlx::query!(
+            r#"
+                select version, file_size, upload_date
+                    from file_versions
+                    where file_id=$1
+                    order by version
+            "#,
+            file_id as FileId,
+        )
+        .fetch_all(&self.db)
+        .await?
+        .into_iter()
+        .map(|row| FileVersion {
+            version: row.version,
+            file_size: row.file_size.try_into().unwrap_or_default(),
+            upload_date: row.upload_date,
+            current: false,
+        })
+        .collect::<Vec<_>>();
+
+        versions.push(FileVersion {
+            version: current.version,
+            file_size: current.file_size,
+            upload_date: current.upload_date,
+            current: true,
+        });
+        Ok(Some(versions))
+    }
+
+    /// Permanently removes an older version of a file. The current version can not be removed
+    /// this way. Returns `false` if the version did not exist.
+    pub async fn remove_version(&self, file_id: FileId, version: i32) -> anyhow::Result<bool> {
+        let result = sqlx::query!(
+            "delete from file_versions where file_id=$1 and version=$2",
+            file_id as FileId,
+            version,
+        )
+        .execute(&self.db)
+        .await?;
+        if result.rows_affected() == 0 {
+            return Ok(false);
+        }
+
+        self.store.remove_version(file_id, version).await?;
+        Ok(true)
+    }
+
     pub async fn content(
         &self,
         file_id: FileId,
         file_name: &str,
-    ) -> anyhow::Result<Option<impl AsyncRead>> {
+    ) -> anyhow::Result<Option<fs::File>> {
         let exists = sqlx::query!(
-            "select exists(select * from files where file_id=$1 and file_name=$2)",
+            r#"
+                select exists(
+                    select * from files
+                        where file_id=$1 and file_name=$2
+                            and (expires_at is null or expires_at > now())
+                )
+            "#,
             file_id as FileId,
             file_name
         )
@@ -210,6 +729,245 @@ impl FileDb {
 
         self.store.get(file_id).await
     }
+
+    /// Get the content of a specific version of a file.
+    pub async fn content_version(
+        &self,
+        file_id: FileId,
+        file_name: &str,
+        version: i32,
+    ) -> anyhow::Result<Option<fs::File>> {
+        let Some(row) = sqlx::query!(
+            r#"
+                select
+                    version as current_version,
+                    exists(select * from file_versions where file_id=$1 and version=$3)
+                        as "has_version!"
+                    from files
+                    where file_id=$1 and file_name=$2
+                        and (expires_at is null or expires_at > now())
+            "#,
+            file_id as FileId,
+            file_name,
+            version,
+        )
+        .fetch_optional(&self.db)
+        .await?
+        else {
+            return Ok(None);
+        };
+
+        if row.current_version == version {
+            self.store.get(file_id).await
+        } else if row.has_version {
+            self.store.get_version(file_id, version).await
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Permanently removes a file and its content. Returns `false` if the file did not exist.
+    pub async fn remove(&self, file_id: FileId) -> anyhow::Result<bool> {
+        let result = sqlx::query!("delete from files where file_id=$1", file_id as FileId)
+            .execute(&self.db)
+            .await?;
+        if result.rows_affected() == 0 {
+            return Ok(false);
+        }
+
+        self.store.remove(file_id).await?;
+        Ok(true)
+    }
+
+    /// Starts a task that periodically removes expired files. The task stops when the [FileDb] is
+    /// dropped.
+    pub fn start_expiry_task(self: &Arc<Self>) {
+        let weak_db = Arc::downgrade(self);
+        tokio::spawn(async move {
+            loop {
+                sleep(Duration::from_secs(60)).await;
+                let Some(file_db) = weak_db.upgrade() else {
+                    return;
+                };
+
+                match file_db.remove_expired().await {
+                    Ok(0) => {}
+                    Ok(amount) => info!(amount, "Removed expired files."),
+                    Err(err) => error!("Could not remove expired files: {err:?}"),
+                }
+            }
+        });
+    }
+
+    /// Removes all files that have expired, returning the amount of files removed.
+    pub async fn remove_expired(&self) -> anyhow::Result<u64> {
+        let mut tx = self.db.begin().await?;
+
+        let files = sqlx::query!(
+            r#"
+                delete from files
+                    where expires_at <= now()
+                    returning file_id as "file_id: FileId"
+            "#,
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        // The rows are gone at this point, so a failure to remove a blob only leaves an orphaned
+        // file behind instead of a file that can't be downloaded.
+        for file in &files {
+            if let Err(err) = self.store.remove(file.file_id).await {
+                warn!(?file.file_id, "Could not remove expired file: {err:?}");
+            }
+        }
+
+        Ok(files.len() as u64)
+    }
+
+    /// Removes content from the file store that doesn't belong to any file, such as content left
+    /// behind by uploads that were interrupted. Returns the amount of entries removed.
+    ///
+    /// Content modified less than `min_age` ago is kept, as it may belong to an upload that is
+    /// still in progress. It can only be zero while nothing is being uploaded.
+    pub async fn remove_orphans(&self, min_age: Duration) -> anyhow::Result<u64> {
+        let known = KnownContent::load(&self.db).await?;
+
+        let mut removed = 0;
+        for entry in self.store.entries().await? {
+            if known.is_orphan(entry.kind) && entry.is_older_than(min_age) {
+                debug!(path = ?entry.path, "Removing orphaned content");
+                self.store.remove_entry(&entry.path).await?;
+                removed += 1;
+            }
+        }
+        Ok(removed)
+    }
+
+    /// Get a preview of a file, generating and caching it if this has not been done before.
+    ///
+    /// Returns `None` if the file does not exist or if no preview can be generated for it.
+    pub async fn preview(
+        &self,
+        file_id: FileId,
+        file_name: &str,
+    ) -> anyhow::Result<Option<(PreviewKind, Vec<u8>)>> {
+        let Some(kind) = PreviewKind::for_file_name(file_name) else {
+            return Ok(None);
+        };
+        let Some(content) = self.content(file_id, file_name).await? else {
+            return Ok(None);
+        };
+
+        if let Some(preview) = self.store.get_preview(file_id, kind).await? {
+            return Ok(Some((kind, preview)));
+        }
+
+        let Some(preview) = preview::generate(kind, file_name, content).await? else {
+            return Ok(None);
+        };
+        self.store.put_preview(file_id, kind, &preview).await?;
+
+        Ok(Some((kind, preview)))
+    }
+}
+
+/// See [FileDb::remaining_quota].
+async fn remaining_quota(
+    conn: &mut PgConnection,
+    file_id: FileId,
+    user_quota: Option<u64>,
+) -> sqlx::Result<Option<i64>> {
+    let Some(file) = sqlx::query!(
+        "select uploader_id, team_id from files where file_id=$1",
+        file_id as FileId,
+    )
+    .fetch_optional(&mut *conn)
+    .await?
+    else {
+        return Ok(None);
+    };
+
+    match file.team_id {
+        Some(team_id) => team::remaining_quota(conn, team_id).await,
+        None => user::remaining_quota(conn, file.uploader_id, user_quota).await,
+    }
+}
+
+/// The directory inside the file store that content is moved to by [FileStore::quarantine_entry].
+pub const QUARANTINE_DIR: &str = ".quarantine";
+
+/// What an entry in the file store contains.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EntryKind {
+    /// The current content of a file.
+    Content(FileId),
+    /// An older version of the content of a file.
+    Version(FileId, i32),
+    /// A generated preview of a file.
+    Preview(FileId),
+    /// A file that is only used while content is being written.
+    Temporary,
+}
+
+/// An entry in the file store, see [FileStore::entries].
+#[derive(Debug)]
+pub struct StoreEntry {
+    /// The path of the entry, relative to the root of the file store.
+    pub path: PathBuf,
+    pub kind: EntryKind,
+    pub size: u64,
+    pub modified: SystemTime,
+}
+
+impl StoreEntry {
+    /// Returns true if the entry was last modified at least `age` ago. Entries modified in the
+    /// future are never considered old.
+    pub fn is_older_than(&self, age: Duration) -> bool {
+        SystemTime::now()
+            .duration_since(self.modified)
+            .is_ok_and(|v| v >= age)
+    }
+}
+
+/// The content the database expects to be in the file store, along with its size.
+#[derive(Debug, Default)]
+pub struct KnownContent {
+    /// The files along with the size of their current content.
+    pub files: HashMap<FileId, u64>,
+    /// The older versions of files along with their size.
+    pub versions: HashMap<(FileId, i32), u64>,
+}
+
+impl KnownContent {
+    pub async fn load(db: &PgPool) -> sqlx::Result<Self> {
+        let files = sqlx::query!(r#"select file_id as "file_id: FileId", file_size from files"#)
+            .fetch_all(db)
+            .await?
+            .into_iter()
+            .map(|row| (row.file_id, row.file_size as u64))
+            .collect();
+        let versions = sqlx::query!(
+            r#"select file_id as "file_id: FileId", version, file_size from file_versions"#
+        )
+        .fetch_all(db)
+        .await?
+        .into_iter()
+        .map(|row| ((row.file_id, row.version), row.file_size as u64))
+        .collect();
+
+        Ok(Self { files, versions })
+    }
+
+    /// Returns true if an entry of the given kind doesn't belong to any file.
+    pub fn is_orphan(&self, kind: EntryKind) -> bool {
+        match kind {
+            EntryKind::Content(id) | EntryKind::Preview(id) => !self.files.contains_key(&id),
+            EntryKind::Version(id, version) => !self.versions.contains_key(&(id, version)),
+            EntryKind::Temporary => true,
+        }
+    }
 }
 
 /// Responsible for keeping track of file contents.
@@ -227,50 +985,321 @@ impl FileStore {
         Ok(Self { root })
     }
 
+    /// Checks that files can be written to the file store by writing and removing a small file.
+    pub async fn check_writable(&self) -> anyhow::Result<()> {
+        let path = self
+            .root
+            .join(format!(".write-check-{:x}", rand::random::<u64>()));
+        fs::write(&path, b"beacon")
+            .await
+            .context("could not write file")?;
+        fs::remove_file(&path)
+            .await
+            .context("could not remove file")?;
+        Ok(())
+    }
+
+    /// Get the amount of bytes that are still available on the file system of the file store.
+    pub async fn available_space(&self) -> anyhow::Result<u64> {
+        let root = self.root.clone();
+        tokio::task::spawn_blocking(move || fs4::available_space(root))
+            .await
+            .context("could not join file system thread")?
+            .context("could not get available space")
+    }
+
+    /// Lists all content in the file store, including older versions, previews and temporary
+    /// files. Entries that were not created by the file store are skipped.
+    pub async fn entries(&self) -> anyhow::Result<Vec<StoreEntry>> {
+        let mut result = Vec::new();
+        let mut entries = fs::read_dir(&self.root)
+            .await
+            .context("could not read file store")?;
+        while let Some(entry) = entries.next_entry().await? {
+            let name = entry.file_name();
+            let Some(name) = name.to_str() else {
+                continue;
+            };
+            let metadata = entry.metadata().await?;
+
+            // Entries start with the ID of the file they belong to, such as `<id>.versions`.
+            let (id, rest) = name.split_once('.').unwrap_or((name, ""));
+            let kind = match (id.parse::<FileId>(), rest) {
+                (Ok(id), "versions") if metadata.is_dir() => {
+                    self.version_entries(id, name, &mut result).await?;
+                    continue;
+                }
+                (Ok(id), "") => EntryKind::Content(id),
+                // Previews that were not moved into place.
+                (Ok(_), rest) if rest.starts_with("preview.") && rest.ends_with(".tmp") => {
+                    EntryKind::Temporary
+                }
+                (Ok(id), rest) if rest.starts_with("preview.") => EntryKind::Preview(id),
+                (Err(_), _) if name.starts_with(".write-check-") => EntryKind::Temporary,
+                _ => continue,
+            };
+
+            result.push(StoreEntry {
+                path: PathBuf::from(name),
+                kind,
+                size: metadata.len(),
+                modified: metadata.modified()?,
+            });
+        }
+
+        Ok(result)
+    }
+
+    /// Adds the entries in the versions directory of a file to `result`.
+    async fn version_entries(
+        &self,
+        id: FileId,
+        dir_name: &str,
+        result: &mut Vec<StoreEntry>,
+    ) -> anyhow::Result<()> {
+        let mut entries = fs::read_dir(self.root.join(dir_name))
+            .await
+            .context("could not read versions directory")?;
+        while let Some(entry) = entries.next_entry().await? {
+            let name = entry.file_name();
+            let Some(name) = name.to_str() else {
+                continue;
+            };
+
+            let kind = if let Ok(version) = name.parse() {
+                EntryKind::Version(id, version)
+            } else if name.starts_with("staged-") {
+                // Content staged using [FileStore::stage] that was never made current.
+                EntryKind::Temporary
+            } else {
+                continue;
+            };
+
+            let metadata = entry.metadata().await?;
+            result.push(StoreEntry {
+                path: Path::new(dir_name).join(name),
+                kind,
+                size: metadata.len(),
+                modified: metadata.modified()?,
+            });
+        }
+        Ok(())
+    }
+
+    /// Permanently removes an entry returned by [FileStore::entries]. The versions directory it is
+    /// in is removed as well once it is empty.
+    pub async fn remove_entry(&self, path: &Path) -> anyhow::Result<()> {
+        fs::remove_file(self.root.join(path))
+            .await
+            .with_context(|| format!("could not remove `{}`", path.display()))?;
+        self.remove_empty_parent(path).await;
+        Ok(())
+    }
+
+    /// Moves an entry returned by [FileStore::entries] into `dir`, a directory inside the
+    /// quarantine directory of the file store, so it can be inspected before being removed.
+    /// Returns the new path of the entry.
+    pub async fn quarantine_entry(&self, path: &Path, dir: &str) -> anyhow::Result<PathBuf> {
+        let target = self.root.join(QUARANTINE_DIR).join(dir).join(path);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)
+                .await
+                .context("could not create quarantine directory")?;
+        }
+        fs::rename(self.root.join(path), &target)
+            .await
+            .with_context(|| format!("could not quarantine `{}`", path.display()))?;
+        self.remove_empty_parent(path).await;
+        Ok(target)
+    }
+
+    /// Removes the versions directory an entry was in if it has become empty.
+    async fn remove_empty_parent(&self, path: &Path) {
+        if let Some(parent) = path.parent().filter(|v| !v.as_os_str().is_empty()) {
+            // Fails if the directory still has other versions in it, in which case it is kept.
+            _ = fs::remove_dir(self.root.join(parent)).await;
+        }
+    }
+
     /// Get the path a file would be stored at. Also works for files that do not exists.
     fn path(&self, id: FileId) -> PathBuf {
         self.root.join(id.to_string())
     }
 
-    pub async fn get(&self, id: FileId) -> anyhow::Result<Option<impl AsyncRead>> {
-        let path = self.path(id);
-        let file = match fs::File::open(path).await {
-            Ok(file) => file,
-            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(None),
-            Err(err) => return Err(err.into()),
-        };
-        Ok(Some(file))
+    pub async fn get(&self, id: FileId) -> anyhow::Result<Option<fs::File>> {
+        open(self.path(id)).await
+    }
+
+    /// Get the content of an older version of a file.
+    pub async fn get_version(&self, id: FileId, version: i32) -> anyhow::Result<Option<fs::File>> {
+        open(self.version_path(id, version)).await
     }
 
     /// Writes a file to the file store, returning how many bytes were written.
     pub async fn put(&self, id: FileId, data: impl AsyncRead) -> anyhow::Result<u64> {
-        let path = self.path(id);
+        write(self.path(id), data).await
+    }
 
-        // Clean up the file if an error occurs.
-        let defer = guard(path.clone(), |path| {
-            tokio::spawn(fs::remove_file(path));
-        });
+    /// Get the directory in which the older versions of a file are stored.
+    fn versions_dir(&self, id: FileId) -> PathBuf {
+        self.root.join(format!("{id}.versions"))
+    }
+
+    /// Get the path an older version of a file would be stored at.
+    fn version_path(&self, id: FileId, version: i32) -> PathBuf {
+        self.versions_dir(id).join(version.to_string())
+    }
+
+    /// Writes new content for an existing file to a temporary location, returning the path and how
+    /// many bytes were written. The content can then be made current using [FileStore::swap_in].
+    pub async fn stage(&self, id: FileId, data: impl AsyncRead) -> anyhow::Result<(PathBuf, u64)> {
+        let dir = self.versions_dir(id);
+        fs::create_dir_all(&dir)
+            .await
+            .context("could not create versions directory")?;
 
-        let mut file = fs::File::create(path)
+        let path = dir.join(format!("staged-{:x}", rand::random::<u64>()));
+        let size = write(path.clone(), data).await?;
+        Ok((path, size))
+    }
+
+    /// Makes staged content the current content of a file. The current content is kept as
+    /// `version`.
+    pub async fn swap_in(&self, id: FileId, version: i32, staged: &Path) -> anyhow::Result<()> {
+        fs::rename(self.path(id), self.version_path(id, version))
             .await
-            .context("could not create file")?;
+            .context("could not archive current version")?;
+        if let Err(err) = fs::rename(staged, self.path(id)).await {
+            // Restore the previous content so the file doesn't disappear.
+            fs::rename(self.version_path(id, version), self.path(id))
+                .await
+                .context("could not restore current version")?;
+            return Err(err).context("could not move new version into place");
+        }
+        Ok(())
+    }
 
-        let mut pinned_data = pin!(data);
-        let size = tokio::io::copy(&mut pinned_data, &mut file)
+    /// Reverts [FileStore::swap_in], making `version` the current content again. The content that
+    /// was current is removed.
+    pub async fn swap_out(&self, id: FileId, version: i32) -> anyhow::Result<()> {
+        fs::rename(self.version_path(id, version), self.path(id))
             .await
-            .context("while writing to file")?;
+            .context("could not restore version")?;
+        Ok(())
+    }
 
-        // Cancel the deferred function.
-        ScopeGuard::into_inner(defer);
-        Ok(size)
+    /// Permanently remove an older version of a file.
+    pub async fn remove_version(&self, id: FileId, version: i32) -> anyhow::Result<()> {
+        fs::remove_file(self.version_path(id, version))
+            .await
+            .context("could not remove version")?;
+        Ok(())
     }
 
-    /// Permanently remove a file from the file store.
+    /// Permanently remove a file from the file store, along with its older versions and previews.
     pub async fn remove(&self, id: FileId) -> anyhow::Result<()> {
         let path = self.path(id);
         fs::remove_file(path)
             .await
             .context("could not remove file")?;
+
+        match fs::remove_dir_all(self.versions_dir(id)).await {
+            Ok(()) => {}
+            Err(err) if err.kind() == ErrorKind::NotFound => {}
+            Err(err) => return Err(err).context("could not remove older versions"),
+        }
+        self.remove_previews(id).await
+    }
+
+    /// Remove all previews of a file. They will be generated again when requested.
+    pub async fn remove_previews(&self, id: FileId) -> anyhow::Result<()> {
+        for kind in PreviewKind::ALL {
+            match fs::remove_file(self.preview_path(id, kind)).await {
+                Ok(()) => {}
+                Err(err) if err.kind() == ErrorKind::NotFound => {}
+                Err(err) => return Err(err).context("could not remove preview"),
+            }
+        }
         Ok(())
     }
+
+    /// Get the path the preview of a file would be stored at.
+    fn preview_path(&self, id: FileId, kind: PreviewKind) -> PathBuf {
+        self.root.join(format!("{id}.preview.{}", kind.extension()))
+    }
+
+    /// Get a previously generated preview of a file.
+    pub async fn get_preview(
+        &self,
+        id: FileId,
+        kind: PreviewKind,
+    ) -> anyhow::Result<Option<Vec<u8>>> {
+        match fs::read(self.preview_path(id, kind)).await {
+            Ok(data) => Ok(Some(data)),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Stores the preview of a file, replacing any existing preview of the same kind.
+    pub async fn put_preview(
+        &self,
+        id: FileId,
+        kind: PreviewKind,
+        data: &[u8],
+    ) -> anyhow::Result<()> {
+        let path = self.preview_path(id, kind);
+        // Write to a uniquely named temporary file first, so concurrent requests never observe
+        // a partially written preview.
+        let tmp_path = self
+            .root
+            .join(format!("{id}.preview.{:x}.tmp", rand::random::<u64>()));
+
+        let defer = guard(tmp_path.clone(), |path| {
+            tokio::spawn(fs::remove_file(path));
+        });
+        fs::write(&tmp_path, data)
+            .await
+            .context("could not write preview")?;
+        fs::rename(&tmp_path, path)
+            .await
+            .context("could not move preview into place")?;
+
+        // Cancel the deferred function.
+        ScopeGuard::into_inner(defer);
+        Ok(())
+    }
+}
+
+/// Opens a file for reading, returning `None` if it does not exist.
+async fn open(path: PathBuf) -> anyhow::Result<Option<fs::File>> {
+    match fs::File::open(path).await {
+        Ok(file) => Ok(Some(file)),
+        Err(err) if err.kind() == ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Writes data to a file, returning how many bytes were written. The file is removed again if an
+/// error occurs.
+async fn write(path: PathBuf, data: impl AsyncRead) -> anyhow::Result<u64> {
+    // Clean up the file if an error occurs.
+    let defer = guard(path.clone(), |path| {
+        tokio::spawn(fs::remove_file(path));
+    });
+
+    let mut file = fs::File::create(path)
+        .await
+        .context("could not create file")?;
+
+    let _transfer = METRICS.start_transfer(Direction::Upload);
+    let mut pinned_data = pin!(data);
+    let size = tokio::io::copy(&mut pinned_data, &mut file)
+        .await
+        .context("while writing to file")?;
+    METRICS.transferred(Direction::Upload, size);
+
+    // Cancel the deferred function.
+    ScopeGuard::into_inner(defer);
+    Ok(size)
 }
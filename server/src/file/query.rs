@@ -0,0 +1,390 @@
+use chrono::{serde::ts_milliseconds_option, DateTime, TimeDelta, Utc};
+use http::StatusCode;
+use serde::{Deserialize, Serialize};
+use sqlx::{postgres::PgRow, Postgres, QueryBuilder, Row};
+use thiserror::Error;
+use tracing::warn;
+use uuid::Uuid;
+
+use super::{FileDb, FileId, FileInfo};
+
+/// The amount of files returned per page if no limit is requested.
+const DEFAULT_LIMIT: u32 = 100;
+/// The maximum amount of files that can be requested per page.
+const MAX_LIMIT: u32 = 1000;
+
+/// An error that occurred while listing files.
+#[derive(Debug, Error)]
+pub enum QueryError {
+    #[error("the cursor is invalid or does not match the sort order")]
+    InvalidCursor,
+    #[error("the limit must be between 1 and {MAX_LIMIT}")]
+    InvalidLimit,
+    #[error("the minimum size is larger than the maximum size")]
+    InvalidSizeRange,
+    #[error(transparent)]
+    Database(#[from] sqlx::Error),
+}
+
+impl QueryError {
+    /// The status code of the response when the error is returned to the user.
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            QueryError::Database(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            _ => StatusCode::BAD_REQUEST,
+        }
+    }
+}
+
+/// Selects a subset of files. All conditions that are set must match.
+#[derive(Debug, Default, Deserialize)]
+pub struct FileFilter {
+    /// Only include files whose name contains this text, ignoring case. If the text contains `*`
+    /// or `?`, it is used as a glob pattern that has to match the whole name instead.
+    pub name: Option<String>,
+    /// The minimum size of the file in bytes.
+    pub min_size: Option<u64>,
+    /// The maximum size of the file in bytes.
+    pub max_size: Option<u64>,
+    #[serde(default, with = "ts_milliseconds_option")]
+    pub uploaded_after: Option<DateTime<Utc>>,
+    #[serde(default, with = "ts_milliseconds_option")]
+    pub uploaded_before: Option<DateTime<Utc>>,
+    /// Only include files of this MIME type. A whole category can be matched using `image/*`.
+    pub content_type: Option<String>,
+    /// Only include files that were uploaded by this user.
+    pub uploader: Option<Uuid>,
+    /// Only include files that were uploaded at least this many seconds ago.
+    pub older_than: Option<u64>,
+    /// Only include files directly inside this folder.
+    pub folder: Option<Uuid>,
+    /// Only include files with this tag.
+    pub tag: Option<String>,
+    /// Only include files owned by this team.
+    pub team: Option<Uuid>,
+}
+
+impl FileFilter {
+    /// Returns true if no conditions are set, meaning the filter matches all files.
+    pub fn is_empty(&self) -> bool {
+        self.name.is_none()
+            && self.min_size.is_none()
+            && self.max_size.is_none()
+            && self.uploaded_after.is_none()
+            && self.uploaded_before.is_none()
+            && self.content_type.is_none()
+            && self.uploader.is_none()
+            && self.older_than.is_none()
+            && self.folder.is_none()
+            && self.tag.is_none()
+            && self.team.is_none()
+    }
+
+    /// Appends a `where` clause for the filter to a query on the `files` table.
+    fn push_conditions(&self, query: &mut QueryBuilder<'_, Postgres>) -> Result<(), QueryError> {
+        query.push(" where (expires_at is null or expires_at > now())");
+
+        if let Some(name) = &self.name {
+            query
+                .push(" and file_name ilike ")
+                .push_bind(name_pattern(name));
+        }
+
+        if let (Some(min), Some(max)) = (self.min_size, self.max_size) {
+            if min > max {
+                return Err(QueryError::InvalidSizeRange);
+            }
+        }
+        if let Some(min) = self.min_size {
+            query
+                .push(" and file_size >= ")
+                .push_bind(i64::try_from(min).unwrap_or(i64::MAX));
+        }
+        if let Some(max) = self.max_size {
+            query
+                .push(" and file_size <= ")
+                .push_bind(i64::try_from(max).unwrap_or(i64::MAX));
+        }
+
+        if let Some(after) = self.uploaded_after {
+            query.push(" and upload_date >= ").push_bind(after);
+        }
+        if let Some(before) = self.uploaded_before {
+            query.push(" and upload_date < ").push_bind(before);
+        }
+        if let Some(older_than) = self.older_than {
+            let before = i64::try_from(older_than)
+                .ok()
+                .and_then(TimeDelta::try_seconds)
+                .and_then(|age| Utc::now().checked_sub_signed(age));
+            match before {
+                Some(before) => query.push(" and upload_date < ").push_bind(before),
+                // No file can be that old.
+                None => query.push(" and false"),
+            };
+        }
+
+        if let Some(uploader) = self.uploader {
+            query.push(" and uploader_id = ").push_bind(uploader);
+        }
+
+        if let Some(team) = self.team {
+            query.push(" and team_id = ").push_bind(team);
+        }
+        if let Some(folder) = self.folder {
+            query.push(" and folder_id = ").push_bind(folder);
+        }
+        if let Some(tag) = &self.tag {
+            query
+                .push(" and exists(select * from file_tags where file_tags.file_id = files.file_id")
+                .push(" and tag = ")
+                .push_bind(tag.trim().to_lowercase())
+                .push(")");
+        }
+
+        if let Some(content_type) = &self.content_type {
+            match content_type.strip_suffix("/*") {
+                Some(category) => {
+                    let pattern = format!("{}/%", escape_like(&category.to_lowercase()));
+                    query.push(" and content_type like ").push_bind(pattern);
+                }
+                None => {
+                    query
+                        .push(" and content_type = ")
+                        .push_bind(content_type.to_lowercase());
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The column a file listing is sorted by.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortKey {
+    Name,
+    Size,
+    #[default]
+    Date,
+}
+
+impl SortKey {
+    fn column(self) -> &'static str {
+        match self {
+            SortKey::Name => "file_name",
+            SortKey::Size => "file_size",
+            SortKey::Date => "upload_date",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortOrder {
+    Asc,
+    #[default]
+    Desc,
+}
+
+/// Determines which page of a file listing is returned.
+#[derive(Debug, Default, Deserialize)]
+pub struct Page {
+    #[serde(default)]
+    pub sort: SortKey,
+    #[serde(default)]
+    pub order: SortOrder,
+    /// The maximum amount of files to return.
+    pub limit: Option<u32>,
+    /// The `next` cursor of the previous page. The first page is returned if this is not set.
+    pub cursor: Option<String>,
+}
+
+/// A single page of a file listing.
+#[derive(Debug, Serialize)]
+pub struct FilePage {
+    pub files: Vec<FileInfo>,
+    /// The cursor to request the next page with, or `None` if this is the last page.
+    pub next: Option<String>,
+}
+
+/// The position in a listing after which the next page starts. This is the sort key and ID of the
+/// last file on the previous page, so pages stay consistent when files are added or removed.
+#[derive(Serialize, Deserialize)]
+struct Cursor {
+    key: CursorKey,
+    id: FileId,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum CursorKey {
+    Int(i64),
+    Text(String),
+}
+
+impl Cursor {
+    fn encode(&self) -> String {
+        hex::encode(serde_json::to_vec(self).expect("cursor can always be serialized"))
+    }
+
+    fn decode(cursor: &str) -> Result<Self, QueryError> {
+        let bytes = hex::decode(cursor).map_err(|_| QueryError::InvalidCursor)?;
+        serde_json::from_slice(&bytes).map_err(|_| QueryError::InvalidCursor)
+    }
+
+    /// Creates a cursor pointing at a file.
+    fn after(file: &FileInfo, sort: SortKey) -> Self {
+        let key = match sort {
+            SortKey::Name => CursorKey::Text(file.file_name.clone()),
+            SortKey::Size => CursorKey::Int(file.file_size.try_into().unwrap_or(i64::MAX)),
+            // Timestamps are stored with microsecond precision, so the cursor needs the same
+            // precision to not skip files.
+            SortKey::Date => CursorKey::Int(file.upload_date.timestamp_micros()),
+        };
+        Self {
+            key,
+            id: file.file_id,
+        }
+    }
+
+    /// Appends the sort key of the cursor to a query.
+    fn push_key(
+        self,
+        query: &mut QueryBuilder<'_, Postgres>,
+        sort: SortKey,
+    ) -> Result<(), QueryError> {
+        match (sort, self.key) {
+            (SortKey::Name, CursorKey::Text(name)) => query.push_bind(name),
+            (SortKey::Size, CursorKey::Int(size)) => query.push_bind(size),
+            (SortKey::Date, CursorKey::Int(micros)) => query.push_bind(
+                DateTime::<Utc>::from_timestamp_micros(micros).ok_or(QueryError::InvalidCursor)?,
+            ),
+            _ => return Err(QueryError::InvalidCursor),
+        };
+        Ok(())
+    }
+}
+
+impl FileDb {
+    /// Lists files matching a filter, one page at a time.
+    pub async fn list(&self, filter: &FileFilter, page: &Page) -> Result<FilePage, QueryError> {
+        let limit = page.limit.unwrap_or(DEFAULT_LIMIT);
+        if !(1..=MAX_LIMIT).contains(&limit) {
+            return Err(QueryError::InvalidLimit);
+        }
+        let cursor = page.cursor.as_deref().map(Cursor::decode).transpose()?;
+
+        let mut query = QueryBuilder::new(
+            r#"
+                select file_id, file_name, file_size, upload_date, expires_at, version,
+                    content_type, folder_id, description, team_id, broken_at
+                    from files
+            "#,
+        );
+        filter.push_conditions(&mut query)?;
+
+        let column = page.sort.column();
+        let (comparison, direction) = match page.order {
+            SortOrder::Asc => (">", "asc"),
+            SortOrder::Desc => ("<", "desc"),
+        };
+        if let Some(cursor) = cursor {
+            let id = cursor.id;
+            query.push(format!(" and ({column}, file_id) {comparison} ("));
+            cursor.push_key(&mut query, page.sort)?;
+            query.push(", ").push_bind(id).push(")");
+        }
+        query.push(format!(
+            " order by {column} {direction}, file_id {direction} limit "
+        ));
+        // One more file than requested is fetched to find out whether there is a next page.
+        query.push_bind(i64::from(limit) + 1);
+
+        let mut files = query
+            .build()
+            .try_map(|row: PgRow| {
+                Ok(FileInfo {
+                    file_id: row.try_get("file_id")?,
+                    file_name: row.try_get("file_name")?,
+                    file_size: row
+                        .try_get::<i64, _>("file_size")?
+                        .try_into()
+                        .map_err(|err| sqlx::Error::Decode(Box::new(err)))?,
+                    upload_date: row.try_get("upload_date")?,
+                    expires_at: row.try_get("expires_at")?,
+                    version: row.try_get("version")?,
+                    content_type: row.try_get("content_type")?,
+                    folder_id: row.try_get("folder_id")?,
+                    description: row.try_get("description")?,
+                    team_id: row.try_get("team_id")?,
+                    broken_at: row.try_get("broken_at")?,
+                })
+            })
+            .fetch_all(&self.db)
+            .await?;
+
+        let next = if files.len() > limit as usize {
+            files.truncate(limit as usize);
+            files
+                .last()
+                .map(|file| Cursor::after(file, page.sort).encode())
+        } else {
+            None
+        };
+        Ok(FilePage { files, next })
+    }
+
+    /// Counts the files matching a filter, returning the amount of files and their total size.
+    pub async fn count_matching(&self, filter: &FileFilter) -> Result<(u64, u64), QueryError> {
+        let mut query =
+            QueryBuilder::new("select count(*), coalesce(sum(file_size), 0)::bigint from files");
+        filter.push_conditions(&mut query)?;
+
+        let (count, total_size): (i64, i64) = query.build_query_as().fetch_one(&self.db).await?;
+        Ok((count as u64, total_size as u64))
+    }
+
+    /// Permanently removes all files matching a filter, returning the amount of files removed and
+    /// their total size.
+    pub async fn remove_matching(&self, filter: &FileFilter) -> Result<(u64, u64), QueryError> {
+        let mut query = QueryBuilder::new("delete from files");
+        filter.push_conditions(&mut query)?;
+        query.push(" returning file_id, file_size");
+
+        let files: Vec<(FileId, i64)> = query.build_query_as().fetch_all(&self.db).await?;
+
+        // Like with expired files, the rows are already gone, so a failure only leaves an orphaned
+        // blob behind.
+        for (file_id, _) in &files {
+            if let Err(err) = self.store.remove(*file_id).await {
+                warn!(?file_id, "Could not remove file: {err:?}");
+            }
+        }
+
+        let total_size = files.iter().map(|(_, size)| *size as u64).sum();
+        Ok((files.len() as u64, total_size))
+    }
+}
+
+/// Converts a name filter into a pattern for `ilike`.
+fn name_pattern(name: &str) -> String {
+    let escaped = escape_like(name);
+    if name.contains(['*', '?']) {
+        escaped.replace('*', "%").replace('?', "_")
+    } else {
+        format!("%{escaped}%")
+    }
+}
+
+/// Escapes the characters that have a special meaning in `like` patterns.
+fn escape_like(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '%' | '_' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
@@ -0,0 +1,245 @@
+use chrono::{serde::ts_milliseconds, DateTime, Utc};
+use http::StatusCode;
+use serde::Serialize;
+use sqlx::{PgExecutor, Postgres, Transaction};
+use thiserror::Error;
+use uuid::Uuid;
+
+use crate::file::{normalize_file_name, FileNameError};
+
+/// The reasons a folder operation may fail.
+#[derive(Debug, Error)]
+pub enum FolderError {
+    #[error("invalid folder name: {0}")]
+    InvalidName(#[from] FileNameError),
+    #[error("folder does not exist")]
+    NotFound,
+    #[error("a folder named `{0}` already exists here")]
+    Exists(String),
+    #[error("a folder can not be moved into itself or one of its subfolders")]
+    Cycle,
+    #[error(transparent)]
+    Database(#[from] sqlx::Error),
+}
+
+impl FolderError {
+    /// The status code to respond with when this error is caused by the user.
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            FolderError::Database(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            FolderError::NotFound => StatusCode::NOT_FOUND,
+            FolderError::Exists(_) => StatusCode::CONFLICT,
+            FolderError::InvalidName(_) | FolderError::Cycle => StatusCode::BAD_REQUEST,
+        }
+    }
+}
+
+/// A folder that files can be organized in.
+#[derive(Debug, Serialize)]
+pub struct Folder {
+    pub folder_id: Uuid,
+    /// The folder this folder is in, or `None` if it is at the top level.
+    pub parent_id: Option<Uuid>,
+    pub name: String,
+    #[serde(with = "ts_milliseconds")]
+    pub created_at: DateTime<Utc>,
+}
+
+/// Lists all folders of a user.
+pub async fn list_folders(db: impl PgExecutor<'_>, owner: Uuid) -> sqlx::Result<Vec<Folder>> {
+    sqlx::query_as!(
+        Folder,
+        r#"
+            select folder_id, parent_id, name, created_at
+                from folders
+                where owner_id=$1
+                order by name
+        "#,
+        owner,
+    )
+    .fetch_all(db)
+    .await
+}
+
+/// Checks that a folder exists and is owned by `owner`.
+pub async fn check_folder(
+    db: impl PgExecutor<'_>,
+    owner: Uuid,
+    folder_id: Uuid,
+) -> Result<(), FolderError> {
+    let exists = sqlx::query!(
+        "select exists(select * from folders where folder_id=$1 and owner_id=$2)",
+        folder_id,
+        owner,
+    )
+    .fetch_one(db)
+    .await?
+    .exists
+    .unwrap_or(false);
+
+    if !exists {
+        return Err(FolderError::NotFound);
+    }
+    Ok(())
+}
+
+/// Creates a new folder, either at the top level or inside `parent`.
+pub async fn create_folder(
+    tx: &mut Transaction<'_, Postgres>,
+    owner: Uuid,
+    parent: Option<Uuid>,
+    name: &str,
+) -> Result<Folder, FolderError> {
+    let name = normalize_file_name(name)?;
+    if let Some(parent) = parent {
+        check_folder(&mut **tx, owner, parent).await?;
+    }
+
+    sqlx::query_as!(
+        Folder,
+        r#"
+            insert into folders (folder_id, owner_id, parent_id, name)
+                values (gen_random_uuid(), $1, $2, $3)
+                returning folder_id, parent_id, name, created_at
+        "#,
+        owner,
+        parent,
+        name,
+    )
+    .fetch_one(&mut **tx)
+    .await
+    .map_err(|err| name_conflict(err, &name))
+}
+
+/// Finds a folder by its path, such as `builds/nightly`, creating any folders that don't exist
+/// yet. Returns `None` if the path refers to the top level.
+pub async fn resolve_path(
+    tx: &mut Transaction<'_, Postgres>,
+    owner: Uuid,
+    path: &str,
+) -> Result<Option<Uuid>, FolderError> {
+    let mut current: Option<Uuid> = None;
+    for name in path.split('/').filter(|v| !v.trim().is_empty()) {
+        let name = normalize_file_name(name)?;
+
+        let existing = sqlx::query!(
+            r#"
+                select folder_id
+                    from folders
+                    where owner_id=$1 and parent_id is not distinct from $2 and name=$3
+            "#,
+            owner,
+            current,
+            name,
+        )
+        .fetch_optional(&mut **tx)
+        .await?;
+
+        current = Some(match existing {
+            Some(row) => row.folder_id,
+            None => create_folder(tx, owner, current, &name).await?.folder_id,
+        });
+    }
+    Ok(current)
+}
+
+/// Renames a folder and/or moves it into another folder. A `parent` of `Some(None)` moves the
+/// folder to the top level.
+pub async fn update_folder(
+    tx: &mut Transaction<'_, Postgres>,
+    owner: Uuid,
+    folder_id: Uuid,
+    name: Option<&str>,
+    parent: Option<Option<Uuid>>,
+) -> Result<Folder, FolderError> {
+    let Some(current) = sqlx::query!(
+        "select name from folders where folder_id=$1 and owner_id=$2",
+        folder_id,
+        owner,
+    )
+    .fetch_optional(&mut **tx)
+    .await?
+    else {
+        return Err(FolderError::NotFound);
+    };
+
+    let name = match name {
+        Some(name) => normalize_file_name(name)?,
+        None => current.name,
+    };
+
+    if let Some(Some(parent)) = parent {
+        check_folder(&mut **tx, owner, parent).await?;
+
+        // Walk up from the new parent. If the folder itself is found along the way, it would end
+        // up inside of itself.
+        let is_cycle = sqlx::query!(
+            r#"
+                with recursive ancestors(folder_id, parent_id) as (
+                    select folder_id, parent_id from folders where folder_id=$1
+                    union
+                    select folders.folder_id, folders.parent_id
+                        from folders
+                            join ancestors on folders.folder_id=ancestors.parent_id
+                )
+                select exists(select * from ancestors where folder_id=$2) as "is_cycle!"
+            "#,
+            parent,
+            folder_id,
+        )
+        .fetch_one(&mut **tx)
+        .await?
+        .is_cycle;
+
+        if is_cycle {
+            return Err(FolderError::Cycle);
+        }
+    }
+
+    let folder = sqlx::query_as!(
+        Folder,
+        r#"
+            update folders
+                set name=$2,
+                    parent_id=case when $3 then $4 else parent_id end
+                where folder_id=$1
+                returning folder_id, parent_id, name, created_at
+        "#,
+        folder_id,
+        name,
+        parent.is_some(),
+        parent.flatten(),
+    )
+    .fetch_one(&mut **tx)
+    .await
+    .map_err(|err| name_conflict(err, &name))?;
+
+    Ok(folder)
+}
+
+/// Removes a folder and its subfolders. The files inside are moved to the top level.
+pub async fn remove_folder(
+    db: impl PgExecutor<'_>,
+    owner: Uuid,
+    folder_id: Uuid,
+) -> sqlx::Result<bool> {
+    let result = sqlx::query!(
+        "delete from folders where folder_id=$1 and owner_id=$2",
+        folder_id,
+        owner,
+    )
+    .execute(db)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Converts a database error, reporting a violation of the unique folder name constraint as
+/// [FolderError::Exists].
+fn name_conflict(err: sqlx::Error, name: &str) -> FolderError {
+    match &err {
+        sqlx::Error::Database(db_err) if db_err.is_unique_violation() => {
+            FolderError::Exists(name.to_string())
+        }
+        _ => FolderError::Database(err),
+    }
+}
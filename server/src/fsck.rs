@@ -0,0 +1,198 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+    time::Duration,
+};
+
+use chrono::Utc;
+use serde::Serialize;
+use tracing::{info, warn};
+
+use crate::file::{EntryKind, FileDb, FileId, KnownContent, QUARANTINE_DIR};
+
+/// Content that was modified more recently than this is never considered orphaned, as it may
+/// belong to an upload that is still in progress.
+pub const ORPHAN_GRACE_PERIOD: Duration = Duration::from_secs(10 * 60);
+
+/// The result of checking the file store against the database.
+#[derive(Debug, Default, Serialize)]
+pub struct FsckReport {
+    /// True if the problems that were found have been repaired.
+    pub fixed: bool,
+    /// The amount of files and older versions that were checked.
+    pub checked: u64,
+    /// Content in the file store that doesn't belong to any file, relative to the root of the
+    /// file store.
+    pub orphans: Vec<PathBuf>,
+    /// The directory orphans were moved to, relative to the root of the file store.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quarantine_dir: Option<PathBuf>,
+    /// Files or older versions whose content is missing.
+    pub missing: Vec<BrokenContent>,
+    /// Files or older versions whose content doesn't match their size.
+    pub size_mismatches: Vec<BrokenContent>,
+    /// Files that were marked as broken, but whose content is intact again.
+    pub recovered: Vec<FileId>,
+}
+
+impl FsckReport {
+    /// The amount of problems that were found.
+    pub fn problem_count(&self) -> usize {
+        self.orphans.len() + self.missing.len() + self.size_mismatches.len() + self.recovered.len()
+    }
+}
+
+/// A file or older version with missing or damaged content.
+#[derive(Debug, Serialize)]
+pub struct BrokenContent {
+    pub file_id: FileId,
+    pub version: Option<i32>,
+    /// The size according to the database.
+    pub expected_size: u64,
+    /// The size of the content in the file store, if it exists.
+    pub actual_size: Option<u64>,
+}
+
+/// Checks that the file store matches the database.
+///
+/// If `fix` is set, orphaned content is moved into a quarantine directory, files with missing or
+/// damaged content are marked as broken and older versions with missing content are removed.
+/// Otherwise nothing is changed.
+pub async fn check(file_db: &FileDb, fix: bool) -> anyhow::Result<FsckReport> {
+    let store = file_db.file_store();
+    let known = KnownContent::load(file_db.db()).await?;
+    let entries = store.entries().await?;
+
+    let mut report = FsckReport {
+        fixed: fix,
+        checked: (known.files.len() + known.versions.len()) as u64,
+        ..Default::default()
+    };
+
+    let mut sizes = HashMap::new();
+    for entry in entries {
+        if !known.is_orphan(entry.kind) {
+            sizes.insert(entry.kind, entry.size);
+        } else if entry.is_older_than(ORPHAN_GRACE_PERIOD) {
+            report.orphans.push(entry.path);
+        }
+    }
+
+    let mut broken_files = HashSet::new();
+    for (&file_id, &expected_size) in &known.files {
+        let actual_size = sizes.get(&EntryKind::Content(file_id)).copied();
+        if let Some(list) = find_problem(&mut report, actual_size, expected_size) {
+            broken_files.insert(file_id);
+            list.push(BrokenContent {
+                file_id,
+                version: None,
+                expected_size,
+                actual_size,
+            });
+        }
+    }
+    let mut missing_versions = Vec::new();
+    for (&(file_id, version), &expected_size) in &known.versions {
+        let actual_size = sizes.get(&EntryKind::Version(file_id, version)).copied();
+        if let Some(list) = find_problem(&mut report, actual_size, expected_size) {
+            if actual_size.is_none() {
+                missing_versions.push((file_id, version));
+            }
+            list.push(BrokenContent {
+                file_id,
+                version: Some(version),
+                expected_size,
+                actual_size,
+            });
+        }
+    }
+
+    report.orphans.sort();
+    report.missing.sort_by_key(|v| (v.file_id, v.version));
+    report
+        .size_mismatches
+        .sort_by_key(|v| (v.file_id, v.version));
+
+    // Files that were marked as broken before, but have intact content now, such as after being
+    // restored from a backup.
+    report.recovered = sqlx::query_scalar!(
+        r#"select file_id as "file_id: FileId" from files where broken_at is not null"#
+    )
+    .fetch_all(file_db.db())
+    .await?
+    .into_iter()
+    .filter(|file_id| !broken_files.contains(file_id))
+    .collect();
+
+    if fix {
+        repair(file_db, &mut report, &broken_files, &missing_versions).await?;
+    }
+
+    Ok(report)
+}
+
+/// Returns the list a file or version belongs in if its content is missing or damaged.
+fn find_problem(
+    report: &mut FsckReport,
+    actual_size: Option<u64>,
+    expected_size: u64,
+) -> Option<&mut Vec<BrokenContent>> {
+    match actual_size {
+        None => Some(&mut report.missing),
+        Some(size) if size != expected_size => Some(&mut report.size_mismatches),
+        Some(_) => None,
+    }
+}
+
+async fn repair(
+    file_db: &FileDb,
+    report: &mut FsckReport,
+    broken_files: &HashSet<FileId>,
+    missing_versions: &[(FileId, i32)],
+) -> anyhow::Result<()> {
+    if !report.orphans.is_empty() {
+        let dir = Utc::now().format("%Y%m%dT%H%M%S").to_string();
+        for path in &report.orphans {
+            file_db.file_store().quarantine_entry(path, &dir).await?;
+        }
+        info!(
+            amount = report.orphans.len(),
+            dir, "Moved orphaned content into quarantine."
+        );
+        report.quarantine_dir = Some(PathBuf::from(QUARANTINE_DIR).join(dir));
+    }
+
+    let mut tx = file_db.db().begin().await?;
+
+    let broken: Vec<_> = broken_files.iter().copied().collect();
+    sqlx::query!(
+        "update files set broken_at=now() where file_id=any($1) and broken_at is null",
+        &broken as &[FileId],
+    )
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query!(
+        "update files set broken_at=null where file_id=any($1)",
+        &report.recovered as &[FileId],
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    // Older versions can't be downloaded without their content, so they are removed.
+    for &(file_id, version) in missing_versions {
+        sqlx::query!(
+            "delete from file_versions where file_id=$1 and version=$2",
+            file_id as FileId,
+            version,
+        )
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    tx.commit().await?;
+
+    if !broken.is_empty() {
+        warn!(amount = broken.len(), "Marked files with damaged content.");
+    }
+    Ok(())
+}
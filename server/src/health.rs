@@ -0,0 +1,174 @@
+use std::{collections::HashSet, future::Future, time::Instant};
+
+use serde::Serialize;
+use sqlx::PgPool;
+use tokio::time::{timeout, Duration};
+use tracing::warn;
+
+use crate::{file::FileStore, MIGRATOR};
+
+/// The maximum time a single check may take before it is considered failed.
+const CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The result of checking if the server is ready to handle requests.
+#[derive(Debug, Serialize)]
+pub struct Readiness {
+    /// True if all checks succeeded and the server is not shutting down.
+    pub ready: bool,
+    /// True if the server is shutting down.
+    pub draining: bool,
+    pub database: DatabaseCheck,
+    pub storage: StorageCheck,
+    pub migrations: MigrationsCheck,
+}
+
+/// Whether the database can be reached.
+#[derive(Debug, Serialize)]
+pub struct DatabaseCheck {
+    pub ok: bool,
+    /// The time it took to run a query on the database.
+    pub latency_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Whether files can be stored.
+#[derive(Debug, Serialize)]
+pub struct StorageCheck {
+    pub ok: bool,
+    pub writable: bool,
+    pub available_bytes: Option<u64>,
+    /// The amount of bytes that must be available.
+    pub min_free_bytes: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Whether the database schema is up to date.
+#[derive(Debug, Serialize)]
+pub struct MigrationsCheck {
+    pub ok: bool,
+    /// The versions of the migrations that have not been applied.
+    pub pending: Vec<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Checks if the server is ready to handle requests, running all checks concurrently.
+pub async fn readiness(
+    db: &PgPool,
+    store: &FileStore,
+    min_free_space: u64,
+    draining: bool,
+) -> Readiness {
+    let (database, storage, migrations) = tokio::join!(
+        check_database(db),
+        check_storage(store, min_free_space),
+        check_migrations(db),
+    );
+
+    Readiness {
+        ready: !draining && database.ok && storage.ok && migrations.ok,
+        draining,
+        database,
+        storage,
+        migrations,
+    }
+}
+
+/// Runs a check, failing it if it takes longer than [CHECK_TIMEOUT].
+async fn with_timeout<T>(check: impl Future<Output = anyhow::Result<T>>) -> anyhow::Result<T> {
+    match timeout(CHECK_TIMEOUT, check).await {
+        Ok(result) => result,
+        Err(_) => Err(anyhow::anyhow!("timed out")),
+    }
+}
+
+async fn check_database(db: &PgPool) -> DatabaseCheck {
+    let start = Instant::now();
+    let result = with_timeout(async {
+        sqlx::query!("select 1 as ping").fetch_one(db).await?;
+        Ok(())
+    })
+    .await;
+
+    DatabaseCheck {
+        ok: result.is_ok(),
+        latency_ms: start.elapsed().as_millis() as u64,
+        error: result.err().map(|err| {
+            warn!("Readiness check of the database failed: {err:#}");
+            "unreachable".to_string()
+        }),
+    }
+}
+
+async fn check_storage(store: &FileStore, min_free_space: u64) -> StorageCheck {
+    let writable = with_timeout(store.check_writable()).await;
+    let available = with_timeout(store.available_space()).await;
+
+    // The details of errors are only logged, as they may include paths of the server.
+    let error = match (&writable, &available) {
+        (Err(err), _) => {
+            warn!("Readiness check of the file store failed: {err:#}");
+            Some("not writable".to_string())
+        }
+        (_, Err(err)) => {
+            warn!("Could not determine the free space of the file store: {err:#}");
+            Some("free space unknown".to_string())
+        }
+        (_, Ok(v)) if *v < min_free_space => Some("not enough free space".to_string()),
+        _ => None,
+    };
+
+    StorageCheck {
+        ok: error.is_none(),
+        writable: writable.is_ok(),
+        available_bytes: available.ok(),
+        min_free_bytes: min_free_space,
+        error,
+    }
+}
+
+async fn check_migrations(db: &PgPool) -> MigrationsCheck {
+    match with_timeout(pending_migrations(db)).await {
+        Ok(pending) => MigrationsCheck {
+            ok: pending.is_empty(),
+            pending,
+            error: None,
+        },
+        Err(err) => {
+            warn!("Readiness check of the migrations failed: {err:#}");
+            MigrationsCheck {
+                ok: false,
+                pending: Vec::new(),
+                error: Some("unknown".to_string()),
+            }
+        }
+    }
+}
+
+/// Returns the versions of the migrations that have not been applied to the database yet.
+pub async fn pending_migrations(db: &PgPool) -> anyhow::Result<Vec<i64>> {
+    // The table is created by the migrator, so it doesn't exist before the first migration and
+    // isn't known to the query macros.
+    let exists =
+        sqlx::query_scalar!(r#"select to_regclass('_sqlx_migrations') is not null as "exists!""#)
+            .fetch_one(db)
+            .await?;
+    let applied: HashSet<i64> = if exists {
+        sqlx::query_scalar("select version from _sqlx_migrations where success")
+            .fetch_all(db)
+            .await?
+            .into_iter()
+            .collect()
+    } else {
+        HashSet::new()
+    };
+
+    Ok(MIGRATOR
+        .iter()
+        .filter(|migration| !migration.migration_type.is_down_migration())
+        .map(|migration| migration.version)
+        .filter(|version| !applied.contains(version))
+        .collect())
+}
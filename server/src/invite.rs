@@ -0,0 +1,41 @@
+use chrono::{DateTime, Utc};
+use rand::{distributions::Alphanumeric, Rng};
+use sqlx::PgExecutor;
+use uuid::Uuid;
+
+/// The length of generated invite codes.
+const GENERATED_CODE_LEN: usize = 12;
+
+/// Generates a random invite code.
+pub fn random_code() -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(GENERATED_CODE_LEN)
+        .map(char::from)
+        .collect()
+}
+
+/// Creates an invite code. Returns `false` if an invite with the same code already exists.
+pub async fn create_invite(
+    db: impl PgExecutor<'_>,
+    invite_code: &str,
+    max_uses: u16,
+    valid_until: DateTime<Utc>,
+    created_by: Uuid,
+) -> sqlx::Result<bool> {
+    let result = sqlx::query!(
+        r#"
+            insert into invites (invite, max_uses, valid_until, created_by)
+                values ($1, $2, $3, $4)
+                on conflict (invite) do nothing
+        "#,
+        invite_code,
+        max_uses as i32,
+        valid_until,
+        created_by,
+    )
+    .execute(db)
+    .await?;
+
+    Ok(result.rows_affected() == 1)
+}
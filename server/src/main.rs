@@ -1,87 +1,303 @@
-use std::{env, net::SocketAddr, sync::Arc};
+use std::{future::IntoFuture, io, net::SocketAddr, path::PathBuf, sync::Arc, time::Duration};
 
 use anyhow::Context;
-use axum::Router;
-use sqlx::PgPool;
-use tracing::{error, info, level_filters::LevelFilter};
-use tracing_subscriber::EnvFilter;
+use axum::{extract::DefaultBodyLimit, middleware, Router};
+use clap::{Parser, Subcommand};
+use futures::FutureExt;
+use sqlx::{migrate::Migrator, postgres::PgPoolOptions, PgPool};
+use tracing::{error, info, warn};
+use tracing_subscriber::{fmt::writer::BoxMakeWriter, EnvFilter};
 
 use crate::{
-    auth::ssh::SSHAuthState,
-    config::Config,
+    auth::{ssh::SSHAuthState, UserAuthFailures},
+    commands::{invite::InviteCommand, user::UserCommand, FsckArgs},
+    config::{reload_on_sighup, Config, ConfigHandle, LogFormat, LoggingConfig},
     file::{FileDb, FileStore},
+    rate_limit::RateLimiter,
+    setup::SetupState,
+    shutdown::Shutdown,
     state::AppState,
 };
 
 mod api;
+mod audit;
 mod auth;
+mod client;
+mod commands;
 mod config;
 mod error;
 mod file;
+mod folder;
+mod fsck;
+mod health;
+mod invite;
+mod metrics;
+mod preview;
+mod rate_limit;
 mod session;
+mod setup;
+mod shutdown;
+mod slug;
 mod state;
+mod stats;
+mod tag;
+mod team;
+mod tls;
+mod upload_request;
+mod user;
+
+/// The migrations of the database, embedded in the binary.
+static MIGRATOR: Migrator = sqlx::migrate!("../migrations");
+
+/// Host files and the API of beacon, or manage the instance.
+#[derive(Parser)]
+struct Command {
+    /// The configuration file. Defaults to `BEACON_SERVER_CONFIG` or `beacon.toml`.
+    #[arg(long, global = true, value_name = "PATH")]
+    config: Option<PathBuf>,
+    /// Override a setting of the configuration file, such as `database.pool_size=20`. Takes
+    /// precedence over environment variables. Can be used multiple times.
+    #[arg(long = "set", global = true, value_name = "KEY=VALUE")]
+    overrides: Vec<String>,
+    #[command(subcommand)]
+    subcommand: Option<Subcommands>,
+}
+
+#[derive(Subcommand)]
+enum Subcommands {
+    /// Run the server. This is the default when no subcommand is provided.
+    Serve,
+    /// Apply pending database migrations.
+    Migrate,
+    #[command(subcommand)]
+    User(UserCommand),
+    #[command(subcommand)]
+    Invite(InviteCommand),
+    Fsck(FsckArgs),
+    /// Remove expired files and sessions, as well as orphaned content in the file store.
+    Gc,
+}
 
 #[tokio::main]
 pub async fn main() -> anyhow::Result<()> {
+    let command = Command::parse();
     dotenvy::dotenv().ok();
 
-    tracing_subscriber::fmt()
-        .with_env_filter(
-            EnvFilter::builder()
-                .with_default_directive(LevelFilter::INFO.into())
-                .from_env()
-                .context("failed to configure logger")?,
-        )
-        .init();
+    let config = Config::read(command.config.clone(), &command.overrides)
+        .await
+        .context("invalid configuration")?;
 
-    let config = Config::read().await.context("could not read config")?;
+    let subcommand = command.subcommand.unwrap_or(Subcommands::Serve);
+    // Logs are written to stderr for the other commands, so they don't mix with their output.
+    let writer = match subcommand {
+        Subcommands::Serve => BoxMakeWriter::new(io::stdout),
+        _ => BoxMakeWriter::new(io::stderr),
+    };
+    init_logging(&config.logging, writer);
 
-    let bind_addr: SocketAddr = env::var("BEACON_SERVER_ADDR")
-        .unwrap_or_else(|_| "127.0.0.1:4000".to_string())
-        .parse()
-        .context("failed to parse bind address")?;
+    match subcommand {
+        Subcommands::Serve => {
+            let config = ConfigHandle::new(config, command.config, command.overrides);
+            serve(Arc::new(config)).await
+        }
+        Subcommands::Migrate => commands::migrate(&connect_database(&config).await?).await,
+        Subcommands::User(command) => {
+            commands::user::run(&open_file_db(&config).await?, command).await
+        }
+        Subcommands::Invite(command) => {
+            commands::invite::run(open_file_db(&config).await?.db(), command).await
+        }
+        Subcommands::Fsck(args) => commands::fsck(&open_file_db(&config).await?, args).await,
+        Subcommands::Gc => commands::gc(&open_file_db(&config).await?).await,
+    }
+}
 
+fn init_logging(config: &LoggingConfig, writer: BoxMakeWriter) {
+    // The level has been validated when reading the config.
+    let filter = EnvFilter::new(&config.level);
+    let builder = tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(writer);
+    match config.format {
+        LogFormat::Full => builder.init(),
+        LogFormat::Compact => builder.compact().init(),
+        LogFormat::Json => builder.json().init(),
+    }
+}
+
+async fn connect_database(config: &Config) -> anyhow::Result<PgPool> {
     info!("Connecting to database.");
-    let pool = PgPool::connect(
-        &env::var("DATABASE_URL").context("could not read `DATABASE_URL` environment variable")?,
-    )
-    .await
-    .context("could not connect to database")?;
+    PgPoolOptions::new()
+        .max_connections(config.database.pool_size)
+        .acquire_timeout(Duration::from_secs(config.database.acquire_timeout))
+        .connect(&config.database.url)
+        .await
+        .context("could not connect to database")
+}
+
+async fn open_file_store(config: &Config) -> anyhow::Result<FileStore> {
+    info!("Opening file store.");
+    FileStore::new(&config.storage.root)
+        .await
+        .context("could not create file store")
+}
+
+/// Opens the database and file store for a command other than serving, which requires the
+/// database to be migrated already.
+async fn open_file_db(config: &Config) -> anyhow::Result<FileDb> {
+    let pool = connect_database(config).await?;
+    commands::ensure_migrated(&pool).await?;
+    Ok(FileDb::new(pool, open_file_store(config).await?))
+}
+
+async fn serve(config_handle: Arc<ConfigHandle>) -> anyhow::Result<()> {
+    let config = config_handle.load();
+    let bind_addr = config.bind_addr;
 
-    sqlx::migrate!("../migrations")
+    let pool = connect_database(&config).await?;
+    MIGRATOR
         .run(&pool)
         .await
         .context("error while migrating database")?;
 
-    info!("Opening file store.");
-    let file_store = FileStore::new(
-        env::var("FILE_STORE_ROOT")
-            .context("could not read `FILE_STORE_ROOT` environment variable")?,
-    )
-    .await
-    .context("could not create file store")?;
+    config_handle
+        .load_settings(&pool)
+        .await
+        .context("could not load the settings stored in the database")?;
+
+    let setup = SetupState::init(&pool)
+        .await
+        .context("could not set up the initial administrator")?;
+
+    let file_store = open_file_store(&config).await?;
+
+    let file_db = Arc::new(FileDb::new(pool.clone(), file_store));
+    // Content without a file is left behind by uploads that were interrupted, such as by a
+    // previous shutdown.
+    match file_db.remove_orphans(fsck::ORPHAN_GRACE_PERIOD).await {
+        Ok(0) => {}
+        Ok(amount) => info!(amount, "Removed orphaned content from the file store."),
+        Err(err) => error!("Could not remove orphaned content: {err:?}"),
+    }
+    file_db.start_expiry_task();
+
+    let metrics_config = &config.metrics;
+    let serve_metrics = metrics_config.enabled && metrics_config.bind_addr.is_none();
+    let metrics_addr = metrics_config.bind_addr.filter(|_| metrics_config.enabled);
 
-    let file_db = FileDb::new(pool.clone(), file_store);
+    let drain_timeout = Duration::from_secs(config.drain_timeout);
+    let max_body_size = config.limits.max_body_size;
+    let shutdown = Shutdown::default();
+
+    tokio::spawn(reload_on_sighup(config_handle.clone(), pool.clone()));
+    let rate_limiter = RateLimiter::init(config_handle.clone());
 
     let state = AppState {
         database: pool,
-        file_store: Arc::new(file_db),
+        file_store: file_db,
         ssh_auth: SSHAuthState::init(),
-        config: Arc::new(config),
-        auth_failures: Arc::new(Default::default()),
+        config: config_handle,
+        auth_failures: UserAuthFailures::init(),
+        rate_limiter,
+        shutdown: shutdown.clone(),
+        setup,
     };
 
-    let app = Router::new()
+    // Metrics can be served on a separate address, so they aren't reachable from the outside.
+    if let Some(metrics_addr) = metrics_addr {
+        let metrics_app = Router::new()
+            .nest("/metrics", api::metrics_router())
+            .with_state(state.clone());
+        let listener = tokio::net::TcpListener::bind(metrics_addr)
+            .await
+            .context("could not bind metrics address")?;
+        info!("Serving metrics on `http://{metrics_addr}/metrics`.");
+        tokio::spawn(async move {
+            if let Err(err) = axum::serve(listener, metrics_app).await {
+                error!("Metrics server stopped: {err}");
+            }
+        });
+    }
+
+    let mut app = Router::new()
         .nest("/api", api::router())
+        .nest("/s", api::short_link_router())
+        .merge(api::health_router());
+    if serve_metrics {
+        app = app.nest("/metrics", api::metrics_router());
+    }
+    let app = app
+        .route_layer(middleware::from_fn(metrics::track_requests))
+        .layer(DefaultBodyLimit::max(max_body_size))
         // Provides an API to easily read or modify cookies.
         .layer(tower_cookies::CookieManagerLayer::new())
         .with_state(state);
 
-    info!("Listening on `http://{}`.", &bind_addr);
-    let listener = tokio::net::TcpListener::bind(bind_addr).await.unwrap();
-    axum::serve(listener, app.into_make_service())
-        .await
-        .unwrap();
+    tokio::spawn({
+        let shutdown = shutdown.clone();
+        async move {
+            shutdown::signal_received().await;
+            info!("Shutting down, waiting for in-flight requests to finish.");
+            shutdown.start();
+        }
+    });
+
+    let app = app.into_make_service_with_connect_info::<SocketAddr>();
+    let server = match config.tls.files() {
+        Some((cert, key)) => {
+            let rustls = tls::load_certificate(cert, key).await?;
+            tokio::spawn(tls::reload_certificate(
+                rustls.clone(),
+                cert.to_path_buf(),
+                key.to_path_buf(),
+                config.tls.reload_interval,
+            ));
+            if let Some(redirect_addr) = config.tls.redirect_addr {
+                tls::serve_redirect(redirect_addr, config.external_url.clone(), shutdown.clone())
+                    .await?;
+            }
+
+            let handle = axum_server::Handle::new();
+            tokio::spawn({
+                let (handle, shutdown) = (handle.clone(), shutdown.clone());
+                async move {
+                    shutdown.started().await;
+                    handle.graceful_shutdown(None);
+                }
+            });
+
+            info!("Listening on `https://{}`.", &bind_addr);
+            axum_server::bind_rustls(bind_addr, rustls)
+                .handle(handle)
+                .serve(app)
+                .boxed()
+        }
+        None => {
+            let listener = tokio::net::TcpListener::bind(bind_addr)
+                .await
+                .with_context(|| format!("could not bind `{bind_addr}`"))?;
+            info!("Listening on `http://{}`.", &bind_addr);
+            axum::serve(listener, app)
+                .with_graceful_shutdown({
+                    let shutdown = shutdown.clone();
+                    async move { shutdown.started().await }
+                })
+                .into_future()
+                .boxed()
+        }
+    };
+
+    tokio::select! {
+        result = server => result.context("error while serving")?,
+        _ = async {
+            shutdown.started().await;
+            tokio::time::sleep(drain_timeout).await;
+        } => {
+            // Interrupted uploads leave content behind, which is removed on the next start.
+            warn!("Requests did not finish within the drain timeout, aborting them.");
+        }
+    }
 
+    info!("Shut down.");
     Ok(())
 }
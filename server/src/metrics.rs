@@ -0,0 +1,235 @@
+use std::{sync::LazyLock, time::Instant};
+
+use axum::{
+    extract::{MatchedPath, Request},
+    middleware::Next,
+    response::Response,
+};
+use prometheus::{
+    exponential_buckets, Encoder, HistogramOpts, HistogramVec, IntCounterVec, IntGauge,
+    IntGaugeVec, Opts, Registry, TextEncoder,
+};
+
+/// The metrics of the server, exported in the Prometheus text format.
+pub static METRICS: LazyLock<Metrics> = LazyLock::new(Metrics::new);
+
+/// The direction of a file transfer, used as the `direction` label.
+#[derive(Debug, Clone, Copy)]
+pub enum Direction {
+    Upload,
+    Download,
+}
+
+impl Direction {
+    fn as_str(self) -> &'static str {
+        match self {
+            Direction::Upload => "upload",
+            Direction::Download => "download",
+        }
+    }
+}
+
+pub struct Metrics {
+    registry: Registry,
+    /// Handled requests by method, route and status code.
+    http_requests: IntCounterVec,
+    /// The time until the response headers were sent, by method and route.
+    http_request_duration: HistogramVec,
+    /// File content that was received or sent, by direction.
+    transfer_bytes: IntCounterVec,
+    /// The time it took to receive or send file content, by direction.
+    transfer_duration: HistogramVec,
+    /// Uploads and downloads that are in progress, by direction.
+    active_transfers: IntGaugeVec,
+    /// Failed authentication attempts by method.
+    auth_failures: IntCounterVec,
+    /// Requests rejected by a rate limit, by policy.
+    rate_limited: IntCounterVec,
+    pub db_connections: IntGauge,
+    pub db_connections_idle: IntGauge,
+    pub storage_bytes: IntGauge,
+    pub stored_files: IntGauge,
+    pub ssh_tickets: IntGauge,
+}
+
+impl Metrics {
+    fn new() -> Self {
+        let registry =
+            Registry::new_custom(Some("beacon".to_string()), None).expect("namespace is valid");
+
+        let http_requests = IntCounterVec::new(
+            Opts::new("http_requests_total", "Handled HTTP requests."),
+            &["method", "route", "status"],
+        )
+        .unwrap();
+        let http_request_duration = HistogramVec::new(
+            HistogramOpts::new(
+                "http_request_duration_seconds",
+                "Time until the response of a HTTP request was ready.",
+            ),
+            &["method", "route"],
+        )
+        .unwrap();
+        let transfer_bytes = IntCounterVec::new(
+            Opts::new("transfer_bytes_total", "File content received or sent."),
+            &["direction"],
+        )
+        .unwrap();
+        let transfer_duration = HistogramVec::new(
+            HistogramOpts::new(
+                "transfer_duration_seconds",
+                "Time it took to receive or send file content.",
+            )
+            // From 10ms up to about 45 minutes.
+            .buckets(exponential_buckets(0.01, 4.0, 10).unwrap()),
+            &["direction"],
+        )
+        .unwrap();
+        let active_transfers = IntGaugeVec::new(
+            Opts::new("active_transfers", "Uploads and downloads in progress."),
+            &["direction"],
+        )
+        .unwrap();
+        let auth_failures = IntCounterVec::new(
+            Opts::new("auth_failures_total", "Failed authentication attempts."),
+            &["method"],
+        )
+        .unwrap();
+        let rate_limited = IntCounterVec::new(
+            Opts::new("rate_limited_total", "Requests rejected by a rate limit."),
+            &["policy"],
+        )
+        .unwrap();
+        let db_connections = IntGauge::new("db_connections", "Open database connections.").unwrap();
+        let db_connections_idle =
+            IntGauge::new("db_connections_idle", "Idle database connections.").unwrap();
+        let storage_bytes =
+            IntGauge::new("storage_bytes", "Total size of all stored files.").unwrap();
+        let stored_files = IntGauge::new("stored_files", "Amount of stored files.").unwrap();
+        let ssh_tickets = IntGauge::new(
+            "ssh_tickets",
+            "Outstanding tickets of the SSH authentication flow.",
+        )
+        .unwrap();
+
+        registry.register(Box::new(http_requests.clone())).unwrap();
+        registry
+            .register(Box::new(http_request_duration.clone()))
+            .unwrap();
+        registry.register(Box::new(transfer_bytes.clone())).unwrap();
+        registry
+            .register(Box::new(transfer_duration.clone()))
+            .unwrap();
+        registry
+            .register(Box::new(active_transfers.clone()))
+            .unwrap();
+        registry.register(Box::new(auth_failures.clone())).unwrap();
+        registry.register(Box::new(rate_limited.clone())).unwrap();
+        registry.register(Box::new(db_connections.clone())).unwrap();
+        registry
+            .register(Box::new(db_connections_idle.clone()))
+            .unwrap();
+        registry.register(Box::new(storage_bytes.clone())).unwrap();
+        registry.register(Box::new(stored_files.clone())).unwrap();
+        registry.register(Box::new(ssh_tickets.clone())).unwrap();
+
+        Self {
+            registry,
+            http_requests,
+            http_request_duration,
+            transfer_bytes,
+            transfer_duration,
+            active_transfers,
+            auth_failures,
+            rate_limited,
+            db_connections,
+            db_connections_idle,
+            storage_bytes,
+            stored_files,
+            ssh_tickets,
+        }
+    }
+
+    /// Records a failed authentication attempt, such as `password` or `ssh`.
+    pub fn auth_failure(&self, method: &str) {
+        self.auth_failures.with_label_values(&[method]).inc();
+    }
+
+    /// Records a request that was rejected by the rate limit of a policy, such as `auth`.
+    pub fn rate_limited(&self, policy: &str) {
+        self.rate_limited.with_label_values(&[policy]).inc();
+    }
+
+    /// Records file content that was received or sent.
+    pub fn transferred(&self, direction: Direction, bytes: u64) {
+        self.transfer_bytes
+            .with_label_values(&[direction.as_str()])
+            .inc_by(bytes);
+    }
+
+    /// Starts tracking a transfer. The transfer is counted as active until the returned guard is
+    /// dropped, at which point its duration is recorded.
+    pub fn start_transfer(&self, direction: Direction) -> TransferGuard {
+        self.active_transfers
+            .with_label_values(&[direction.as_str()])
+            .inc();
+        TransferGuard {
+            direction,
+            start: Instant::now(),
+        }
+    }
+
+    /// Encodes all metrics in the Prometheus text format.
+    pub fn encode(&self) -> String {
+        let mut buf = Vec::new();
+        TextEncoder::new()
+            .encode(&self.registry.gather(), &mut buf)
+            .expect("writing to a Vec can't fail");
+        String::from_utf8(buf).expect("the text format is valid UTF-8")
+    }
+}
+
+/// Tracks an active transfer, see [Metrics::start_transfer].
+pub struct TransferGuard {
+    direction: Direction,
+    start: Instant,
+}
+
+impl Drop for TransferGuard {
+    fn drop(&mut self) {
+        let direction = self.direction.as_str();
+        METRICS
+            .active_transfers
+            .with_label_values(&[direction])
+            .dec();
+        METRICS
+            .transfer_duration
+            .with_label_values(&[direction])
+            .observe(self.start.elapsed().as_secs_f64());
+    }
+}
+
+/// A middleware that records the amount and duration of requests per route.
+///
+/// Must be added using `route_layer`, so the matched route is known.
+pub async fn track_requests(req: Request, next: Next) -> Response {
+    let start = Instant::now();
+    let method = req.method().clone();
+    let route = req
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|v| v.as_str().to_string())
+        .unwrap_or_default();
+
+    let response = next.run(req).await;
+
+    METRICS
+        .http_requests
+        .with_label_values(&[method.as_str(), &route, response.status().as_str()])
+        .inc();
+    METRICS
+        .http_request_duration
+        .with_label_values(&[method.as_str(), &route])
+        .observe(start.elapsed().as_secs_f64());
+    response
+}
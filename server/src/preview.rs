@@ -0,0 +1,210 @@
+use std::{io::Cursor, path::Path, pin::pin, sync::LazyLock};
+
+use anyhow::Context;
+use image::{
+    io::{Limits, Reader as ImageReader},
+    ImageFormat,
+};
+use syntect::{
+    highlighting::ThemeSet,
+    html::highlighted_html_for_string,
+    parsing::{SyntaxReference, SyntaxSet},
+};
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+/// Images larger than this are not decoded to generate a thumbnail.
+const MAX_IMAGE_SIZE: u64 = 32 * 1024 * 1024;
+/// The maximum width and height of a thumbnail in pixels.
+const THUMBNAIL_SIZE: u32 = 256;
+/// The maximum amount of bytes read from a text file to create an excerpt.
+const MAX_EXCERPT_SIZE: u64 = 64 * 1024;
+/// The maximum amount of lines shown in a text excerpt.
+const MAX_EXCERPT_LINES: usize = 200;
+/// The theme used for syntax highlighting.
+const HIGHLIGHT_THEME: &str = "InspiredGitHub";
+
+// Loading the syntax definitions is relatively expensive, so this is only done once.
+static SYNTAXES: LazyLock<SyntaxSet> = LazyLock::new(SyntaxSet::load_defaults_newlines);
+static THEMES: LazyLock<ThemeSet> = LazyLock::new(ThemeSet::load_defaults);
+
+/// The kind of preview that can be generated for a file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PreviewKind {
+    /// A small PNG image that fits in a [THUMBNAIL_SIZE] by [THUMBNAIL_SIZE] square.
+    Thumbnail,
+    /// A syntax highlighted HTML excerpt of the start of a text file.
+    Html,
+}
+
+impl PreviewKind {
+    /// All kinds of previews.
+    pub const ALL: [PreviewKind; 2] = [PreviewKind::Thumbnail, PreviewKind::Html];
+
+    /// Determines what kind of preview can be generated for a file based on its name. Returns
+    /// `None` if previews are not supported for the file.
+    pub fn for_file_name(file_name: &str) -> Option<Self> {
+        if ImageFormat::from_path(file_name).is_ok_and(is_supported_image) {
+            return Some(Self::Thumbnail);
+        }
+        if find_syntax(file_name).is_some() {
+            return Some(Self::Html);
+        }
+        None
+    }
+
+    /// The extension used when storing the preview.
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Thumbnail => "png",
+            Self::Html => "html",
+        }
+    }
+
+    /// The value of the `Content-Type` header when serving the preview.
+    pub fn content_type(self) -> &'static str {
+        match self {
+            Self::Thumbnail => "image/png",
+            Self::Html => "text/html; charset=utf-8",
+        }
+    }
+}
+
+/// Generates a preview of a file from its content. Returns `None` if the content is not suitable
+/// for the requested preview, for example when an image is too large or a text file turns out to
+/// be binary.
+pub async fn generate(
+    kind: PreviewKind,
+    file_name: &str,
+    content: impl AsyncRead,
+) -> anyhow::Result<Option<Vec<u8>>> {
+    match kind {
+        PreviewKind::Thumbnail => {
+            let Some(data) = read_at_most(content, MAX_IMAGE_SIZE, true).await? else {
+                return Ok(None);
+            };
+            // Decoding and resizing images is CPU bound, so it should not block the runtime.
+            tokio::task::spawn_blocking(move || thumbnail(&data))
+                .await
+                .context("could not join thumbnail thread")?
+        }
+        PreviewKind::Html => {
+            let Some(data) = read_at_most(content, MAX_EXCERPT_SIZE, false).await? else {
+                return Ok(None);
+            };
+            let file_name = file_name.to_string();
+            tokio::task::spawn_blocking(move || excerpt(&file_name, &data))
+                .await
+                .context("could not join highlighter thread")?
+        }
+    }
+}
+
+/// Reads up to `limit` bytes. If `exact` is set, `None` is returned when the content is longer than
+/// the limit.
+async fn read_at_most(
+    content: impl AsyncRead,
+    limit: u64,
+    exact: bool,
+) -> anyhow::Result<Option<Vec<u8>>> {
+    let mut data = Vec::new();
+    pin!(content)
+        .take(limit + 1)
+        .read_to_end(&mut data)
+        .await
+        .context("could not read file content")?;
+
+    if data.len() as u64 > limit {
+        if exact {
+            return Ok(None);
+        }
+        data.truncate(limit as usize);
+    }
+    Ok(Some(data))
+}
+
+fn thumbnail(data: &[u8]) -> anyhow::Result<Option<Vec<u8>>> {
+    let mut reader = ImageReader::new(Cursor::new(data))
+        .with_guessed_format()
+        .context("could not read image")?;
+    if !reader.format().is_some_and(is_supported_image) {
+        return Ok(None);
+    }
+
+    // Protect against images that are small on disk but huge when decoded.
+    let mut limits = Limits::default();
+    limits.max_image_width = Some(16 * 1024);
+    limits.max_image_height = Some(16 * 1024);
+    limits.max_alloc = Some(256 * 1024 * 1024);
+    reader.limits(limits);
+
+    let Ok(image) = reader.decode() else {
+        return Ok(None);
+    };
+
+    let mut out = Vec::new();
+    image
+        .thumbnail(THUMBNAIL_SIZE, THUMBNAIL_SIZE)
+        .write_to(&mut Cursor::new(&mut out), ImageFormat::Png)
+        .context("could not encode thumbnail")?;
+    Ok(Some(out))
+}
+
+fn excerpt(file_name: &str, data: &[u8]) -> anyhow::Result<Option<Vec<u8>>> {
+    // Null bytes practically never occur in text files.
+    if data.contains(&0) {
+        return Ok(None);
+    }
+    // The excerpt may have cut a multi-byte character in half, so only the valid part is used.
+    let text = match std::str::from_utf8(data) {
+        Ok(v) => v,
+        Err(err) if err.error_len().is_none() => {
+            std::str::from_utf8(&data[..err.valid_up_to()]).expect("prefix is valid UTF-8")
+        }
+        Err(_) => return Ok(None),
+    };
+
+    let mut excerpt = String::new();
+    for line in text.split_inclusive('\n').take(MAX_EXCERPT_LINES) {
+        excerpt.push_str(line);
+    }
+
+    let syntax = find_syntax(file_name).unwrap_or_else(|| SYNTAXES.find_syntax_plain_text());
+    let html =
+        highlighted_html_for_string(&excerpt, &SYNTAXES, syntax, &THEMES.themes[HIGHLIGHT_THEME])
+            .context("could not highlight text")?;
+    Ok(Some(html.into_bytes()))
+}
+
+/// Finds the file extension used for a language. The language may either be its name (e.g. `rust`)
+/// or one of its extensions (e.g. `rs`).
+pub fn extension_for_language(language: &str) -> Option<&'static str> {
+    SYNTAXES
+        .find_syntax_by_token(language)
+        .and_then(|syntax| syntax.file_extensions.first())
+        .map(String::as_str)
+}
+
+/// Finds the syntax definition for a file, either by its full name (e.g. `Makefile`) or by its
+/// extension.
+fn find_syntax(file_name: &str) -> Option<&'static SyntaxReference> {
+    let extension = Path::new(file_name).extension().and_then(|v| v.to_str());
+    SYNTAXES
+        .find_syntax_by_extension(file_name)
+        .or_else(|| extension.and_then(|v| SYNTAXES.find_syntax_by_extension(v)))
+        .or_else(|| {
+            // Plain text files are not always stored with a `.txt` extension.
+            matches!(extension, Some("log" | "text" | "conf" | "cfg" | "env"))
+                .then(|| SYNTAXES.find_syntax_plain_text())
+        })
+}
+
+fn is_supported_image(format: ImageFormat) -> bool {
+    matches!(
+        format,
+        ImageFormat::Bmp
+            | ImageFormat::Gif
+            | ImageFormat::Jpeg
+            | ImageFormat::Png
+            | ImageFormat::WebP
+    )
+}
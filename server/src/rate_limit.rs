@@ -0,0 +1,223 @@
+use std::{
+    collections::HashMap,
+    hash::{BuildHasher, RandomState},
+    net::IpAddr,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use axum::response::{IntoResponse, Response};
+use http::{header, StatusCode};
+use tokio::{sync::Mutex, time::sleep};
+use tracing::debug;
+
+use crate::{
+    config::{ConfigHandle, RateLimit},
+    metrics::METRICS,
+};
+
+/// The groups of endpoints that are limited separately.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Policy {
+    /// Logging in, using a password or an SSH key, and the first-run setup.
+    Auth,
+    /// Creating an account.
+    Registration,
+    /// Uploading files, pastes and new versions of files.
+    Uploads,
+    /// Downloading the content of files.
+    Downloads,
+}
+
+impl Policy {
+    /// The name of the policy, as used in the configuration and metrics.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Policy::Auth => "auth",
+            Policy::Registration => "registration",
+            Policy::Uploads => "uploads",
+            Policy::Downloads => "downloads",
+        }
+    }
+}
+
+/// Who a bucket limits. Users are identified by a hash of their name, as the name may be chosen
+/// by the client and be arbitrarily long.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Key {
+    Ip(IpAddr),
+    User(u64),
+}
+
+impl Key {
+    /// The key for a client address. IPv6 clients usually control a whole /64 network, so they
+    /// share one bucket.
+    fn ip(ip: IpAddr) -> Self {
+        match ip.to_canonical() {
+            IpAddr::V6(v6) => {
+                let network = u128::from(v6) & !(u128::MAX >> 64);
+                Key::Ip(IpAddr::V6(network.into()))
+            }
+            ip => Key::Ip(ip),
+        }
+    }
+}
+
+/// A token bucket. Every request takes a token, and tokens are added back at a steady rate up to
+/// the size of the burst.
+struct Bucket {
+    tokens: f64,
+    updated_at: Instant,
+}
+
+impl Bucket {
+    /// A bucket that hasn't been used yet, which is full.
+    fn new(limit: &RateLimit, now: Instant) -> Self {
+        Self {
+            tokens: limit.burst as f64,
+            updated_at: now,
+        }
+    }
+
+    /// Adds the tokens that became available since the last update.
+    fn refill(&mut self, limit: &RateLimit, now: Instant) {
+        let elapsed = now.duration_since(self.updated_at).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * limit.per_second()).min(limit.burst as f64);
+        self.updated_at = now;
+    }
+
+    /// The time until a token is available.
+    fn wait_time(&self, limit: &RateLimit) -> Duration {
+        if self.tokens >= 1.0 {
+            return Duration::ZERO;
+        }
+        Duration::from_secs_f64((1.0 - self.tokens) / limit.per_second())
+    }
+}
+
+/// Limits how often clients and users can make requests, using a token bucket per client address
+/// and per user for each [Policy]. The limits are read from the configuration on every request,
+/// so they can be changed by reloading it.
+pub struct RateLimiter {
+    config: Arc<ConfigHandle>,
+    buckets: Mutex<HashMap<(Policy, Key), Bucket>>,
+    /// Hashes user names. Its keys are random, so clients can't choose names that share a bucket.
+    user_hasher: RandomState,
+}
+
+impl RateLimiter {
+    /// Initialize a new [RateLimiter] along with a cleanup task that removes buckets that have
+    /// been refilled, so clients that stopped making requests are forgotten.
+    pub fn init(config: Arc<ConfigHandle>) -> Arc<Self> {
+        let limiter = Arc::new(Self {
+            config,
+            buckets: Default::default(),
+            user_hasher: RandomState::new(),
+        });
+
+        let weak_limiter = Arc::downgrade(&limiter);
+        tokio::spawn(async move {
+            loop {
+                sleep(Duration::from_secs(60)).await;
+                let Some(limiter) = weak_limiter.upgrade() else {
+                    return;
+                };
+
+                let config = limiter.config.load();
+                let now = Instant::now();
+                limiter
+                    .buckets
+                    .lock()
+                    .await
+                    .retain(|(policy, key), bucket| {
+                        let policy = config.rate_limits.policy(*policy);
+                        let limit = match key {
+                            Key::Ip(_) => &policy.per_ip,
+                            Key::User(_) => &policy.per_user,
+                        };
+                        if !config.rate_limits.enabled || !limit.is_enabled() {
+                            return false;
+                        }
+                        // A full bucket is the same as a new one.
+                        bucket.refill(limit, now);
+                        bucket.tokens < limit.burst as f64
+                    });
+            }
+        });
+
+        limiter
+    }
+
+    /// Takes a token from the buckets of the client address and, if given, the user. No token is
+    /// taken if either bucket is empty, in which case the request has to be rejected.
+    pub async fn check(
+        &self,
+        policy: Policy,
+        ip: IpAddr,
+        user: Option<&str>,
+    ) -> Result<(), RateLimited> {
+        let config = self.config.load();
+        if !config.rate_limits.enabled {
+            return Ok(());
+        }
+        let limits = config.rate_limits.policy(policy);
+
+        // The address is checked first, so a client that is limited can't make the limiter keep
+        // track of new users.
+        let mut keys = Vec::with_capacity(2);
+        if limits.per_ip.is_enabled() {
+            keys.push((Key::ip(ip), &limits.per_ip));
+        }
+        if let Some(user) = user.filter(|_| limits.per_user.is_enabled()) {
+            keys.push((Key::User(self.user_hasher.hash_one(user)), &limits.per_user));
+        }
+        if keys.is_empty() {
+            return Ok(());
+        }
+
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().await;
+        for (key, limit) in &keys {
+            // Buckets are only created once a request is allowed, a missing one is full.
+            let wait_time = match buckets.get_mut(&(policy, *key)) {
+                Some(bucket) => {
+                    bucket.refill(limit, now);
+                    bucket.wait_time(limit)
+                }
+                None => Bucket::new(limit, now).wait_time(limit),
+            };
+            if !wait_time.is_zero() {
+                drop(buckets);
+                debug!(policy = policy.as_str(), %ip, user, "Rate limited request");
+                METRICS.rate_limited(policy.as_str());
+                return Err(RateLimited(wait_time));
+            }
+        }
+
+        for (key, limit) in keys {
+            buckets
+                .entry((policy, key))
+                .or_insert_with(|| Bucket::new(limit, now))
+                .tokens -= 1.0;
+        }
+        Ok(())
+    }
+}
+
+/// The response to a request that exceeded a rate limit, containing the time until the request
+/// can be made again.
+#[derive(Debug)]
+pub struct RateLimited(Duration);
+
+impl IntoResponse for RateLimited {
+    fn into_response(self) -> Response {
+        // Rounded up, so retrying after this time succeeds.
+        let retry_after = self.0.as_secs() + u64::from(self.0.subsec_nanos() > 0);
+        (
+            StatusCode::TOO_MANY_REQUESTS,
+            [(header::RETRY_AFTER, retry_after.to_string())],
+            "Too many requests, try again later",
+        )
+            .into_response()
+    }
+}
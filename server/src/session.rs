@@ -7,7 +7,7 @@ use anyhow::Context;
 use chrono::{serde::ts_milliseconds, DateTime, Duration, Utc};
 use hex::{FromHex, FromHexError};
 use serde::{de::Visitor, Deserialize, Serialize};
-use sqlx::{Postgres, Transaction};
+use sqlx::{PgPool, Postgres, Transaction};
 use time::OffsetDateTime;
 use tower_cookies::{Cookie, Cookies};
 use uuid::Uuid;
@@ -92,13 +92,22 @@ pub async fn create_session(
     })
 }
 
-/// Store a session in the cookies when sending a response.
-pub fn store_session(cookies: &Cookies, session: &SessionInfo) -> anyhow::Result<()> {
+/// Removes sessions that have expired, returning the amount of sessions removed.
+pub async fn remove_expired(db: &PgPool) -> sqlx::Result<u64> {
+    let result = sqlx::query!("delete from sessions where expires_on <= now()")
+        .execute(db)
+        .await?;
+    Ok(result.rows_affected())
+}
+
+/// Store a session in the cookies when sending a response. The session token is only sent over
+/// HTTPS if `secure` is true.
+pub fn store_session(cookies: &Cookies, session: &SessionInfo, secure: bool) -> anyhow::Result<()> {
     let cookie_expire = OffsetDateTime::from_unix_timestamp(session.valid_until.timestamp())
         .context("could not convert date")?;
 
     let mut cookie = Cookie::new("session-token", session.token.to_string());
-    cookie.set_secure(Some(true));
+    cookie.set_secure(Some(secure));
     cookie.set_http_only(Some(true));
     cookie.set_expires(cookie_expire);
     cookie.set_path("/");
@@ -0,0 +1,100 @@
+use std::{env, sync::Arc};
+
+use anyhow::{anyhow, Context};
+use sqlx::PgPool;
+use tokio::sync::{Mutex, MutexGuard};
+use tracing::{info, warn};
+
+use crate::{
+    audit::{AuditAction, AuditEvent},
+    user::{admin_exists, create_user, hash_password},
+};
+
+/// The first-run setup, which creates the first administrator of a new instance.
+#[derive(Default)]
+pub struct SetupState {
+    /// The token that unlocks creating the first administrator through the API. Unset if an
+    /// administrator exists.
+    token: Mutex<Option<String>>,
+}
+
+impl SetupState {
+    /// Makes sure the instance can be managed by an administrator.
+    ///
+    /// If no administrator exists, one is created from the `BEACON_INITIAL_ADMIN_USERNAME` and
+    /// `BEACON_INITIAL_ADMIN_PASSWORD` environment variables. If those are unset, a setup token is
+    /// generated and logged, which can be used once to create the first administrator.
+    pub async fn init(db: &PgPool) -> anyhow::Result<Arc<Self>> {
+        if admin_exists(db).await? {
+            return Ok(Default::default());
+        }
+
+        let username = env::var("BEACON_INITIAL_ADMIN_USERNAME").ok();
+        let password = env::var("BEACON_INITIAL_ADMIN_PASSWORD").ok();
+        match (username, password) {
+            (Some(username), Some(password)) => {
+                if username.is_empty() || password.is_empty() {
+                    return Err(anyhow!(
+                        "`BEACON_INITIAL_ADMIN_USERNAME` and `BEACON_INITIAL_ADMIN_PASSWORD` can not be empty"
+                    ));
+                }
+
+                let password_hash = hash_password(password).await?;
+                let user_id = create_user(db, &username, &password_hash, true)
+                    .await?
+                    .with_context(|| {
+                        format!("could not create initial admin, the username `{username}` is taken")
+                    })?;
+
+                info!(?user_id, username, "Created the initial administrator.");
+                AuditEvent::new(AuditAction::AdminChanged)
+                    .by_operator()
+                    .target("user", user_id)
+                    .details(serde_json::json!({ "is_admin": true }))
+                    .record(db)
+                    .await;
+                Ok(Default::default())
+            }
+            (None, None) => {
+                let token = hex::encode(rand::random::<[u8; 32]>());
+                warn!(
+                    "No administrator exists yet. Create one using the setup token `{token}` at \
+                    `/setup`, or by running `beacon-server user create --admin`."
+                );
+                Ok(Arc::new(Self {
+                    token: Mutex::new(Some(token)),
+                }))
+            }
+            _ => Err(anyhow!(
+                "both `BEACON_INITIAL_ADMIN_USERNAME` and `BEACON_INITIAL_ADMIN_PASSWORD` must be set"
+            )),
+        }
+    }
+
+    /// Returns true if the first administrator still has to be created.
+    pub async fn is_pending(&self) -> bool {
+        self.token.lock().await.is_some()
+    }
+
+    /// Checks the setup token, returning a guard that completes the setup when
+    /// [SetupGuard::complete] is called. Returns `None` if the token is wrong or setup has been
+    /// completed already.
+    pub async fn start(&self, token: &str) -> Option<SetupGuard<'_>> {
+        let guard = self.token.lock().await;
+        if guard.as_deref() != Some(token) {
+            return None;
+        }
+        Some(SetupGuard(guard))
+    }
+}
+
+/// Holds the setup token while the first administrator is being created, so this can only happen
+/// once.
+pub struct SetupGuard<'a>(MutexGuard<'a, Option<String>>);
+
+impl SetupGuard<'_> {
+    /// Invalidates the setup token.
+    pub fn complete(mut self) {
+        self.0.take();
+    }
+}
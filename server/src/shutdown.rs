@@ -0,0 +1,72 @@
+use axum::{
+    async_trait,
+    extract::{FromRef, FromRequestParts},
+    http::{request::Parts, StatusCode},
+};
+use tokio::signal::unix::{signal, SignalKind};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error};
+
+/// Tracks whether the server is shutting down.
+#[derive(Clone, Default)]
+pub struct Shutdown(CancellationToken);
+
+impl Shutdown {
+    /// Starts shutting down. No new connections are accepted after this and in-flight requests
+    /// are given time to finish.
+    pub fn start(&self) {
+        self.0.cancel();
+    }
+
+    /// Returns true if the server is shutting down.
+    pub fn is_draining(&self) -> bool {
+        self.0.is_cancelled()
+    }
+
+    /// Waits until the server starts shutting down.
+    pub async fn started(&self) {
+        self.0.cancelled().await
+    }
+}
+
+/// Waits until the process receives SIGTERM or SIGINT.
+pub async fn signal_received() {
+    let (mut sigterm, mut sigint) = match (
+        signal(SignalKind::terminate()),
+        signal(SignalKind::interrupt()),
+    ) {
+        (Ok(sigterm), Ok(sigint)) => (sigterm, sigint),
+        (Err(err), _) | (_, Err(err)) => {
+            error!("Could not listen for shutdown signals: {err}");
+            return std::future::pending().await;
+        }
+    };
+
+    tokio::select! {
+        _ = sigterm.recv() => {}
+        _ = sigint.recv() => {}
+    }
+}
+
+/// A HTTP extractor that rejects requests with SERVICE_UNAVAILABLE while the server is shutting
+/// down. Used for uploads, as they may take longer than the server waits for requests to finish.
+pub struct AcceptingUploads;
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for AcceptingUploads
+where
+    Shutdown: FromRef<S>,
+{
+    type Rejection = (StatusCode, &'static str);
+
+    async fn from_request_parts(_parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        if Shutdown::from_ref(state).is_draining() {
+            debug!("Rejected upload while shutting down");
+            return Err((
+                StatusCode::SERVICE_UNAVAILABLE,
+                "The server is shutting down, try again later",
+            ));
+        }
+        Ok(AcceptingUploads)
+    }
+}
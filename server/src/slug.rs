@@ -0,0 +1,168 @@
+use http::StatusCode;
+use rand::{seq::SliceRandom, thread_rng};
+use sqlx::{Postgres, Transaction};
+use thiserror::Error;
+use uuid::Uuid;
+
+use crate::file::FileId;
+
+/// The minimum length of a custom slug.
+pub const MIN_SLUG_LEN: usize = 3;
+/// The maximum length of a custom slug.
+pub const MAX_SLUG_LEN: usize = 64;
+/// The length of randomly generated slugs.
+const RANDOM_SLUG_LEN: usize = 7;
+/// Characters used for random slugs. Characters that are easily confused with each other, such as
+/// `l` and `1`, are left out.
+const RANDOM_SLUG_CHARS: &[u8] = b"abcdefghijkmnpqrstuvwxyz23456789";
+
+/// Slugs that may not be used, as they could be confused with pages of the site.
+const RESERVED_SLUGS: &[&str] = &[
+    "admin", "api", "files", "login", "logout", "metrics", "panel", "register", "s", "setup",
+    "static",
+];
+
+/// The reasons a slug could not be created.
+#[derive(Debug, Error)]
+pub enum SlugError {
+    #[error("slug must be between {MIN_SLUG_LEN} and {MAX_SLUG_LEN} characters long")]
+    InvalidLength,
+    #[error("slug may only contain letters, digits, `-` and `_`")]
+    InvalidCharacter,
+    #[error("slug `{0}` is reserved")]
+    Reserved(String),
+    #[error("slug `{0}` is already in use")]
+    Taken(String),
+    #[error("you can not create more than {0} slugs")]
+    LimitReached(u32),
+    #[error(transparent)]
+    Database(#[from] sqlx::Error),
+}
+
+impl SlugError {
+    /// The status code to respond with when this error is caused by the user.
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            SlugError::Database(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            SlugError::Taken(_) => StatusCode::CONFLICT,
+            SlugError::LimitReached(_) => StatusCode::FORBIDDEN,
+            SlugError::InvalidLength | SlugError::InvalidCharacter | SlugError::Reserved(_) => {
+                StatusCode::BAD_REQUEST
+            }
+        }
+    }
+}
+
+/// Validates a user-requested slug and returns its normalized (lowercase) form.
+pub fn normalize_slug(slug: &str) -> Result<String, SlugError> {
+    let slug = slug.to_ascii_lowercase();
+
+    if !(MIN_SLUG_LEN..=MAX_SLUG_LEN).contains(&slug.len()) {
+        return Err(SlugError::InvalidLength);
+    }
+    if !slug
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(SlugError::InvalidCharacter);
+    }
+    if RESERVED_SLUGS.contains(&slug.as_str()) {
+        return Err(SlugError::Reserved(slug));
+    }
+
+    Ok(slug)
+}
+
+/// Checks whether a user can create a slug, without creating it. If `slug` is set, this also checks
+/// whether that slug is still available.
+pub async fn check_slug(
+    tx: &mut Transaction<'_, Postgres>,
+    user_id: Uuid,
+    slug: Option<&str>,
+    max_per_user: u32,
+) -> Result<(), SlugError> {
+    let amount = sqlx::query!(
+        r#"select count(*) as "amount!" from slugs where created_by=$1"#,
+        user_id,
+    )
+    .fetch_one(&mut **tx)
+    .await?
+    .amount;
+    if amount >= max_per_user as i64 {
+        return Err(SlugError::LimitReached(max_per_user));
+    }
+
+    if let Some(slug) = slug {
+        if slug_exists(tx, slug).await? {
+            return Err(SlugError::Taken(slug.to_string()));
+        }
+    }
+    Ok(())
+}
+
+/// Creates a slug for a file. A random slug is generated if no slug was requested.
+pub async fn create_slug(
+    tx: &mut Transaction<'_, Postgres>,
+    file_id: FileId,
+    user_id: Uuid,
+    requested: Option<&str>,
+    max_per_user: u32,
+) -> Result<String, SlugError> {
+    let requested = requested.map(normalize_slug).transpose()?;
+    check_slug(tx, user_id, requested.as_deref(), max_per_user).await?;
+
+    // A slug may be taken concurrently after it was checked, in which case nothing is inserted
+    // and a random slug is generated again.
+    loop {
+        let slug = match &requested {
+            Some(slug) => slug.clone(),
+            None => random_slug(),
+        };
+
+        let inserted = sqlx::query_scalar!(
+            r#"
+                insert into slugs (slug, file_id, created_by) values ($1, $2, $3)
+                    on conflict do nothing
+                    returning slug
+            "#,
+            slug,
+            file_id as FileId,
+            user_id,
+        )
+        .fetch_optional(&mut **tx)
+        .await?;
+
+        if let Some(slug) = inserted {
+            return Ok(slug);
+        }
+        if requested.is_some() {
+            return Err(SlugError::Taken(slug));
+        }
+    }
+}
+
+/// The link that redirects to the file a slug belongs to.
+pub fn short_link(external_url: &str, slug: &str) -> String {
+    format!("{external_url}/s/{slug}")
+}
+
+async fn slug_exists(tx: &mut Transaction<'_, Postgres>, slug: &str) -> sqlx::Result<bool> {
+    Ok(sqlx::query!(
+        r#"select exists(select * from slugs where slug=$1) as "exists!""#,
+        slug,
+    )
+    .fetch_one(&mut **tx)
+    .await?
+    .exists)
+}
+
+fn random_slug() -> String {
+    let mut rng = thread_rng();
+    (0..RANDOM_SLUG_LEN)
+        .map(|_| {
+            *RANDOM_SLUG_CHARS
+                .choose(&mut rng)
+                .expect("charset is not empty") as char
+        })
+        .collect()
+}
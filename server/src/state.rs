@@ -5,7 +5,10 @@ use sqlx::PgPool;
 
 use crate::{
     auth::{ssh::SSHAuthState, UserAuthFailures},
-    config::Config,
+    config::{Config, ConfigHandle},
+    rate_limit::RateLimiter,
+    setup::SetupState,
+    shutdown::Shutdown,
 };
 
 use super::file::FileDb;
@@ -16,6 +19,17 @@ pub struct AppState {
     pub database: PgPool,
     pub file_store: Arc<FileDb>,
     pub ssh_auth: Arc<SSHAuthState>,
-    pub config: Arc<Config>,
+    pub config: Arc<ConfigHandle>,
     pub auth_failures: Arc<UserAuthFailures>,
+    pub rate_limiter: Arc<RateLimiter>,
+    pub shutdown: Shutdown,
+    pub setup: Arc<SetupState>,
+}
+
+/// Handlers extract the configuration as it is when the request is handled, which may change when
+/// it is reloaded.
+impl FromRef<AppState> for Arc<Config> {
+    fn from_ref(state: &AppState) -> Self {
+        state.config.load()
+    }
 }
@@ -0,0 +1,372 @@
+use std::{
+    io,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use axum::body::Bytes;
+use chrono::{
+    serde::{ts_milliseconds, ts_milliseconds_option},
+    DateTime, Utc,
+};
+use futures::Stream;
+use num_traits::ToPrimitive;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use tracing::error;
+use uuid::Uuid;
+
+use crate::{
+    file::FileId,
+    metrics::{Direction, TransferGuard, METRICS},
+};
+
+/// The amount of downloads included in the history of a file.
+const RECENT_DOWNLOADS: i64 = 50;
+/// The amount of files included in the most downloaded files of the instance.
+const TOP_FILES: i64 = 10;
+
+/// Wraps the body of a download, recording how much of the file was sent once the response has
+/// finished or the client has disconnected. Nothing is recorded if the body is never sent, such as
+/// for `HEAD` requests.
+pub struct TrackedDownload<S> {
+    inner: S,
+    db: PgPool,
+    file_id: FileId,
+    user_id: Option<Uuid>,
+    /// The size of the content that is being sent.
+    size: u64,
+    bytes_served: u64,
+    /// True once sending the body has started.
+    polled: bool,
+    _transfer: TransferGuard,
+}
+
+impl<S> TrackedDownload<S> {
+    pub fn new(inner: S, db: PgPool, file_id: FileId, user_id: Option<Uuid>, size: u64) -> Self {
+        Self {
+            inner,
+            db,
+            file_id,
+            user_id,
+            size,
+            bytes_served: 0,
+            polled: false,
+            _transfer: METRICS.start_transfer(Direction::Download),
+        }
+    }
+}
+
+impl<S: Stream<Item = io::Result<Bytes>> + Unpin> Stream for TrackedDownload<S> {
+    type Item = io::Result<Bytes>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.polled = true;
+        let poll = Pin::new(&mut self.inner).poll_next(cx);
+        if let Poll::Ready(Some(Ok(chunk))) = &poll {
+            self.bytes_served += chunk.len() as u64;
+            METRICS.transferred(Direction::Download, chunk.len() as u64);
+        }
+        poll
+    }
+}
+
+impl<S> Drop for TrackedDownload<S> {
+    fn drop(&mut self) {
+        if !self.polled {
+            return;
+        }
+        let db = self.db.clone();
+        let file_id = self.file_id;
+        let user_id = self.user_id;
+        let bytes_served = self.bytes_served;
+        let completed = bytes_served >= self.size;
+
+        tokio::spawn(async move {
+            let result = sqlx::query!(
+                r#"
+                    insert into file_downloads (file_id, user_id, bytes_served, completed)
+                        values ($1, $2, $3, $4)
+                "#,
+                file_id as FileId,
+                user_id,
+                bytes_served.min(i64::MAX as u64) as i64,
+                completed,
+            )
+            .execute(&db)
+            .await;
+
+            // The file may have been removed while it was being downloaded.
+            match result {
+                Ok(_) => {}
+                Err(sqlx::Error::Database(err)) if err.is_foreign_key_violation() => {}
+                Err(err) => error!(?file_id, "Could not record download: {err:?}"),
+            }
+        });
+    }
+}
+
+/// A single download of a file.
+#[derive(Debug, Serialize)]
+pub struct Download {
+    #[serde(with = "ts_milliseconds")]
+    pub downloaded_at: DateTime<Utc>,
+    /// The user that downloaded the file, if they were logged in.
+    pub user_id: Option<Uuid>,
+    pub bytes_served: u64,
+    /// True if the whole file was sent.
+    pub completed: bool,
+}
+
+/// Download statistics of a single file.
+#[derive(Debug, Serialize)]
+pub struct FileStats {
+    pub download_count: u64,
+    /// The amount of downloads in which the whole file was sent.
+    pub completed_downloads: u64,
+    pub bytes_served: u64,
+    #[serde(with = "ts_milliseconds_option")]
+    pub last_download: Option<DateTime<Utc>>,
+    /// The most recent downloads, newest first.
+    pub recent_downloads: Vec<Download>,
+}
+
+/// Gets the download statistics of a file.
+pub async fn file_stats(db: &PgPool, file_id: FileId) -> sqlx::Result<FileStats> {
+    let totals = sqlx::query!(
+        r#"
+            select
+                count(*) as "download_count!",
+                count(*) filter (where completed) as "completed_downloads!",
+                sum(bytes_served) as "bytes_served",
+                max(downloaded_at) as "last_download"
+            from file_downloads
+            where file_id=$1
+        "#,
+        file_id as FileId,
+    )
+    .fetch_one(db)
+    .await?;
+
+    let recent_downloads = sqlx::query!(
+        r#"
+            select downloaded_at, user_id, bytes_served, completed
+                from file_downloads
+                where file_id=$1
+                order by downloaded_at desc
+                limit $2
+        "#,
+        file_id as FileId,
+        RECENT_DOWNLOADS,
+    )
+    .fetch_all(db)
+    .await?
+    .into_iter()
+    .map(|row| Download {
+        downloaded_at: row.downloaded_at,
+        user_id: row.user_id,
+        bytes_served: row.bytes_served as u64,
+        completed: row.completed,
+    })
+    .collect();
+
+    Ok(FileStats {
+        download_count: totals.download_count as u64,
+        completed_downloads: totals.completed_downloads as u64,
+        bytes_served: totals.bytes_served.and_then(|v| v.to_u64()).unwrap_or(0),
+        last_download: totals.last_download,
+        recent_downloads,
+    })
+}
+
+/// The size of the time buckets downloads are grouped in.
+#[derive(Debug, Default, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Bucket {
+    Hour,
+    #[default]
+    Day,
+    Week,
+    Month,
+}
+
+impl Bucket {
+    /// The name of the bucket as used by `date_trunc`.
+    fn as_str(self) -> &'static str {
+        match self {
+            Bucket::Hour => "hour",
+            Bucket::Day => "day",
+            Bucket::Week => "week",
+            Bucket::Month => "month",
+        }
+    }
+}
+
+/// The downloads within a single time bucket.
+#[derive(Debug, Serialize)]
+pub struct DownloadBucket {
+    #[serde(with = "ts_milliseconds")]
+    pub start: DateTime<Utc>,
+    pub downloads: u64,
+    pub bytes_served: u64,
+}
+
+/// Statistics about the files of a user.
+#[derive(Debug, Serialize)]
+pub struct UserStats {
+    pub upload_count: u64,
+    pub total_storage: u64,
+    /// The total amount of downloads of the user's files.
+    pub download_count: u64,
+    pub bytes_served: u64,
+    /// The downloads of the user's files over time, oldest first. Buckets without downloads are
+    /// left out.
+    pub downloads: Vec<DownloadBucket>,
+}
+
+/// Gets statistics about the files of a user, grouping downloads since `since` into buckets.
+pub async fn user_stats(
+    db: &PgPool,
+    user_id: Uuid,
+    bucket: Bucket,
+    since: DateTime<Utc>,
+) -> sqlx::Result<UserStats> {
+    let files = sqlx::query!(
+        r#"
+            select count(*) as "upload_count!", sum(file_size) as "total_storage"
+                from files
+                where uploader_id=$1
+        "#,
+        user_id,
+    )
+    .fetch_one(db)
+    .await?;
+
+    let totals = sqlx::query!(
+        r#"
+            select count(*) as "download_count!", sum(bytes_served) as "bytes_served"
+                from file_downloads
+                    join files on files.file_id=file_downloads.file_id
+                where uploader_id=$1
+        "#,
+        user_id,
+    )
+    .fetch_one(db)
+    .await?;
+
+    let downloads = sqlx::query!(
+        r#"
+            select
+                date_trunc($2, downloaded_at) as "start!",
+                count(*) as "downloads!",
+                sum(bytes_served) as "bytes_served!"
+            from file_downloads
+                join files on files.file_id=file_downloads.file_id
+            where uploader_id=$1 and downloaded_at >= $3
+            group by 1
+            order by 1
+        "#,
+        user_id,
+        bucket.as_str(),
+        since,
+    )
+    .fetch_all(db)
+    .await?
+    .into_iter()
+    .map(|row| DownloadBucket {
+        start: row.start,
+        downloads: row.downloads as u64,
+        bytes_served: row.bytes_served.to_u64().unwrap_or(0),
+    })
+    .collect();
+
+    Ok(UserStats {
+        upload_count: files.upload_count as u64,
+        total_storage: files.total_storage.and_then(|v| v.to_u64()).unwrap_or(0),
+        download_count: totals.download_count as u64,
+        bytes_served: totals.bytes_served.and_then(|v| v.to_u64()).unwrap_or(0),
+        downloads,
+    })
+}
+
+/// A file in the list of most downloaded files.
+#[derive(Debug, Serialize)]
+pub struct TopFile {
+    pub file_id: FileId,
+    pub file_name: String,
+    pub uploader_id: Uuid,
+    pub download_count: u64,
+    pub bytes_served: u64,
+}
+
+/// Statistics about the whole instance.
+#[derive(Debug, Serialize)]
+pub struct InstanceSummary {
+    pub user_count: u64,
+    pub file_count: u64,
+    pub total_storage: u64,
+    pub download_count: u64,
+    pub bytes_served: u64,
+    /// The amount of downloads in the last 24 hours.
+    pub downloads_last_day: u64,
+    /// The most downloaded files, most downloads first.
+    pub top_files: Vec<TopFile>,
+}
+
+/// Gets statistics about the whole instance.
+pub async fn instance_summary(db: &PgPool) -> sqlx::Result<InstanceSummary> {
+    let counts = sqlx::query!(
+        r#"
+            select
+                (select count(*) from users) as "user_count!",
+                (select count(*) from files) as "file_count!",
+                (select sum(file_size) from files) as "total_storage",
+                (select count(*) from file_downloads) as "download_count!",
+                (select sum(bytes_served) from file_downloads) as "bytes_served",
+                (
+                    select count(*) from file_downloads
+                        where downloaded_at > now() - interval '1 day'
+                ) as "downloads_last_day!"
+        "#,
+    )
+    .fetch_one(db)
+    .await?;
+
+    let top_files = sqlx::query!(
+        r#"
+            select
+                files.file_id as "file_id: FileId",
+                file_name,
+                uploader_id,
+                count(*) as "download_count!",
+                sum(bytes_served) as "bytes_served!"
+            from file_downloads
+                join files on files.file_id=file_downloads.file_id
+            group by files.file_id
+            order by 4 desc
+            limit $1
+        "#,
+        TOP_FILES,
+    )
+    .fetch_all(db)
+    .await?
+    .into_iter()
+    .map(|row| TopFile {
+        file_id: row.file_id,
+        file_name: row.file_name,
+        uploader_id: row.uploader_id,
+        download_count: row.download_count as u64,
+        bytes_served: row.bytes_served.to_u64().unwrap_or(0),
+    })
+    .collect();
+
+    Ok(InstanceSummary {
+        user_count: counts.user_count as u64,
+        file_count: counts.file_count as u64,
+        total_storage: counts.total_storage.and_then(|v| v.to_u64()).unwrap_or(0),
+        download_count: counts.download_count as u64,
+        bytes_served: counts.bytes_served.and_then(|v| v.to_u64()).unwrap_or(0),
+        downloads_last_day: counts.downloads_last_day as u64,
+        top_files,
+    })
+}
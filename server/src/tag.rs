@@ -0,0 +1,117 @@
+use http::StatusCode;
+use sqlx::{Postgres, Transaction};
+use thiserror::Error;
+
+use crate::file::FileId;
+
+/// The maximum length of a tag in bytes.
+pub const MAX_TAG_LEN: usize = 64;
+/// The maximum amount of tags a single file can have.
+pub const MAX_TAGS_PER_FILE: usize = 32;
+
+/// The reasons a tag may be rejected.
+#[derive(Debug, Error)]
+pub enum TagError {
+    #[error("tag must not be empty")]
+    Empty,
+    #[error("tag must be at most {MAX_TAG_LEN} bytes long")]
+    TooLong,
+    #[error("tag must not contain commas or control characters")]
+    InvalidCharacter,
+    #[error("a file can not have more than {MAX_TAGS_PER_FILE} tags")]
+    LimitReached,
+    #[error(transparent)]
+    Database(#[from] sqlx::Error),
+}
+
+impl TagError {
+    /// The status code to respond with when this error is caused by the user.
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            TagError::Database(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            _ => StatusCode::BAD_REQUEST,
+        }
+    }
+}
+
+/// Validates a user supplied tag and returns its normalized (trimmed and lowercase) form.
+pub fn normalize_tag(tag: &str) -> Result<String, TagError> {
+    let tag = tag.trim().to_lowercase();
+
+    if tag.is_empty() {
+        return Err(TagError::Empty);
+    }
+    if tag.len() > MAX_TAG_LEN {
+        return Err(TagError::TooLong);
+    }
+    // Commas are used to separate tags when uploading.
+    if tag.chars().any(|c| c == ',' || c.is_control()) {
+        return Err(TagError::InvalidCharacter);
+    }
+
+    Ok(tag)
+}
+
+/// Parses a comma separated list of tags, removing duplicates.
+pub fn parse_tags(tags: &str) -> Result<Vec<String>, TagError> {
+    let mut parsed = Vec::new();
+    for tag in tags.split(',').filter(|v| !v.trim().is_empty()) {
+        let tag = normalize_tag(tag)?;
+        if !parsed.contains(&tag) {
+            parsed.push(tag);
+        }
+    }
+
+    if parsed.len() > MAX_TAGS_PER_FILE {
+        return Err(TagError::LimitReached);
+    }
+    Ok(parsed)
+}
+
+/// Adds normalized tags to a file. Tags the file already has are ignored.
+pub async fn add_tags(
+    tx: &mut Transaction<'_, Postgres>,
+    file_id: FileId,
+    tags: &[String],
+) -> Result<(), TagError> {
+    sqlx::query!(
+        r#"
+            insert into file_tags (file_id, tag)
+                select $1, unnest($2::text[])
+                on conflict do nothing
+        "#,
+        file_id as FileId,
+        tags,
+    )
+    .execute(&mut **tx)
+    .await?;
+
+    let count = sqlx::query!(
+        r#"select count(*) as "count!" from file_tags where file_id=$1"#,
+        file_id as FileId,
+    )
+    .fetch_one(&mut **tx)
+    .await?
+    .count;
+
+    if count as usize > MAX_TAGS_PER_FILE {
+        return Err(TagError::LimitReached);
+    }
+    Ok(())
+}
+
+/// Lists the tags of a file in alphabetical order.
+pub async fn file_tags(
+    db: impl sqlx::PgExecutor<'_>,
+    file_id: FileId,
+) -> sqlx::Result<Vec<String>> {
+    Ok(sqlx::query!(
+        "select tag from file_tags where file_id=$1 order by tag",
+        file_id as FileId,
+    )
+    .fetch_all(db)
+    .await?
+    .into_iter()
+    .map(|row| row.tag)
+    .collect())
+}
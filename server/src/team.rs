@@ -0,0 +1,494 @@
+use std::{fmt, str::FromStr};
+
+use chrono::{serde::ts_milliseconds, DateTime, Utc};
+use http::StatusCode;
+use num_traits::ToPrimitive;
+use serde::{Deserialize, Serialize};
+use sqlx::{PgExecutor, Postgres, Transaction};
+use thiserror::Error;
+use uuid::Uuid;
+
+use crate::{auth::Authentication, file::FileId};
+
+/// The maximum length of a team name in bytes.
+pub const MAX_TEAM_NAME_LEN: usize = 64;
+
+/// The reasons a team operation may fail.
+#[derive(Debug, Error)]
+pub enum TeamError {
+    #[error("team name must be between 1 and {MAX_TEAM_NAME_LEN} bytes long")]
+    InvalidName,
+    #[error("a team named `{0}` already exists")]
+    Exists(String),
+    #[error("team does not exist")]
+    NotFound,
+    #[error("user does not exist")]
+    UserNotFound,
+    #[error("a team must keep at least one owner")]
+    LastOwner,
+    #[error("the team still owns files, transfer or remove them first")]
+    HasFiles,
+    #[error(transparent)]
+    Database(#[from] sqlx::Error),
+}
+
+impl TeamError {
+    /// The status code to respond with when this error is caused by the user.
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            TeamError::Database(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            TeamError::NotFound | TeamError::UserNotFound => StatusCode::NOT_FOUND,
+            TeamError::Exists(_) | TeamError::LastOwner | TeamError::HasFiles => {
+                StatusCode::CONFLICT
+            }
+            TeamError::InvalidName => StatusCode::BAD_REQUEST,
+        }
+    }
+}
+
+/// The role of a user within a team.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TeamRole {
+    /// Can manage the team and its members, as well as its files.
+    Owner,
+    /// Can upload, replace and delete the team's files.
+    Member,
+    /// Can only list the team's files.
+    Viewer,
+}
+
+impl TeamRole {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TeamRole::Owner => "owner",
+            TeamRole::Member => "member",
+            TeamRole::Viewer => "viewer",
+        }
+    }
+
+    /// Returns true if the role allows managing the team's files.
+    pub fn can_manage_files(self) -> bool {
+        matches!(self, TeamRole::Owner | TeamRole::Member)
+    }
+}
+
+impl fmt::Display for TeamRole {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for TeamRole {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "owner" => Ok(TeamRole::Owner),
+            "member" => Ok(TeamRole::Member),
+            "viewer" => Ok(TeamRole::Viewer),
+            other => Err(format!("unknown team role `{other}`")),
+        }
+    }
+}
+
+/// Information about a team.
+#[derive(Debug, Serialize)]
+pub struct TeamInfo {
+    pub team_id: Uuid,
+    pub name: String,
+    /// The maximum total size of the team's files in bytes, if any.
+    pub quota: Option<u64>,
+    /// The total size of the team's files and their older versions in bytes.
+    pub usage: u64,
+    #[serde(with = "ts_milliseconds")]
+    pub created_at: DateTime<Utc>,
+}
+
+/// A member of a team.
+#[derive(Debug, Serialize)]
+pub struct TeamMember {
+    pub user_id: Uuid,
+    pub username: String,
+    pub role: TeamRole,
+    #[serde(with = "ts_milliseconds")]
+    pub added_at: DateTime<Utc>,
+}
+
+/// Lists teams in alphabetical order. If `member` is set, only the teams that user is a member of
+/// are listed.
+pub async fn list_teams(
+    db: impl PgExecutor<'_>,
+    member: Option<Uuid>,
+) -> sqlx::Result<Vec<TeamInfo>> {
+    Ok(sqlx::query!(
+        r#"
+            select teams.team_id, name, quota, created_at,
+                coalesce(sum(files.file_size), 0) + coalesce(
+                    (
+                        select sum(file_versions.file_size)
+                            from file_versions
+                                join files on files.file_id=file_versions.file_id
+                            where files.team_id=teams.team_id
+                    ),
+                    0
+                ) as "usage"
+                from teams
+                    left outer join files on files.team_id=teams.team_id
+                where $1::uuid is null or exists(
+                    select * from team_members
+                        where team_members.team_id=teams.team_id and user_id=$1
+                )
+                group by teams.team_id
+                order by name
+        "#,
+        member,
+    )
+    .fetch_all(db)
+    .await?
+    .into_iter()
+    .map(|row| TeamInfo {
+        team_id: row.team_id,
+        name: row.name,
+        quota: row.quota.map(|v| v as u64),
+        usage: row.usage.and_then(|v| v.to_u64()).unwrap_or(0),
+        created_at: row.created_at,
+    })
+    .collect())
+}
+
+/// Gets information about a team, or `None` if it doesn't exist.
+pub async fn team_info(db: impl PgExecutor<'_>, team_id: Uuid) -> sqlx::Result<Option<TeamInfo>> {
+    Ok(sqlx::query!(
+        r#"
+            select teams.team_id, name, quota, created_at,
+                coalesce(sum(files.file_size), 0) + coalesce(
+                    (
+                        select sum(file_versions.file_size)
+                            from file_versions
+                                join files on files.file_id=file_versions.file_id
+                            where files.team_id=teams.team_id
+                    ),
+                    0
+                ) as "usage"
+                from teams
+                    left outer join files on files.team_id=teams.team_id
+                where teams.team_id=$1
+                group by teams.team_id
+        "#,
+        team_id,
+    )
+    .fetch_optional(db)
+    .await?
+    .map(|row| TeamInfo {
+        team_id: row.team_id,
+        name: row.name,
+        quota: row.quota.map(|v| v as u64),
+        usage: row.usage.and_then(|v| v.to_u64()).unwrap_or(0),
+        created_at: row.created_at,
+    }))
+}
+
+/// Gets the role of a user within a team, or `None` if the user is not a member.
+pub async fn member_role(
+    db: impl PgExecutor<'_>,
+    team_id: Uuid,
+    user_id: Uuid,
+) -> sqlx::Result<Option<TeamRole>> {
+    let Some(row) = sqlx::query!(
+        "select role from team_members where team_id=$1 and user_id=$2",
+        team_id,
+        user_id,
+    )
+    .fetch_optional(db)
+    .await?
+    else {
+        return Ok(None);
+    };
+
+    row.role
+        .parse()
+        .map(Some)
+        .map_err(|err: String| sqlx::Error::Decode(err.into()))
+}
+
+/// Returns how many bytes can still be added to a team's files before its quota is reached. Older
+/// versions of the files count towards the quota as well. The result is negative if the team is over its quota, and `None` if the team has no quota.
+pub async fn remaining_quota(db: impl PgExecutor<'_>, team_id: Uuid) -> sqlx::Result<Option<i64>> {
+    Ok(sqlx::query!(
+        r#"
+            select quota - (
+                coalesce((select sum(file_size) from files where team_id=$1), 0)
+                    + coalesce(
+                        (
+                            select sum(file_versions.file_size)
+                                from file_versions
+                                    join files on files.file_id=file_versions.file_id
+                                where team_id=$1
+                        ),
+                        0
+                    )
+            )::bigint as "remaining"
+                from teams
+                where team_id=$1
+        "#,
+        team_id,
+    )
+    .fetch_optional(db)
+    .await?
+    .and_then(|row| row.remaining))
+}
+
+/// Checks whether a user may modify a file. This is the case for admins, and either members of the
+/// team that owns the file or, for files without a team, its uploader. Uploaders who left the team
+/// can't modify its files anymore. If `file_name` is set, it must match the name of
+/// the file.
+///
+/// Returns `None` if the file does not exist.
+pub async fn can_modify_file(
+    db: impl PgExecutor<'_>,
+    auth: &Authentication,
+    file_id: FileId,
+    file_name: Option<&str>,
+) -> sqlx::Result<Option<bool>> {
+    let Some(row) = sqlx::query!(
+        r#"
+            select uploader_id, team_id, exists(
+                select * from team_members
+                    where team_members.team_id=files.team_id
+                        and team_members.user_id=$3
+                        and role in ('owner', 'member')
+            ) as "is_team_member!"
+                from files
+                where file_id=$1 and ($2::text is null or file_name=$2)
+                    and (expires_at is null or expires_at > now())
+        "#,
+        file_id as FileId,
+        file_name,
+        auth.user_id,
+    )
+    .fetch_optional(db)
+    .await?
+    else {
+        return Ok(None);
+    };
+
+    let is_owner = match row.team_id {
+        Some(_) => row.is_team_member,
+        None => row.uploader_id == auth.user_id,
+    };
+    Ok(Some(auth.is_admin || is_owner))
+}
+
+/// Validates a user supplied team name and returns its trimmed form.
+fn normalize_team_name(name: &str) -> Result<String, TeamError> {
+    let name = name.trim();
+    if name.is_empty() || name.len() > MAX_TEAM_NAME_LEN || name.chars().any(char::is_control) {
+        return Err(TeamError::InvalidName);
+    }
+    Ok(name.to_string())
+}
+
+/// Creates a new team with `owner` as its only member.
+pub async fn create_team(
+    tx: &mut Transaction<'_, Postgres>,
+    owner: Uuid,
+    name: &str,
+    quota: Option<u64>,
+) -> Result<Uuid, TeamError> {
+    let name = normalize_team_name(name)?;
+
+    let team_id = sqlx::query!(
+        r#"
+            insert into teams (team_id, name, quota)
+                values (gen_random_uuid(), $1, $2)
+                returning team_id
+        "#,
+        name,
+        quota.map(|v| v.min(i64::MAX as u64) as i64),
+    )
+    .fetch_one(&mut **tx)
+    .await
+    .map_err(|err| name_conflict(err, &name))?
+    .team_id;
+
+    sqlx::query!(
+        "insert into team_members (team_id, user_id, role) values ($1, $2, 'owner')",
+        team_id,
+        owner,
+    )
+    .execute(&mut **tx)
+    .await?;
+
+    Ok(team_id)
+}
+
+/// Renames a team and/or changes its quota. A `quota` of `Some(None)` removes the quota.
+pub async fn update_team(
+    db: impl PgExecutor<'_>,
+    team_id: Uuid,
+    name: Option<&str>,
+    quota: Option<Option<u64>>,
+) -> Result<(), TeamError> {
+    let name = name.map(normalize_team_name).transpose()?;
+
+    let result = sqlx::query!(
+        r#"
+            update teams
+                set name=coalesce($2, name),
+                    quota=case when $3 then $4 else quota end
+                where team_id=$1
+        "#,
+        team_id,
+        name,
+        quota.is_some(),
+        quota.flatten().map(|v| v.min(i64::MAX as u64) as i64),
+    )
+    .execute(db)
+    .await
+    .map_err(|err| name_conflict(err, name.as_deref().unwrap_or_default()))?;
+
+    if result.rows_affected() == 0 {
+        return Err(TeamError::NotFound);
+    }
+    Ok(())
+}
+
+/// Removes a team. Teams that still own files can not be removed.
+pub async fn remove_team(db: impl PgExecutor<'_>, team_id: Uuid) -> Result<(), TeamError> {
+    let result = sqlx::query!("delete from teams where team_id=$1", team_id)
+        .execute(db)
+        .await
+        .map_err(|err| match &err {
+            sqlx::Error::Database(db_err) if db_err.is_foreign_key_violation() => {
+                TeamError::HasFiles
+            }
+            _ => TeamError::Database(err),
+        })?;
+
+    if result.rows_affected() == 0 {
+        return Err(TeamError::NotFound);
+    }
+    Ok(())
+}
+
+/// Lists the members of a team, owners first.
+pub async fn list_members(db: impl PgExecutor<'_>, team_id: Uuid) -> sqlx::Result<Vec<TeamMember>> {
+    sqlx::query!(
+        r#"
+            select team_members.user_id, username, role, added_at
+                from team_members
+                    join users on users.user_id=team_members.user_id
+                where team_id=$1
+                order by role='owner' desc, username
+        "#,
+        team_id,
+    )
+    .fetch_all(db)
+    .await?
+    .into_iter()
+    .map(|row| {
+        Ok(TeamMember {
+            user_id: row.user_id,
+            username: row.username,
+            role: row
+                .role
+                .parse()
+                .map_err(|err: String| sqlx::Error::Decode(err.into()))?,
+            added_at: row.added_at,
+        })
+    })
+    .collect()
+}
+
+/// Adds a user to a team or changes their role if they're already a member.
+pub async fn set_member_role(
+    tx: &mut Transaction<'_, Postgres>,
+    team_id: Uuid,
+    user_id: Uuid,
+    role: TeamRole,
+) -> Result<(), TeamError> {
+    sqlx::query!(
+        r#"
+            insert into team_members (team_id, user_id, role)
+                values ($1, $2, $3)
+                on conflict (team_id, user_id) do update set role=excluded.role
+        "#,
+        team_id,
+        user_id,
+        role.as_str(),
+    )
+    .execute(&mut **tx)
+    .await
+    .map_err(|err| match &err {
+        sqlx::Error::Database(db_err) if db_err.is_foreign_key_violation() => {
+            match db_err.constraint() {
+                Some("team_members_team_id_fkey") => TeamError::NotFound,
+                _ => TeamError::UserNotFound,
+            }
+        }
+        _ => TeamError::Database(err),
+    })?;
+
+    check_owners(tx, team_id).await
+}
+
+/// Removes a user from a team. Returns `false` if the user was not a member.
+pub async fn remove_member(
+    tx: &mut Transaction<'_, Postgres>,
+    team_id: Uuid,
+    user_id: Uuid,
+) -> Result<bool, TeamError> {
+    let result = sqlx::query!(
+        "delete from team_members where team_id=$1 and user_id=$2",
+        team_id,
+        user_id,
+    )
+    .execute(&mut **tx)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Ok(false);
+    }
+    check_owners(tx, team_id).await?;
+    Ok(true)
+}
+
+/// Makes sure a team still has at least one owner after its members have been changed.
+async fn check_owners(tx: &mut Transaction<'_, Postgres>, team_id: Uuid) -> Result<(), TeamError> {
+    // Lock the team so that two owners can't demote each other at the same time.
+    sqlx::query!(
+        "select team_id from teams where team_id=$1 for update",
+        team_id
+    )
+    .fetch_optional(&mut **tx)
+    .await?;
+
+    let has_owner = sqlx::query!(
+        r#"
+            select exists(
+                select * from team_members where team_id=$1 and role='owner'
+            ) as "has_owner!"
+        "#,
+        team_id,
+    )
+    .fetch_one(&mut **tx)
+    .await?
+    .has_owner;
+
+    if !has_owner {
+        return Err(TeamError::LastOwner);
+    }
+    Ok(())
+}
+
+/// Converts a database error, reporting a violation of the unique team name constraint as
+/// [TeamError::Exists].
+fn name_conflict(err: sqlx::Error, name: &str) -> TeamError {
+    match &err {
+        sqlx::Error::Database(db_err) if db_err.is_unique_violation() => {
+            TeamError::Exists(name.to_string())
+        }
+        _ => TeamError::Database(err),
+    }
+}
@@ -0,0 +1,117 @@
+use std::{
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use anyhow::Context;
+use axum::{
+    extract::State,
+    http::Uri,
+    response::{IntoResponse, Redirect, Response},
+    Router,
+};
+use axum_server::tls_rustls::RustlsConfig;
+use tokio::{
+    fs,
+    signal::unix::{signal, SignalKind},
+    time::MissedTickBehavior,
+};
+use tracing::{error, info};
+
+use crate::shutdown::Shutdown;
+
+/// Loads the certificate and key used to serve HTTPS.
+pub async fn load_certificate(cert: &Path, key: &Path) -> anyhow::Result<RustlsConfig> {
+    // Another provider may already be installed, which is fine as well.
+    let _ = rustls::crypto::ring::default_provider().install_default();
+
+    RustlsConfig::from_pem_file(cert, key)
+        .await
+        .with_context(|| {
+            format!(
+                "could not load TLS certificate `{}` with key `{}`",
+                cert.display(),
+                key.display()
+            )
+        })
+}
+
+/// Reloads the certificate when the process receives SIGHUP, or when the files have been modified
+/// if `interval` is not zero. New connections use the new certificate, while existing ones keep
+/// using the old one. The current certificate is kept if the new one can't be loaded.
+pub async fn reload_certificate(rustls: RustlsConfig, cert: PathBuf, key: PathBuf, interval: u64) {
+    let mut sighup = match signal(SignalKind::hangup()) {
+        Ok(sighup) => Some(sighup),
+        Err(err) => {
+            error!("Could not listen for SIGHUP: {err}");
+            None
+        }
+    };
+    let mut interval = (interval != 0).then(|| {
+        let mut interval = tokio::time::interval(Duration::from_secs(interval));
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        interval
+    });
+
+    let mut modified = modified_times(&cert, &key).await;
+    loop {
+        tokio::select! {
+            Some(_) = async { sighup.as_mut()?.recv().await } => {
+                info!("Received SIGHUP, reloading TLS certificate.");
+            }
+            Some(_) = async { Some(interval.as_mut()?.tick().await) } => {
+                let current = modified_times(&cert, &key).await;
+                if current == modified {
+                    continue;
+                }
+                info!("TLS certificate changed, reloading it.");
+            }
+            else => return,
+        }
+
+        modified = modified_times(&cert, &key).await;
+        match rustls.reload_from_pem_file(&cert, &key).await {
+            Ok(()) => info!("Reloaded TLS certificate."),
+            Err(err) => {
+                error!("Could not reload TLS certificate, keeping the current one: {err}")
+            }
+        }
+    }
+}
+
+/// The times the certificate and key were last modified, if they can be read.
+async fn modified_times(cert: &Path, key: &Path) -> Option<(SystemTime, SystemTime)> {
+    let cert = fs::metadata(cert).await.ok()?.modified().ok()?;
+    let key = fs::metadata(key).await.ok()?.modified().ok()?;
+    Some((cert, key))
+}
+
+/// Serves plain HTTP on `addr`, permanently redirecting every request to the same path on
+/// `external_url`. Stops accepting connections when the server shuts down.
+pub async fn serve_redirect(
+    addr: SocketAddr,
+    external_url: String,
+    shutdown: Shutdown,
+) -> anyhow::Result<()> {
+    let app = Router::new()
+        .fallback(redirect_to_https)
+        .with_state(external_url);
+    let listener = tokio::net::TcpListener::bind(addr)
+        .await
+        .context("could not bind redirect address")?;
+    info!("Redirecting to HTTPS from `http://{addr}`.");
+    tokio::spawn(async move {
+        let server = axum::serve(listener, app)
+            .with_graceful_shutdown(async move { shutdown.started().await });
+        if let Err(err) = server.await {
+            error!("Redirect server stopped: {err}");
+        }
+    });
+    Ok(())
+}
+
+async fn redirect_to_https(State(external_url): State<String>, uri: Uri) -> Response {
+    let path = uri.path_and_query().map_or("/", |v| v.as_str());
+    Redirect::permanent(&format!("{external_url}{path}")).into_response()
+}
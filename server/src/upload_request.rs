@@ -0,0 +1,160 @@
+use chrono::{
+    serde::{ts_milliseconds, ts_milliseconds_option},
+    DateTime, Utc,
+};
+use serde::Serialize;
+use sqlx::PgExecutor;
+use uuid::Uuid;
+
+/// A link that allows people without an account to upload files to a user.
+#[derive(Debug, Serialize)]
+pub struct UploadRequest {
+    /// The random token used in the upload link.
+    pub request_id: String,
+    pub owner_id: Uuid,
+    pub owner_name: String,
+    /// Shown to the people uploading, such as what files are expected.
+    pub description: Option<String>,
+    /// The folder uploaded files are put in.
+    pub folder_id: Option<Uuid>,
+    #[serde(skip)]
+    pub password_hash: Option<String>,
+    pub has_password: bool,
+    /// The maximum amount of files that can be uploaded using the link, if any.
+    pub max_files: Option<u32>,
+    /// The maximum size of a single uploaded file in bytes, if any.
+    pub max_file_size: Option<u64>,
+    /// The amount of files that have been uploaded using the link.
+    pub file_count: u32,
+    /// The time after which the link can no longer be used, if any.
+    #[serde(with = "ts_milliseconds_option")]
+    pub expires_at: Option<DateTime<Utc>>,
+    #[serde(with = "ts_milliseconds")]
+    pub created_at: DateTime<Utc>,
+}
+
+impl UploadRequest {
+    /// Returns true if files can still be uploaded using the link.
+    pub fn is_open(&self) -> bool {
+        self.expires_at.is_none_or(|v| v > Utc::now())
+            && self.max_files.is_none_or(|v| self.file_count < v)
+    }
+
+    /// Returns how many more files can be uploaded using the link, if there is a limit.
+    pub fn remaining_files(&self) -> Option<u32> {
+        self.max_files.map(|v| v.saturating_sub(self.file_count))
+    }
+}
+
+/// Generates a new random request ID. With 128 random bits, the links can't be guessed.
+pub fn random_request_id() -> String {
+    hex::encode(rand::random::<[u8; 16]>())
+}
+
+/// Builds the link that is shared with the people that should upload files.
+pub fn upload_link(external_url: &str, request_id: &str) -> String {
+    format!("{external_url}/upload/{request_id}")
+}
+
+/// Gets an upload request, or `None` if it doesn't exist.
+pub async fn upload_request(
+    db: impl PgExecutor<'_>,
+    request_id: &str,
+) -> sqlx::Result<Option<UploadRequest>> {
+    Ok(sqlx::query!(
+        r#"
+            select request_id, owner_id, username, description, folder_id,
+                    upload_requests.password_hash, max_files, max_file_size, file_count, expires_at,
+                    upload_requests.created_at
+                from upload_requests
+                    join users on users.user_id=upload_requests.owner_id
+                where request_id=$1
+        "#,
+        request_id,
+    )
+    .fetch_optional(db)
+    .await?
+    .map(|row| UploadRequest {
+        request_id: row.request_id,
+        owner_id: row.owner_id,
+        owner_name: row.username,
+        description: row.description,
+        folder_id: row.folder_id,
+        has_password: row.password_hash.is_some(),
+        password_hash: row.password_hash,
+        max_files: row.max_files.map(|v| v as u32),
+        max_file_size: row.max_file_size.map(|v| v as u64),
+        file_count: row.file_count as u32,
+        expires_at: row.expires_at,
+        created_at: row.created_at,
+    }))
+}
+
+/// Lists the upload requests of a user, newest first.
+pub async fn list_upload_requests(
+    db: impl PgExecutor<'_>,
+    owner: Uuid,
+) -> sqlx::Result<Vec<UploadRequest>> {
+    Ok(sqlx::query!(
+        r#"
+            select request_id, owner_id, username, description, folder_id,
+                    upload_requests.password_hash, max_files, max_file_size, file_count, expires_at,
+                    upload_requests.created_at
+                from upload_requests
+                    join users on users.user_id=upload_requests.owner_id
+                where owner_id=$1
+                order by upload_requests.created_at desc
+        "#,
+        owner,
+    )
+    .fetch_all(db)
+    .await?
+    .into_iter()
+    .map(|row| UploadRequest {
+        request_id: row.request_id,
+        owner_id: row.owner_id,
+        owner_name: row.username,
+        description: row.description,
+        folder_id: row.folder_id,
+        has_password: row.password_hash.is_some(),
+        password_hash: row.password_hash,
+        max_files: row.max_files.map(|v| v as u32),
+        max_file_size: row.max_file_size.map(|v| v as u64),
+        file_count: row.file_count as u32,
+        expires_at: row.expires_at,
+        created_at: row.created_at,
+    })
+    .collect())
+}
+
+/// Claims one of the files that can be uploaded using a link. Returns `false` if the link has
+/// expired or no more files can be uploaded.
+///
+/// The claim is made before the upload starts, so concurrent uploads can't exceed the limit. If
+/// the upload fails, the claim should be given back using [release_upload].
+pub async fn claim_upload(db: impl PgExecutor<'_>, request_id: &str) -> sqlx::Result<bool> {
+    let result = sqlx::query!(
+        r#"
+            update upload_requests
+                set file_count=file_count + 1
+                where request_id=$1
+                    and (max_files is null or file_count < max_files)
+                    and (expires_at is null or expires_at > now())
+        "#,
+        request_id,
+    )
+    .execute(db)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Gives back a claim made using [claim_upload] after an upload failed.
+pub async fn release_upload(db: impl PgExecutor<'_>, request_id: &str) -> sqlx::Result<()> {
+    sqlx::query!(
+        "update upload_requests set file_count=file_count - 1 where request_id=$1 and file_count > 0",
+        request_id,
+    )
+    .execute(db)
+    .await?;
+    Ok(())
+}
@@ -0,0 +1,227 @@
+use anyhow::Context;
+use argon2::{password_hash::SaltString, Argon2, PasswordHasher};
+use rand::rngs::OsRng;
+use sqlx::{PgExecutor, Postgres, Transaction};
+use tracing::error;
+use uuid::Uuid;
+
+use crate::file::{FileId, FileStore};
+
+/// Hashes a password for storing it in the database.
+///
+/// Hashing is done in a separate blocking thread as it is an expensive CPU bound operation.
+pub async fn hash_password(password: String) -> anyhow::Result<String> {
+    tokio::task::spawn_blocking(move || {
+        let salt = SaltString::generate(&mut OsRng);
+        Argon2::default()
+            .hash_password(password.as_bytes(), &salt)
+            .map(|hash| hash.to_string())
+    })
+    .await
+    .context("could not join hasher thread")?
+    .context("failed to hash password")
+}
+
+/// Creates a user with an already hashed password. Returns `None` if the username is taken.
+pub async fn create_user(
+    db: impl PgExecutor<'_>,
+    username: &str,
+    password_hash: &str,
+    is_admin: bool,
+) -> sqlx::Result<Option<Uuid>> {
+    let row = sqlx::query!(
+        r#"
+            insert into users (user_id, username, password_hash, is_admin)
+                values (gen_random_uuid(), $1, $2, $3)
+                on conflict (username) do nothing
+                returning user_id
+        "#,
+        username,
+        password_hash,
+        is_admin,
+    )
+    .fetch_optional(db)
+    .await?;
+
+    Ok(row.map(|row| row.user_id))
+}
+
+/// Returns true if at least one user has administrator permissions.
+pub async fn admin_exists(db: impl PgExecutor<'_>) -> sqlx::Result<bool> {
+    sqlx::query_scalar!(r#"select exists(select * from users where is_admin) as "exists!""#)
+        .fetch_one(db)
+        .await
+}
+
+/// Returns how many bytes can still be added to the files a user owns outside of teams before
+/// `quota` is reached. Older versions of the files count towards the quota as well. The result is negative if the user is over the quota, and `None` if there
+/// is no quota.
+pub async fn remaining_quota(
+    db: impl PgExecutor<'_>,
+    user_id: Uuid,
+    quota: Option<u64>,
+) -> sqlx::Result<Option<i64>> {
+    let Some(quota) = quota else {
+        return Ok(None);
+    };
+    let used = sqlx::query_scalar!(
+        r#"
+            select (
+                coalesce(sum(files.file_size), 0)
+                    + coalesce(
+                        (
+                            select sum(file_versions.file_size)
+                                from file_versions
+                                    join files on files.file_id=file_versions.file_id
+                                where uploader_id=$1 and team_id is null
+                        ),
+                        0
+                    )
+            )::bigint as "used!"
+                from files
+                where uploader_id=$1 and team_id is null
+        "#,
+        user_id,
+    )
+    .fetch_one(db)
+    .await?;
+    Ok(Some(i64::try_from(quota).unwrap_or(i64::MAX) - used))
+}
+
+/// Transfers the personal files of a user to another user, optionally putting them in a team.
+/// Short links the user created for those files are kept as well. Files owned by a team stay in
+/// that team, see [transfer_team_files].
+pub async fn transfer_files(
+    tx: &mut Transaction<'_, Postgres>,
+    user_id: Uuid,
+    new_owner: Uuid,
+    team_id: Option<Uuid>,
+) -> sqlx::Result<()> {
+    sqlx::query!(
+        r#"
+            update slugs
+                set created_by=$2
+                where created_by=$1
+                    and file_id in (
+                        select file_id from files where uploader_id=$1 and team_id is null
+                    )
+        "#,
+        user_id,
+        new_owner,
+    )
+    .execute(&mut **tx)
+    .await?;
+
+    // Folders belong to the user being removed, so the files end up at the top level.
+    sqlx::query!(
+        r#"
+            update files
+                set uploader_id=$2, team_id=$3, folder_id=null
+                where uploader_id=$1 and team_id is null
+        "#,
+        user_id,
+        new_owner,
+        team_id,
+    )
+    .execute(&mut **tx)
+    .await?;
+
+    Ok(())
+}
+
+/// Transfers the files a user uploaded to a team to another member of that team, preferring the
+/// longest standing owner, so the team keeps its files when the user is removed. Short links the
+/// user created for those files are transferred as well.
+///
+/// Returns false if a team that owns such files has no other member, in which case its files are
+/// left with the user.
+pub async fn transfer_team_files(
+    tx: &mut Transaction<'_, Postgres>,
+    user_id: Uuid,
+) -> sqlx::Result<bool> {
+    sqlx::query!(
+        r#"
+            update files
+                set uploader_id=(
+                    select user_id
+                        from team_members
+                        where team_members.team_id=files.team_id and user_id!=$1
+                        order by role='owner' desc, role='member' desc, added_at
+                        limit 1
+                ), folder_id=null
+                where uploader_id=$1 and team_id is not null
+                    and exists(
+                        select * from team_members
+                            where team_members.team_id=files.team_id and user_id!=$1
+                    )
+        "#,
+        user_id,
+    )
+    .execute(&mut **tx)
+    .await?;
+
+    sqlx::query!(
+        r#"
+            update slugs
+                set created_by=files.uploader_id
+                from files
+                where slugs.file_id=files.file_id
+                    and slugs.created_by=$1
+                    and files.team_id is not null
+                    and files.uploader_id!=$1
+        "#,
+        user_id,
+    )
+    .execute(&mut **tx)
+    .await?;
+
+    let left = sqlx::query_scalar!(
+        r#"select exists(select * from files where uploader_id=$1) as "exists!""#,
+        user_id,
+    )
+    .fetch_one(&mut **tx)
+    .await?;
+    Ok(!left)
+}
+
+/// Removes the personal files of a user, returning the files that were removed. Their content
+/// must be removed using [remove_contents] once the transaction has been committed.
+pub async fn remove_files(
+    tx: &mut Transaction<'_, Postgres>,
+    user_id: Uuid,
+) -> sqlx::Result<Vec<FileId>> {
+    sqlx::query_scalar!(
+        r#"
+            delete from files
+                where uploader_id=$1 and team_id is null
+                returning file_id as "file_id: FileId"
+        "#,
+        user_id,
+    )
+    .fetch_all(&mut **tx)
+    .await
+}
+
+/// Removes the content of files that were removed from the database.
+pub async fn remove_contents(store: &FileStore, files: &[FileId]) {
+    for file_id in files {
+        if let Err(err) = store.remove(*file_id).await {
+            error!(
+                ?file_id,
+                "Could not remove content of removed file: {err:?}"
+            );
+        }
+    }
+}
+
+/// Removes a user. Invites the user created are removed as well, their files must have been
+/// transferred or removed already.
+pub async fn delete_user(tx: &mut Transaction<'_, Postgres>, user_id: Uuid) -> sqlx::Result<()> {
+    sqlx::query!("delete from invites where created_by=$1", user_id)
+        .execute(&mut **tx)
+        .await?;
+    sqlx::query!("delete from users where user_id=$1", user_id)
+        .execute(&mut **tx)
+        .await?;
+    Ok(())
+}